@@ -1,13 +1,15 @@
 use serde::Deserialize;
 use serde::Serialize;
 use home_config::HomeConfig;
+use std::collections::BTreeMap;
+use std::future::Future;
 use std::fs::OpenOptions;
 use std::io::Write;
 use std::path::Path;
 use std::io;
 use std::fs;
 
-use crate::nodex::errors::NodeXError;
+use crate::nodex::{errors::NodeXError, cipher::sealer::Sealer, extension::{cipher::Cipher, manager::extension_manager}, keyring::signing_key::KeyType, runtime::random::Random, sidetree::payload::{OperationPayload, ServiceEndpoint}};
 
 pub struct KeyPair {
     pub public_key: Vec<u8>,
@@ -20,12 +22,15 @@ struct KeyPairConfig {
     secret_key: String,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 struct KeyPairsConfig {
     sign: Option<KeyPairConfig>,
     update: Option<KeyPairConfig>,
     recover: Option<KeyPairConfig>,
     encrypt: Option<KeyPairConfig>,
+    // NOTE: the curve of the stored signing and key agreement keys, set when the key ring is saved
+    #[serde(skip_serializing_if = "Option::is_none")]
+    key_type: Option<KeyType>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -39,10 +44,13 @@ pub struct TRNGExtensionConfig {
     pub read: Extension,
 }
 
+// NOTE: generate creates the signing and the P-256 key agreement keys in the secure element and returns their public keys,
+//       no secret key is written to the extension
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct SecureKeystoreExtensionConfig {
-    pub write: Extension,
     pub read: Extension,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generate: Option<Extension>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -51,50 +59,301 @@ pub struct CipherExtensionConfig {
     pub decrypt: Extension,
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SecureSignerExtensionConfig {
+    pub sign: Extension,
+    pub ecdh: Extension,
+}
+
+// NOTE: runs every extension library in a helper process, timeout is in milliseconds
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ExtensionIsolationConfig {
+    pub enabled: bool,
+    pub timeout: u64,
+}
+
+impl Default for ExtensionIsolationConfig {
+    fn default() -> Self {
+        ExtensionIsolationConfig {
+            enabled: false,
+            timeout: 5000,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ExtensionsConfig {
     pub trng: Option<TRNGExtensionConfig>,
     pub secure_keystore: Option<SecureKeystoreExtensionConfig>,
     pub cipher: Option<CipherExtensionConfig>,
+    pub secure_signer: Option<SecureSignerExtensionConfig>,
+    #[serde(default)]
+    pub isolation: ExtensionIsolationConfig,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+// NOTE: the backend holding the key pairs of the key ring
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub enum KeyStoreBackend {
+    #[serde(rename = "file")]
+    File,
+
+    // NOTE: an HSM or a smart card. The backend is import-only: the keys are derived from the mnemonic on the host
+    //       and then imported. secp256k1 and P-256 keys are not extractable after the import. Ed25519 and X25519 keys
+    //       are kept as private DATA objects, readable by anyone logged in with the PIN, and handed back to the host
+    #[serde(rename = "pkcs11")]
+    Pkcs11,
+
+    #[serde(rename = "extension")]
+    Extension,
+}
+
+// NOTE: the module is the PKCS#11 library of the vendor (e.g. libsofthsm2.so), the user PIN is NODEX_PKCS11_PIN
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Pkcs11Config {
+    pub module: String,
+    pub token_label: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 #[serde(default)]
-pub struct ConfigRoot {
+pub struct KeyStoreConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backend: Option<KeyStoreBackend>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pkcs11: Option<Pkcs11Config>,
+}
+
+impl KeyStoreConfig {
+    // NOTE: without a backend, a configured secure_keystore extension is used as before, otherwise config.json
+    fn backend(&self, has_extension: bool) -> KeyStoreBackend {
+        match self.backend {
+            Some(v) => v,
+            None if has_extension => KeyStoreBackend::Extension,
+            None => KeyStoreBackend::File,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ResolverConfig {
+    pub cache_ttl: u64,
+    pub negative_cache_ttl: u64,
+    pub offline_fallback: bool,
+    // NOTE: the oldest cached document served while the resolver is unreachable
+    pub max_stale: u64,
+}
+
+impl Default for ResolverConfig {
+    fn default() -> Self {
+        ResolverConfig {
+            cache_ttl: 300,
+            negative_cache_ttl: 30,
+            offline_fallback: true,
+            max_stale: 3600,
+        }
+    }
+}
+
+// NOTE: the method and network of the DIDs created by the agent
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SidetreeConfig {
+    pub did_prefix: String,
+}
+
+impl Default for SidetreeConfig {
+    fn default() -> Self {
+        SidetreeConfig {
+            did_prefix: "did:nodex:test".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct KeyRotationConfig {
+    // NOTE: seconds between rotations of the signing key, 0 disables the schedule
+    pub interval: u64,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+struct KeyIndexesConfig {
+    sign: u32,
+    update: u32,
+    recovery: u32,
+    rotated_at: Option<i64>,
+}
+
+// NOTE: the key state an operation replaced, kept until the operation is published
+//       the keys are derived from the mnemonic of the profile again, so only their indexes are kept
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct KeyCheckpointConfig {
+    operation_id: String,
+    key_indexes: KeyIndexesConfig,
+    services: Vec<ServiceEndpoint>,
+}
+
+pub struct KeyCheckpoint {
+    pub did: Option<String>,
+    pub sign_index: u32,
+    pub update_index: u32,
+    pub recovery_index: u32,
+    pub rotated_at: Option<i64>,
+    pub services: Vec<ServiceEndpoint>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub enum EncryptionMode {
+    #[default]
+    #[serde(rename = "none")]
+    None,
+
+    // NOTE: the key is derived with scrypt from NODEX_CONFIG_PASSPHRASE
+    #[serde(rename = "passphrase")]
+    Passphrase,
+
+    // NOTE: the key is NODEX_CONFIG_KEY (hex, 32 bytes)
+    #[serde(rename = "env")]
+    Env,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct EncryptionConfig {
+    pub mode: EncryptionMode,
+    pub scrypt_log_n: u8,
+    salt: Option<String>,
+    // NOTE: a sealed known value, a wrong key is detected before any secret is read
+    check: Option<String>,
+}
+
+impl Default for EncryptionConfig {
+    fn default() -> Self {
+        EncryptionConfig {
+            mode: EncryptionMode::None,
+            scrypt_log_n: 15,
+            salt: None,
+            check: None,
+        }
+    }
+}
+
+// NOTE: the identity of a profile, the default profile is kept at the top level of config.json
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+struct ProfileConfig {
     did: Option<String>,
     mnemonic: Option<String>,
+    // NOTE: the BIP39 passphrase is written sealed only, with the config in plain text it is kept by the running
+    //       process and has to be given again (restore) after a restart before new keys are derived
+    #[serde(skip_serializing_if = "Option::is_none")]
+    passphrase: Option<String>,
+    #[serde(skip)]
+    unsaved_passphrase: Option<String>,
+    has_passphrase: bool,
     key_pairs: KeyPairsConfig,
-    extensions: ExtensionsConfig,
+    // NOTE: the curve of the signing key, the update and recovery keys of Sidetree are always secp256k1
+    //       with p256 the key agreement key is P-256 too (ECDH-ES), so that a TPM can hold every DIDComm key
+    sign_key_type: KeyType,
+    key_indexes: KeyIndexesConfig,
+    services: Vec<ServiceEndpoint>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    checkpoints: Vec<KeyCheckpointConfig>,
+    // NOTE: the keystore slot of the profile, it overrides extensions.secure_keystore
+    #[serde(skip_serializing_if = "Option::is_none")]
+    secure_keystore: Option<SecureKeystoreExtensionConfig>,
     is_initialized: bool,
+    is_deactivated: bool,
+}
+
+impl ProfileConfig {
+    // NOTE: sign_key_type only chooses the curve of a new key ring, a key ring saved before the curve was stored
+    //       was created with it
+    fn key_pair_type(&self) -> KeyType {
+        self.key_pairs.key_type.unwrap_or(self.sign_key_type)
+    }
+
+    fn secrets_mut(&mut self) -> Vec<&mut String> {
+        let mut secrets: Vec<&mut String> = vec![];
+
+        for key_pair in vec![ self.key_pairs.sign.as_mut(), self.key_pairs.update.as_mut(), self.key_pairs.recover.as_mut(), self.key_pairs.encrypt.as_mut() ].into_iter().flatten() {
+            secrets.push(&mut key_pair.secret_key);
+        }
+
+        secrets.extend(self.mnemonic.as_mut());
+        secrets.extend(self.passphrase.as_mut());
+
+        secrets
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ConfigRoot {
+    #[serde(flatten)]
+    profile: ProfileConfig,
+    profiles: BTreeMap<String, ProfileConfig>,
+    extensions: ExtensionsConfig,
+    keystore: KeyStoreConfig,
+    resolver: ResolverConfig,
+    sidetree: SidetreeConfig,
+    key_rotation: KeyRotationConfig,
+    encryption: EncryptionConfig,
     schema_version: u8,
 }
 
 impl Default for ConfigRoot {
     fn default() -> Self {
         ConfigRoot {
-            did: None,
-            mnemonic: None,
-            key_pairs: KeyPairsConfig {
-                sign: None,
-                update: None,
-                recover: None,
-                encrypt: None,
-            },
+            profile: ProfileConfig::default(),
+            profiles: BTreeMap::new(),
             extensions: ExtensionsConfig {
                 trng: None,
                 secure_keystore: None,
                 cipher: None,
+                secure_signer: None,
+                isolation: ExtensionIsolationConfig::default(),
             },
-            is_initialized: false,
+            keystore: KeyStoreConfig::default(),
+            resolver: ResolverConfig::default(),
+            sidetree: SidetreeConfig::default(),
+            key_rotation: KeyRotationConfig::default(),
+            encryption: EncryptionConfig::default(),
             schema_version: 1,
         }
     }
 }
 
+pub const DEFAULT_PROFILE: &str = "default";
+
+tokio::task_local! {
+    static PROFILE: String;
+}
+
+// NOTE: the profile selected for the running request, AppConfig reads and writes the identity of this profile
+pub fn current_profile() -> String {
+    match PROFILE.try_with(|v| v.clone()) {
+        Ok(v) => v,
+        Err(_) => DEFAULT_PROFILE.to_string(),
+    }
+}
+
+pub async fn with_profile<F: Future>(name: &str, f: F) -> F::Output {
+    PROFILE.scope(name.to_string(), f).await
+}
+
+pub fn is_valid_profile_name(name: &str) -> bool {
+    !name.is_empty() && name.len() <= 32 && name.chars().all(|v| v.is_ascii_alphanumeric() || v == '-' || v == '_')
+}
+
 #[derive(Debug)]
 pub struct AppConfig {
     config: HomeConfig,
     root: ConfigRoot,
+    cipher: Cipher,
 }
 
 impl AppConfig {
@@ -131,12 +390,162 @@ impl AppConfig {
             };
         }
 
-        let root = match config.json::<ConfigRoot>() {
+        let mut root = match config.json::<ConfigRoot>() {
             Ok(v) => v,
             Err(_) => panic!(),
         };
 
-        AppConfig { root, config }
+        let sealer = match Self::sealer(&mut root.encryption) {
+            Ok(v) => v,
+            Err(_) => {
+                log::error!("unable to load the key of config.json, check NODEX_CONFIG_PASSPHRASE or NODEX_CONFIG_KEY");
+                panic!()
+            }
+        };
+
+        let mut app_config = AppConfig { root, config, cipher: Cipher::default() };
+
+        extension_manager().configure(&app_config.root.extensions.isolation);
+
+        // NOTE: the cipher extension seals secrets when it is configured, otherwise the sealer does
+        app_config.cipher = Cipher::new(app_config.load_cipher_encrypt_sig(), app_config.load_cipher_decrypt_sig(), sealer);
+
+        let checked = match Self::check(&app_config.cipher, &mut app_config.root.encryption) {
+            Ok(v) => v,
+            Err(_) => {
+                log::error!("unable to unseal config.json with the configured key or cipher extension");
+                panic!()
+            }
+        };
+
+        if app_config.migrate() || checked {
+            log::info!("sealed the secrets of config.json");
+
+            match app_config.write() {
+                Ok(_) => {},
+                Err(_) => panic!()
+            };
+        }
+
+        app_config
+    }
+
+    fn sealer(encryption: &mut EncryptionConfig) -> Result<Option<Sealer>, NodeXError> {
+        let secret = match encryption.mode {
+            EncryptionMode::None => None,
+            EncryptionMode::Passphrase => std::env::var("NODEX_CONFIG_PASSPHRASE").ok(),
+            EncryptionMode::Env => std::env::var("NODEX_CONFIG_KEY").ok(),
+        };
+
+        Self::sealer_with(encryption, secret.as_deref())
+    }
+
+    // NOTE: the secret is the passphrase or the hex encoded key of the mode
+    fn sealer_with(encryption: &mut EncryptionConfig, secret: Option<&str>) -> Result<Option<Sealer>, NodeXError> {
+        let sealer = match encryption.mode {
+            EncryptionMode::None => return Ok(None),
+            EncryptionMode::Passphrase => {
+                let passphrase = match secret {
+                    Some(v) => v,
+                    None => return Err(NodeXError{})
+                };
+
+                if encryption.salt.is_none() {
+                    encryption.salt = match Random::bytes(&16) {
+                        Ok(v) => Some(hex::encode(v)),
+                        Err(_) => return Err(NodeXError{})
+                    };
+                }
+
+                let salt = match hex::decode(encryption.salt.clone().unwrap_or_default()) {
+                    Ok(v) => v,
+                    Err(_) => return Err(NodeXError{})
+                };
+
+                Sealer::from_passphrase(passphrase, &salt, encryption.scrypt_log_n)
+            },
+            EncryptionMode::Env => {
+                match secret {
+                    Some(v) => Sealer::from_hex(v),
+                    None => return Err(NodeXError{})
+                }
+            },
+        };
+
+        match sealer {
+            Ok(v) => Ok(Some(v)),
+            Err(_) => Err(NodeXError{})
+        }
+    }
+
+    // NOTE: verify the key check, returns whether a new one was created
+    fn check(cipher: &Cipher, encryption: &mut EncryptionConfig) -> Result<bool, NodeXError> {
+        if !cipher.is_enabled() {
+            return Ok(false)
+        }
+
+        match &encryption.check {
+            Some(v) => {
+                match cipher.decrypt(v) {
+                    Ok(_) => Ok(false),
+                    Err(_) => Err(NodeXError{})
+                }
+            },
+            None => {
+                encryption.check = match cipher.encrypt(b"nodex") {
+                    Ok(v) => Some(v),
+                    Err(_) => return Err(NodeXError{})
+                };
+
+                Ok(true)
+            },
+        }
+    }
+
+    // NOTE: seal the secrets of a config written in plain text, returns whether anything changed
+    fn migrate(&mut self) -> bool {
+        if !self.cipher.is_enabled() {
+            return false
+        }
+
+        let mut migrated = false;
+
+        for profile in std::iter::once(&mut self.root.profile).chain(self.root.profiles.values_mut()) {
+            for secret in profile.secrets_mut() {
+                if Cipher::is_sealed(secret) {
+                    continue;
+                }
+
+                match self.cipher.encrypt(secret.as_bytes()) {
+                    Ok(v) => *secret = v,
+                    Err(_) => panic!()
+                };
+
+                migrated = true;
+            }
+        }
+
+        migrated
+    }
+
+    fn seal(&self, value: &str) -> Option<String> {
+        if !self.cipher.is_enabled() {
+            return Some(value.to_string())
+        }
+
+        self.cipher.encrypt(value.as_bytes()).ok()
+    }
+
+    // NOTE: values written before the config was sealed are read as they are
+    fn unseal(&self, value: &str) -> Option<String> {
+        if !Cipher::is_sealed(value) {
+            return Some(value.to_string())
+        }
+
+        match self.cipher.decrypt(value) {
+            Ok(v) => String::from_utf8(v).ok(),
+            Err(_) => None,
+        }
     }
 
     pub fn write(&self) -> Result<(), NodeXError> {
@@ -162,6 +571,76 @@ impl AppConfig {
         }
     }
 
+    fn profile(&self) -> &ProfileConfig {
+        let name = current_profile();
+
+        if name == DEFAULT_PROFILE {
+            return &self.root.profile
+        }
+
+        // NOTE: an unknown profile reads as an empty one
+        match self.root.profiles.get(&name) {
+            Some(v) => v,
+            None => {
+                static EMPTY: std::sync::OnceLock<ProfileConfig> = std::sync::OnceLock::new();
+                EMPTY.get_or_init(ProfileConfig::default)
+            }
+        }
+    }
+
+    // NOTE: a profile is created by add_profile (or in config.json) only, the server refuses unknown profiles before
+    //       a handler writes to them, a profile removed meanwhile is an error
+    fn profile_mut(&mut self) -> Result<&mut ProfileConfig, NodeXError> {
+        let name = current_profile();
+
+        if name == DEFAULT_PROFILE {
+            return Ok(&mut self.root.profile)
+        }
+
+        match self.root.profiles.get_mut(&name) {
+            Some(v) => Ok(v),
+            None => {
+                log::error!("unknown profile: {}", name);
+                Err(NodeXError{})
+            },
+        }
+    }
+
+    // NOTE: PROFILES
+    pub fn get_profile_names(&self) -> Vec<String> {
+        let mut names = vec![ DEFAULT_PROFILE.to_string() ];
+        names.extend(self.root.profiles.keys().cloned());
+        names
+    }
+
+    pub fn has_profile(&self, name: &str) -> bool {
+        name == DEFAULT_PROFILE || self.root.profiles.contains_key(name)
+    }
+
+    pub fn add_profile(&mut self, name: &str) {
+        if self.has_profile(name) {
+            return
+        }
+
+        self.root.profiles.insert(name.to_string(), ProfileConfig::default());
+        match self.write() {
+            Ok(_) => {},
+            Err(_) => panic!()
+        }
+    }
+
+    // NOTE: the profile holding the identity, long-form and short-form DIDs are the same identity
+    pub fn find_profile(&self, did: &str) -> Option<String> {
+        let short_form = OperationPayload::did_short_form(did).ok()?;
+        let matches = |v: &ProfileConfig| v.did.as_ref().and_then(|v| OperationPayload::did_short_form(v).ok()).as_ref() == Some(&short_form);
+
+        if matches(&self.root.profile) {
+            return Some(DEFAULT_PROFILE.to_string())
+        }
+
+        self.root.profiles.iter().find(|(_, v)| matches(v)).map(|(k, _)| k.clone())
+    }
+
     // NOTE: trng - read
     pub fn load_trng_read_sig(&self) -> Option<Extension> {
         match self.root.extensions.trng.clone() {
@@ -172,19 +651,19 @@ impl AppConfig {
         }
     }
 
-    // NOTE: secure_keystore - write
-    pub fn load_secure_keystore_write_sig(&self) -> Option<Extension> {
-        match self.root.extensions.secure_keystore.clone() {
-            Some(v) => {
-                Some(v.write)
-            },
+    // NOTE: the extension keys its slots by the key type only, so extensions.secure_keystore is the slot of the default
+    //       profile and every other profile needs a slot of its own, without one its keys are refused
+    fn secure_keystore(&self) -> Option<SecureKeystoreExtensionConfig> {
+        match &self.profile().secure_keystore {
+            Some(v) => Some(v.clone()),
+            None if current_profile() == DEFAULT_PROFILE => self.root.extensions.secure_keystore.clone(),
             None => None,
         }
     }
 
     // NOTE: secure_keystore - read
     pub fn load_secure_keystore_read_sig(&self) -> Option<Extension> {
-        match self.root.extensions.secure_keystore.clone() {
+        match self.secure_keystore() {
             Some(v) => {
                 Some(v.read)
             },
@@ -192,8 +671,25 @@ impl AppConfig {
         }
     }
 
+    // NOTE: secure_keystore - generate
+    pub fn load_secure_keystore_generate_sig(&self) -> Option<Extension> {
+        match self.secure_keystore() {
+            Some(v) => v.generate,
+            None => None,
+        }
+    }
+
+    // NOTE: keystore - backend
+    pub fn load_key_store_backend(&self) -> KeyStoreBackend {
+        self.root.keystore.backend(self.root.extensions.secure_keystore.is_some() || self.profile().secure_keystore.is_some())
+    }
+
+    // NOTE: keystore - pkcs11
+    pub fn load_pkcs11_config(&self) -> Option<Pkcs11Config> {
+        self.root.keystore.pkcs11.clone()
+    }
+
     // NOTE: cipher - encrypt
-    #[allow(dead_code)]
     pub fn load_cipher_encrypt_sig(&self) -> Option<Extension> {
         match self.root.extensions.cipher.clone() {
             Some(v) => {
@@ -204,7 +700,6 @@ impl AppConfig {
     }
 
     // NOTE: cipher - decrypt
-    #[allow(dead_code)]
     pub fn load_cipher_decrypt_sig(&self) -> Option<Extension> {
         match self.root.extensions.cipher.clone() {
             Some(v) => {
@@ -214,15 +709,96 @@ impl AppConfig {
         }
     }
 
+    // NOTE: every configured extension, named "<extension>.<function>"
+    pub fn get_extensions(&self) -> Vec<(String, Extension)> {
+        let extensions = &self.root.extensions;
+        let mut list: Vec<(String, Extension)> = vec![];
+
+        if let Some(v) = &extensions.trng {
+            list.push(("trng.read".to_string(), v.read.clone()));
+        }
+
+        let keystores = extensions.secure_keystore.iter()
+            .chain(std::iter::once(&self.root.profile).chain(self.root.profiles.values()).filter_map(|v| v.secure_keystore.as_ref()));
+
+        for v in keystores {
+            list.push(("secure_keystore.read".to_string(), v.read.clone()));
+
+            if let Some(generate) = &v.generate {
+                list.push(("secure_keystore.generate".to_string(), generate.clone()));
+            }
+        }
+
+        if let Some(v) = &extensions.cipher {
+            list.push(("cipher.encrypt".to_string(), v.encrypt.clone()));
+            list.push(("cipher.decrypt".to_string(), v.decrypt.clone()));
+        }
+
+        if let Some(v) = &extensions.secure_signer {
+            list.push(("secure_signer.sign".to_string(), v.sign.clone()));
+            list.push(("secure_signer.ecdh".to_string(), v.ecdh.clone()));
+        }
+
+        list
+    }
+
+    // NOTE: secure_signer - sign
+    pub fn load_secure_signer_sign_sig(&self) -> Option<Extension> {
+        match self.root.extensions.secure_signer.clone() {
+            Some(v) => {
+                Some(v.sign)
+            },
+            None => None,
+        }
+    }
+
+    // NOTE: secure_signer - ecdh
+    pub fn load_secure_signer_ecdh_sig(&self) -> Option<Extension> {
+        match self.root.extensions.secure_signer.clone() {
+            Some(v) => {
+                Some(v.ecdh)
+            },
+            None => None,
+        }
+    }
+
+    // NOTE: RESOLVER
+    pub fn load_resolver_config(&self) -> ResolverConfig {
+        self.root.resolver.clone()
+    }
+
+    // NOTE: SIDETREE
+    pub fn load_sidetree_config(&self) -> SidetreeConfig {
+        self.root.sidetree.clone()
+    }
+
+    // NOTE: SERVICES
+    pub fn load_services(&self) -> Vec<ServiceEndpoint> {
+        self.profile().services.clone()
+    }
+
+    pub fn save_services(&mut self, value: &[ServiceEndpoint]) -> Result<(), NodeXError> {
+        let profile = match self.profile_mut() {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+
+        profile.services = value.to_vec();
+        match self.write() {
+            Ok(_) => Ok(()),
+            Err(_) => panic!()
+        }
+    }
+
     // NOTE: SIGN
     pub fn load_sign_key_pair(&self) -> Option<KeyPair> {
-        match self.root.key_pairs.sign.clone() {
+        match self.profile().key_pairs.sign.clone() {
             Some(v) => {
                 let pk = match self.decode(&Some(v.public_key)) {
                     Some(v) => v,
                     None => return None,
                 };
-                let sk = match self.decode(&Some(v.secret_key)) {
+                let sk = match self.decode(&self.unseal(&v.secret_key)) {
                     Some(v) => v,
                     None => return None,
                 };
@@ -242,8 +818,17 @@ impl AppConfig {
             Some(v) => v,
             None => return Err(NodeXError {}),
         };
+        let sk = match self.seal(&sk) {
+            Some(v) => v,
+            None => return Err(NodeXError {}),
+        };
+
+        let profile = match self.profile_mut() {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError {}),
+        };
 
-        self.root.key_pairs.sign = Some(KeyPairConfig {
+        profile.key_pairs.sign = Some(KeyPairConfig {
             public_key: pk,
             secret_key: sk,
         });
@@ -256,13 +841,13 @@ impl AppConfig {
 
     // NOTE: UPDATE
     pub fn load_update_key_pair(&self) -> Option<KeyPair> {
-        match self.root.key_pairs.update.clone() {
+        match self.profile().key_pairs.update.clone() {
             Some(v) => {
                 let pk = match self.decode(&Some(v.public_key)) {
                     Some(v) => v,
                     None => return None,
                 };
-                let sk = match self.decode(&Some(v.secret_key)) {
+                let sk = match self.decode(&self.unseal(&v.secret_key)) {
                     Some(v) => v,
                     None => return None,
                 };
@@ -282,8 +867,17 @@ impl AppConfig {
             Some(v) => v,
             None => return Err(NodeXError {}),
         };
+        let sk = match self.seal(&sk) {
+            Some(v) => v,
+            None => return Err(NodeXError {}),
+        };
+
+        let profile = match self.profile_mut() {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError {}),
+        };
 
-        self.root.key_pairs.update = Some(KeyPairConfig {
+        profile.key_pairs.update = Some(KeyPairConfig {
             public_key: pk,
             secret_key: sk,
         });
@@ -296,13 +890,13 @@ impl AppConfig {
 
     // NOTE: RECOVER
     pub fn load_recovery_key_pair(&self) -> Option<KeyPair> {
-        match self.root.key_pairs.recover.clone() {
+        match self.profile().key_pairs.recover.clone() {
             Some(v) => {
                 let pk = match self.decode(&Some(v.public_key)) {
                     Some(v) => v,
                     None => return None,
                 };
-                let sk = match self.decode(&Some(v.secret_key)) {
+                let sk = match self.decode(&self.unseal(&v.secret_key)) {
                     Some(v) => v,
                     None => return None,
                 };
@@ -322,8 +916,17 @@ impl AppConfig {
             Some(v) => v,
             None => return Err(NodeXError {}),
         };
+        let sk = match self.seal(&sk) {
+            Some(v) => v,
+            None => return Err(NodeXError {}),
+        };
+
+        let profile = match self.profile_mut() {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError {}),
+        };
 
-        self.root.key_pairs.recover = Some(KeyPairConfig {
+        profile.key_pairs.recover = Some(KeyPairConfig {
             public_key: pk,
             secret_key: sk,
         });
@@ -336,13 +939,13 @@ impl AppConfig {
 
     // NOTE: ENCRYPT
     pub fn load_encrypt_key_pair(&self) -> Option<KeyPair> {
-        match self.root.key_pairs.encrypt.clone() {
+        match self.profile().key_pairs.encrypt.clone() {
             Some(v) => {
                 let pk = match self.decode(&Some(v.public_key)) {
                     Some(v) => v,
                     None => return None,
                 };
-                let sk = match self.decode(&Some(v.secret_key)) {
+                let sk = match self.decode(&self.unseal(&v.secret_key)) {
                     Some(v) => v,
                     None => return None,
                 };
@@ -362,8 +965,17 @@ impl AppConfig {
             Some(v) => v,
             None => return Err(NodeXError {}),
         };
+        let sk = match self.seal(&sk) {
+            Some(v) => v,
+            None => return Err(NodeXError {}),
+        };
+
+        let profile = match self.profile_mut() {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError {}),
+        };
 
-        self.root.key_pairs.encrypt = Some(KeyPairConfig {
+        profile.key_pairs.encrypt = Some(KeyPairConfig {
             public_key: pk,
             secret_key: sk,
         });
@@ -374,30 +986,267 @@ impl AppConfig {
         }
     }
 
+    // NOTE: KEY ROTATION
+    pub fn load_key_rotation_config(&self) -> KeyRotationConfig {
+        self.root.key_rotation.clone()
+    }
+
+    pub fn get_sign_key_type(&self) -> KeyType {
+        self.profile().sign_key_type
+    }
+
+    pub fn get_key_pair_type(&self) -> KeyType {
+        self.profile().key_pair_type()
+    }
+
+    pub fn save_key_pair_type(&mut self, value: KeyType) -> Result<(), NodeXError> {
+        let profile = match self.profile_mut() {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+
+        profile.key_pairs.key_type = Some(value);
+        match self.write() {
+            Ok(_) => Ok(()),
+            Err(_) => panic!()
+        }
+    }
+
+    pub fn get_sign_key_index(&self) -> u32 {
+        self.profile().key_indexes.sign
+    }
+
+    pub fn get_update_key_index(&self) -> u32 {
+        self.profile().key_indexes.update
+    }
+
+    pub fn get_recovery_key_index(&self) -> u32 {
+        self.profile().key_indexes.recovery
+    }
+
+    pub fn get_rotated_at(&self) -> Option<i64> {
+        self.profile().key_indexes.rotated_at
+    }
+
+    // NOTE: KEY CHECKPOINTS
+    pub fn save_checkpoint(&mut self, operation_id: &str) -> Result<(), NodeXError> {
+        let profile = match self.profile_mut() {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+
+        let checkpoint = KeyCheckpointConfig {
+            operation_id: operation_id.to_string(),
+            key_indexes: profile.key_indexes.clone(),
+            services: profile.services.clone(),
+        };
+
+        profile.checkpoints.push(checkpoint);
+
+        match self.write() {
+            Ok(_) => Ok(()),
+            Err(_) => panic!()
+        }
+    }
+
+    // NOTE: the operation is published, the keys it replaced are not needed anymore
+    pub fn release_checkpoint(&mut self, operation_id: &str) -> Result<(), NodeXError> {
+        let profile = match self.profile_mut() {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+
+        let index = match profile.checkpoints.iter().position(|v| v.operation_id == operation_id) {
+            Some(v) => v,
+            None => return Ok(()),
+        };
+
+        profile.checkpoints.drain(..=index);
+
+        match self.write() {
+            Ok(_) => Ok(()),
+            Err(_) => panic!()
+        }
+    }
+
+    // NOTE: the operation is rejected, the checkpoints of the operations queued after it are dropped too
+    pub fn take_checkpoint(&mut self, operation_id: &str) -> Option<KeyCheckpoint> {
+        let profile = self.profile_mut().ok()?;
+
+        let index = profile.checkpoints.iter().position(|v| v.operation_id == operation_id)?;
+        let checkpoint = profile.checkpoints.drain(index..).next()?;
+        let did = profile.did.clone();
+
+        match self.write() {
+            Ok(_) => {},
+            Err(_) => panic!()
+        };
+
+        Some(KeyCheckpoint {
+            did,
+            sign_index: checkpoint.key_indexes.sign,
+            update_index: checkpoint.key_indexes.update,
+            recovery_index: checkpoint.key_indexes.recovery,
+            rotated_at: checkpoint.key_indexes.rotated_at,
+            services: checkpoint.services,
+        })
+    }
+
+    pub fn save_rotated_at(&mut self, value: i64) -> Result<(), NodeXError> {
+        let profile = match self.profile_mut() {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+
+        profile.key_indexes.rotated_at = Some(value);
+        match self.write() {
+            Ok(_) => Ok(()),
+            Err(_) => panic!()
+        }
+    }
+
+    pub fn save_sign_key_index(&mut self, value: u32, rotated_at: i64) -> Result<(), NodeXError> {
+        let profile = match self.profile_mut() {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+
+        profile.key_indexes.sign = value;
+        profile.key_indexes.rotated_at = Some(rotated_at);
+        match self.write() {
+            Ok(_) => Ok(()),
+            Err(_) => panic!()
+        }
+    }
+
+    pub fn save_update_key_index(&mut self, value: u32) -> Result<(), NodeXError> {
+        let profile = match self.profile_mut() {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+
+        profile.key_indexes.update = value;
+        match self.write() {
+            Ok(_) => Ok(()),
+            Err(_) => panic!()
+        }
+    }
+
+    pub fn save_recovery_key_index(&mut self, value: u32) -> Result<(), NodeXError> {
+        let profile = match self.profile_mut() {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+
+        profile.key_indexes.recovery = value;
+        match self.write() {
+            Ok(_) => Ok(()),
+            Err(_) => panic!()
+        }
+    }
+
+    pub fn reset_key_indexes(&mut self) -> Result<(), NodeXError> {
+        let profile = match self.profile_mut() {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+
+        profile.key_indexes = KeyIndexesConfig::default();
+        match self.write() {
+            Ok(_) => Ok(()),
+            Err(_) => panic!()
+        }
+    }
+
     // NOTE: DID
     pub fn get_did(&self) -> Option<String> {
-        self.root.did.clone()
+        self.profile().did.clone()
     }
 
-    pub fn save_did(&mut self, value: &str) {
-        self.root.did = Some(value.to_string());
+    pub fn save_did(&mut self, value: &str) -> Result<(), NodeXError> {
+        let profile = match self.profile_mut() {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+
+        profile.did = Some(value.to_string());
 
         match self.write() {
-            Ok(_) => {},
+            Ok(_) => Ok(()),
             Err(_) => panic!()
         }
     }
 
     // NOTE: Mnemonic
     pub fn get_mnemonic(&self) -> Option<String> {
-        self.root.mnemonic.clone()
+        match &self.profile().mnemonic {
+            Some(v) => self.unseal(v),
+            None => None,
+        }
     }
 
-    pub fn save_mnemonic(&mut self, value: &str) {
-        self.root.mnemonic = Some(value.to_string());
+    pub fn save_mnemonic(&mut self, value: &str) -> Result<(), NodeXError> {
+        let sealed = match self.seal(value) {
+            Some(v) => v,
+            None => panic!()
+        };
+
+        let profile = match self.profile_mut() {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+
+        profile.mnemonic = Some(sealed);
 
         match self.write() {
-            Ok(_) => {},
+            Ok(_) => Ok(()),
+            Err(_) => panic!()
+        }
+    }
+
+    // NOTE: Passphrase (BIP39)
+    pub fn get_passphrase(&self) -> Option<String> {
+        match &self.profile().passphrase {
+            Some(v) => self.unseal(v),
+            None => self.profile().unsaved_passphrase.clone(),
+        }
+    }
+
+    // NOTE: a passphrase saved in plain text before it was kept sealed only counts too
+    pub fn has_passphrase(&self) -> bool {
+        self.profile().has_passphrase || self.profile().passphrase.is_some()
+    }
+
+    pub fn save_passphrase(&mut self, value: Option<&str>) -> Result<(), NodeXError> {
+        let sealed = match value {
+            Some(v) if self.cipher.is_enabled() => {
+                match self.seal(v) {
+                    Some(v) => Some(v),
+                    None => panic!()
+                }
+            },
+            Some(_) => {
+                log::warn!("the BIP39 passphrase is not saved while the config is not encrypted, restore the identity again after a restart to derive new keys");
+                None
+            },
+            None => None,
+        };
+        let unsaved = match sealed {
+            Some(_) => None,
+            None => value.map(|v| v.to_string()),
+        };
+
+        let profile = match self.profile_mut() {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+
+        profile.passphrase = sealed;
+        profile.unsaved_passphrase = unsaved;
+        profile.has_passphrase = value.is_some();
+
+        match self.write() {
+            Ok(_) => Ok(()),
             Err(_) => panic!()
         }
     }
@@ -405,14 +1254,210 @@ impl AppConfig {
     // NOTE: Is Initialized
     #[allow(dead_code)]
     pub fn get_is_initialized(&self) -> bool {
-        self.root.is_initialized
+        self.profile().is_initialized
     }
 
-    pub fn save_is_initialized(&mut self, value: bool) {
-        self.root.is_initialized = value;
+    pub fn save_is_initialized(&mut self, value: bool) -> Result<(), NodeXError> {
+        let profile = match self.profile_mut() {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+
+        profile.is_initialized = value;
         match self.write() {
-            Ok(_) => {},
+            Ok(_) => Ok(()),
             Err(_) => panic!()
         }
     }
-}
\ No newline at end of file
+
+    // NOTE: Is Deactivated
+    pub fn get_is_deactivated(&self) -> bool {
+        self.profile().is_deactivated
+    }
+
+    pub fn save_is_deactivated(&mut self, value: bool) -> Result<(), NodeXError> {
+        let profile = match self.profile_mut() {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+
+        profile.is_deactivated = value;
+        match self.write() {
+            Ok(_) => Ok(()),
+            Err(_) => panic!()
+        }
+    }
+}
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use rstest::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_default_profile_layout() {
+        let root = match serde_json::from_value::<ConfigRoot>(json!({
+            "did": "did:nodex:test:a",
+            "is_initialized": true,
+            "profiles": {
+                "tenant-1": { "did": "did:nodex:test:b" },
+            },
+        })) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        // NOTE: the default profile stays at the top level of config.json
+        assert_eq!(root.profile.did, Some("did:nodex:test:a".to_string()));
+        assert!(root.profile.is_initialized);
+        assert_eq!(root.profiles["tenant-1"].did, Some("did:nodex:test:b".to_string()));
+
+        let value = match serde_json::to_value(&root) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert_eq!(value["did"], "did:nodex:test:a");
+        assert_eq!(value["profiles"]["tenant-1"]["did"], "did:nodex:test:b");
+    }
+
+    #[test]
+    fn test_key_store_backend() {
+        let root = match serde_json::from_value::<ConfigRoot>(json!({
+            "keystore": {
+                "backend": "pkcs11",
+                "pkcs11": { "module": "/usr/lib/softhsm/libsofthsm2.so", "token_label": "nodex" },
+            },
+        })) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert_eq!(root.keystore.backend(true), KeyStoreBackend::Pkcs11);
+        assert_eq!(root.keystore.pkcs11.map(|v| v.token_label), Some("nodex".to_string()));
+
+        // NOTE: config.json written before the backends were selectable keeps using the extension
+        assert_eq!(ConfigRoot::default().keystore.backend(true), KeyStoreBackend::Extension);
+        assert_eq!(ConfigRoot::default().keystore.backend(false), KeyStoreBackend::File);
+    }
+
+    #[test]
+    fn test_key_pair_type() {
+        let profile = match serde_json::from_value::<ProfileConfig>(json!({
+            "sign_key_type": "ed25519",
+            "key_pairs": { "key_type": "secp256k1" },
+        })) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        // NOTE: the setting changed after the key ring was saved does not change the curve of its keys
+        assert_eq!(profile.key_pair_type(), KeyType::Secp256k1);
+
+        let profile = match serde_json::from_value::<ProfileConfig>(json!({
+            "sign_key_type": "p256",
+        })) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert_eq!(profile.key_pair_type(), KeyType::P256);
+        let value = match serde_json::to_value(&profile) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert!(value["key_pairs"].get("key_type").is_none());
+    }
+
+    #[test]
+    fn test_sealer() {
+        let mut encryption = EncryptionConfig { mode: EncryptionMode::Env, ..Default::default() };
+
+        // NOTE: the key is passed in, NODEX_CONFIG_KEY is shared with the other tests
+        assert!(AppConfig::sealer_with(&mut encryption, None).is_err());
+
+        let cipher = match AppConfig::sealer_with(&mut encryption, Some("000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f")) {
+            Ok(v) => Cipher::new(None, None, v),
+            Err(_) => panic!()
+        };
+
+        // NOTE: the key check is created once, a different key is rejected afterwards
+        assert_eq!(AppConfig::check(&cipher, &mut encryption).ok(), Some(true));
+        assert_eq!(AppConfig::check(&cipher, &mut encryption).ok(), Some(false));
+
+        encryption.check = match Sealer::new(&[1u8; 32]).and_then(|v| v.seal(b"nodex")) {
+            Ok(v) => Some(v),
+            Err(_) => panic!()
+        };
+        assert!(AppConfig::check(&cipher, &mut encryption).is_err());
+
+        // NOTE: nothing is checked in plain text mode
+        assert_eq!(AppConfig::check(&Cipher::default(), &mut encryption).ok(), Some(false));
+
+        let mut profile = match serde_json::from_value::<ProfileConfig>(json!({
+            "mnemonic": "abandon abandon",
+            "key_pairs": {
+                "sign": { "public_key": "00", "secret_key": "01" },
+            },
+        })) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        for secret in profile.secrets_mut() {
+            *secret = match cipher.encrypt(secret.as_bytes()) {
+                Ok(v) => v,
+                Err(_) => panic!()
+            };
+        }
+
+        assert!(profile.mnemonic.as_deref().map(Cipher::is_sealed).unwrap_or(false));
+
+        let sign = match profile.key_pairs.sign {
+            Some(v) => v,
+            None => panic!()
+        };
+
+        assert_eq!(cipher.decrypt(&sign.secret_key).ok(), Some(b"01".to_vec()));
+    }
+
+    #[rstest]
+    #[case("default", true)]
+    #[case("tenant_1-a", true)]
+    #[case("", false)]
+    #[case("../tenant", false)]
+    #[case("tenant 1", false)]
+    fn test_is_valid_profile_name(#[case] name: &str, #[case] expected: bool) {
+        assert_eq!(is_valid_profile_name(name), expected);
+    }
+
+    #[test]
+    fn test_checkpoint_secrets() {
+        // NOTE: a checkpoint written before only the key indexes were kept drops the mnemonic it copied
+        let mut profile = match serde_json::from_value::<ProfileConfig>(json!({
+            "mnemonic": "current",
+            "checkpoints": [{
+                "operation_id": "a",
+                "mnemonic": "previous",
+                "key_indexes": { "sign": 1, "update": 2 },
+                "services": [],
+            }],
+        })) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        let secrets: Vec<String> = profile.secrets_mut().into_iter().map(|v| v.clone()).collect();
+
+        assert_eq!(secrets, vec![ "current".to_string() ]);
+        assert_eq!(profile.checkpoints[0].key_indexes.update, 2);
+        assert!(json!(&profile)["checkpoints"][0].get("mnemonic").is_none());
+    }
+
+    #[actix_rt::test]
+    async fn test_with_profile() {
+        assert_eq!(current_profile(), DEFAULT_PROFILE);
+        assert_eq!(with_profile("tenant-1", async { current_profile() }).await, "tenant-1");
+    }
+}
@@ -0,0 +1,10 @@
+use actix_web::{ HttpRequest, HttpResponse };
+
+// NOTE: GET /admin/extensions
+pub async fn handler(
+    _req: HttpRequest,
+) -> actix_web::Result<HttpResponse> {
+    let manager = crate::nodex::extension::manager::extension_manager();
+
+    Ok(HttpResponse::Ok().json(manager.list()))
+}
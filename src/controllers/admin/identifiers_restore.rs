@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+use actix_web::{ HttpRequest, HttpResponse, web };
+
+// NOTE: POST /admin/identifiers/restore
+#[derive(Deserialize, Serialize)]
+pub struct MessageContainer {
+    did: String,
+    mnemonic: String,
+    passphrase: Option<String>,
+}
+
+pub async fn handler(
+    _req: HttpRequest,
+    web::Json(json): web::Json<MessageContainer>,
+) -> actix_web::Result<HttpResponse> {
+    let service = crate::services::nodex::NodeX::new();
+
+    match service.restore_identifier(&json.did, &json.mnemonic, json.passphrase.as_deref()).await {
+        Ok(v) => {
+            Ok(HttpResponse::Ok().json(&v))
+        },
+        Err(_) => {
+            Ok(HttpResponse::UnprocessableEntity().finish())
+        }
+    }
+}
@@ -0,0 +1,17 @@
+use actix_web::{ HttpRequest, HttpResponse };
+
+// NOTE: POST /admin/keys/rotate
+pub async fn handler(
+    _req: HttpRequest,
+) -> actix_web::Result<HttpResponse> {
+    let service = crate::services::nodex::NodeX::new();
+
+    match service.rotate_keys().await {
+        Ok(v) => {
+            Ok(HttpResponse::Ok().json(&v))
+        },
+        Err(_) => {
+            Ok(HttpResponse::InternalServerError().finish())
+        }
+    }
+}
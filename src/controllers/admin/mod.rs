@@ -0,0 +1,3 @@
+pub mod keys_rotate;
+pub mod identifiers_restore;
+pub mod extensions;
@@ -1,2 +1,3 @@
 pub mod public;
-pub mod internal;
\ No newline at end of file
+pub mod internal;
+pub mod admin;
\ No newline at end of file
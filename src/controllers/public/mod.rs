@@ -1,3 +1,13 @@
 pub mod nodex_create_identifier;
 pub mod nodex_find_identifier;
+pub mod nodex_dereference_identifier;
+pub mod nodex_update_identifier;
+pub mod nodex_recover_identifier;
+pub mod nodex_deactivate_identifier;
+pub mod nodex_find_services;
+pub mod nodex_add_services;
+pub mod nodex_update_service;
+pub mod nodex_remove_service;
+pub mod nodex_find_operations;
+pub mod nodex_find_operation;
 pub mod nodex_transfer;
\ No newline at end of file
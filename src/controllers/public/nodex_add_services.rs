@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+use actix_web::{ HttpRequest, HttpResponse, web };
+
+use crate::nodex::sidetree::payload::ServiceEndpoint;
+
+// NOTE: POST /identifiers/services
+#[derive(Deserialize, Serialize)]
+pub struct MessageContainer {
+    services: Vec<ServiceEndpoint>,
+}
+
+pub async fn handler(
+    _req: HttpRequest,
+    web::Json(json): web::Json<MessageContainer>,
+) -> actix_web::Result<HttpResponse> {
+    let service = crate::services::nodex::NodeX::new();
+
+    if json.services.is_empty() || json.services.iter().any(|v| !v.is_valid()) {
+        return Ok(HttpResponse::BadRequest().finish())
+    }
+
+    match service.add_services(&json.services).await {
+        Ok(v) => {
+            Ok(HttpResponse::Ok().json(&v))
+        },
+        Err(_) => {
+            Ok(HttpResponse::InternalServerError().finish())
+        }
+    }
+}
@@ -0,0 +1,36 @@
+use actix_web::{ HttpRequest, HttpResponse, web };
+
+use crate::nodex::keyring;
+
+// NOTE: DELETE /identifiers/${ did }
+
+pub async fn handler(
+    _req: HttpRequest,
+    did: web::Path<String>,
+) -> actix_web::Result<HttpResponse> {
+    let service = crate::services::nodex::NodeX::new();
+
+    // NOTE: only the agent's own DID can be deactivated
+    let my_did = match keyring::mnemonic::MnemonicKeyring::load_keyring() {
+        Ok(v) => {
+            match v.get_identifier() {
+                Ok(v) => v,
+                Err(_) => return Ok(HttpResponse::InternalServerError().finish())
+            }
+        },
+        Err(_) => return Ok(HttpResponse::InternalServerError().finish())
+    };
+
+    if *did != my_did {
+        return Ok(HttpResponse::NotFound().finish())
+    }
+
+    match service.deactivate_identifier().await {
+        Ok(v) => {
+            Ok(HttpResponse::Ok().json(&v))
+        },
+        Err(_) => {
+            Ok(HttpResponse::InternalServerError().finish())
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,28 @@
+use actix_web::{ HttpRequest, HttpResponse, web };
+
+use crate::nodex::resolver::{cache::did_resolver, dereferencer::{DidUrl, DidUrlDereferencer}};
+
+// NOTE: GET /identifiers/${ did_url }/dereference
+//       the DID URL may contain a path, "?" and "#" of the DID URL must be percent-encoded
+pub async fn handler(
+    _req: HttpRequest,
+    did_url: web::Path<String>,
+) -> actix_web::Result<HttpResponse> {
+    if DidUrl::parse(&did_url).is_err() {
+        return Ok(HttpResponse::BadRequest().finish())
+    }
+
+    let resolver = did_resolver();
+
+    match DidUrlDereferencer::dereference(resolver.as_ref(), &did_url).await {
+        Ok(Some(v)) => {
+            Ok(HttpResponse::Ok().json(&v))
+        },
+        Ok(None) => {
+            Ok(HttpResponse::NotFound().finish())
+        },
+        Err(_) => {
+            Ok(HttpResponse::InternalServerError().finish())
+        }
+    }
+}
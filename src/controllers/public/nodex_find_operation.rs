@@ -0,0 +1,18 @@
+use actix_web::{ HttpRequest, HttpResponse, web };
+
+// NOTE: GET /operations/${ id }
+pub async fn handler(
+    _req: HttpRequest,
+    id: web::Path<String>,
+) -> actix_web::Result<HttpResponse> {
+    let service = crate::services::nodex::NodeX::new();
+
+    match service.find_operation(&id) {
+        Some(v) => {
+            Ok(HttpResponse::Ok().json(&v))
+        },
+        None => {
+            Ok(HttpResponse::NotFound().finish())
+        }
+    }
+}
@@ -0,0 +1,10 @@
+use actix_web::{ HttpRequest, HttpResponse };
+
+// NOTE: GET /operations
+pub async fn handler(
+    _req: HttpRequest,
+) -> actix_web::Result<HttpResponse> {
+    let service = crate::services::nodex::NodeX::new();
+
+    Ok(HttpResponse::Ok().json(service.find_operations()))
+}
@@ -0,0 +1,17 @@
+use actix_web::{ HttpRequest, HttpResponse };
+
+// NOTE: GET /identifiers/services
+pub async fn handler(
+    _req: HttpRequest,
+) -> actix_web::Result<HttpResponse> {
+    let service = crate::services::nodex::NodeX::new();
+
+    match service.find_services().await {
+        Ok(v) => {
+            Ok(HttpResponse::Ok().json(&v))
+        },
+        Err(_) => {
+            Ok(HttpResponse::InternalServerError().finish())
+        }
+    }
+}
@@ -0,0 +1,27 @@
+use actix_web::{ HttpRequest, HttpResponse, web };
+
+// NOTE: DELETE /identifiers/services/${ id }
+pub async fn handler(
+    _req: HttpRequest,
+    id: web::Path<String>,
+) -> actix_web::Result<HttpResponse> {
+    let service = crate::services::nodex::NodeX::new();
+
+    let services = match service.find_services().await {
+        Ok(v) => v,
+        Err(_) => return Ok(HttpResponse::InternalServerError().finish())
+    };
+
+    if !services.iter().any(|v| v.id == *id || v.id.ends_with(&format!("#{}", id))) {
+        return Ok(HttpResponse::NotFound().finish())
+    }
+
+    match service.remove_services(&[ id.to_string() ]).await {
+        Ok(v) => {
+            Ok(HttpResponse::Ok().json(&v))
+        },
+        Err(_) => {
+            Ok(HttpResponse::InternalServerError().finish())
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+use actix_web::{ HttpRequest, HttpResponse, web };
+
+use crate::nodex::{keyring, sidetree::payload::DIDPatchAction};
+
+// NOTE: PATCH /identifiers/${ did }
+#[derive(Deserialize, Serialize)]
+pub struct MessageContainer {
+    patches: Vec<DIDPatchAction>,
+}
+
+pub async fn handler(
+    _req: HttpRequest,
+    did: web::Path<String>,
+    web::Json(json): web::Json<MessageContainer>,
+) -> actix_web::Result<HttpResponse> {
+    let service = crate::services::nodex::NodeX::new();
+
+    // NOTE: only the agent's own DID can be updated
+    let my_did = match keyring::mnemonic::MnemonicKeyring::load_keyring() {
+        Ok(v) => {
+            match v.get_identifier() {
+                Ok(v) => v,
+                Err(_) => return Ok(HttpResponse::InternalServerError().finish())
+            }
+        },
+        Err(_) => return Ok(HttpResponse::InternalServerError().finish())
+    };
+
+    if *did != my_did {
+        return Ok(HttpResponse::NotFound().finish())
+    }
+
+    match service.update_identifier(&json.patches).await {
+        Ok(v) => {
+            Ok(HttpResponse::Ok().json(&v))
+        },
+        Err(_) => {
+            Ok(HttpResponse::InternalServerError().finish())
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,46 @@
+use serde::{Deserialize, Serialize};
+use actix_web::{ HttpRequest, HttpResponse, web };
+
+use crate::nodex::{schema::did_document::ServiceEndpointValue, sidetree::payload::ServiceEndpoint};
+
+// NOTE: PUT /identifiers/services/${ id }
+#[derive(Deserialize, Serialize)]
+pub struct MessageContainer {
+    #[serde(rename = "type")]
+    r#type: String,
+
+    #[serde(rename = "serviceEndpoint")]
+    service_endpoint: ServiceEndpointValue,
+
+    #[serde(rename = "description")]
+    description: Option<String>,
+}
+
+pub async fn handler(
+    _req: HttpRequest,
+    id: web::Path<String>,
+    web::Json(json): web::Json<MessageContainer>,
+) -> actix_web::Result<HttpResponse> {
+    let service = crate::services::nodex::NodeX::new();
+
+    let endpoint = ServiceEndpoint {
+        id: id.to_string(),
+        r#type: json.r#type,
+        service_endpoint: json.service_endpoint,
+        description: json.description,
+    };
+
+    if !endpoint.is_valid() {
+        return Ok(HttpResponse::BadRequest().finish())
+    }
+
+    // NOTE: add-services replaces a service with the same id
+    match service.add_services(&[ endpoint ]).await {
+        Ok(v) => {
+            Ok(HttpResponse::Ok().json(&v))
+        },
+        Err(_) => {
+            Ok(HttpResponse::InternalServerError().finish())
+        }
+    }
+}
@@ -3,6 +3,8 @@ use serde_json::Value;
 
 pub mod sender;
 pub mod receiver;
+pub mod rotation;
+pub mod operations;
 
 type Responder = oneshot::Sender<bool>;
 
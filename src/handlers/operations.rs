@@ -0,0 +1,23 @@
+use std::sync::{Arc, atomic::AtomicBool};
+use tokio::time::{Duration, sleep};
+
+use crate::services::nodex::NodeX;
+
+pub async fn handler(shutdown_marker: Arc<AtomicBool>) {
+    log::info!("start operation queue");
+
+    let node_x = NodeX::new();
+
+    loop {
+        if shutdown_marker.load(std::sync::atomic::Ordering::SeqCst) {
+            break;
+        }
+
+        // NOTE: every operation has its own backoff, the queue only decides which are due
+        node_x.process_operations().await;
+
+        sleep(Duration::from_secs(1)).await;
+    }
+
+    log::info!("stop operation queue");
+}
@@ -0,0 +1,70 @@
+use std::{collections::HashMap, sync::{Arc, atomic::AtomicBool}};
+use chrono::Utc;
+use tokio::time::{Duration, sleep};
+
+use crate::{app_config, config::{with_profile, current_profile}, services::nodex::NodeX};
+
+const RETRY_INTERVAL: i64 = 60;
+
+pub async fn handler(shutdown_marker: Arc<AtomicBool>) {
+    log::info!("start key rotation");
+
+    let mut retry_at = HashMap::<String, i64>::new();
+
+    loop {
+        if shutdown_marker.load(std::sync::atomic::Ordering::SeqCst) {
+            break;
+        }
+
+        sleep(Duration::from_secs(1)).await;
+
+        let (interval, profiles) = {
+            let config = app_config();
+            let config = config.inner.lock().unwrap();
+
+            (config.load_key_rotation_config().interval, config.get_profile_names())
+        };
+
+        // NOTE: the schedule is disabled
+        if interval == 0 {
+            continue;
+        }
+
+        // NOTE: every profile rotates its own signing key
+        for profile in profiles {
+            let now = Utc::now().timestamp();
+            let rotated_at = with_profile(&profile, async { app_config().inner.lock().unwrap().get_rotated_at() }).await;
+
+            // NOTE: keys created before the time was recorded are scheduled from the first time they are seen
+            let rotated_at = match rotated_at {
+                Some(v) => v,
+                None => {
+                    let saved = with_profile(&profile, async { app_config().inner.lock().unwrap().save_rotated_at(now) }).await;
+
+                    if saved.is_err() {
+                        log::error!("unable to schedule the rotation of profile {}", &profile);
+                    }
+                    continue;
+                },
+            };
+
+            if now < rotated_at + interval as i64 || now < *retry_at.get(&profile).unwrap_or(&0) {
+                continue;
+            }
+
+            let rotated = with_profile(&profile, async {
+                log::info!("rotating the signing key of profile {}", current_profile());
+
+                NodeX::new().rotate_keys().await
+            }).await;
+
+            if rotated.is_err() {
+                log::error!("failed to rotate the signing key of profile {}, retry in {} seconds", &profile, RETRY_INTERVAL);
+
+                retry_at.insert(profile, now + RETRY_INTERVAL);
+            }
+        }
+    }
+
+    log::info!("stop key rotation");
+}
@@ -1,6 +1,6 @@
 extern crate env_logger;
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use rumqttc::{AsyncClient, QoS, MqttOptions};
 use services::nodex::NodeX;
 use tokio::sync::mpsc;
@@ -11,7 +11,7 @@ use std::{fs::{self}, sync::{Arc, Once, Mutex}, collections::HashMap};
 use shadow_rs::shadow;
 
 use handlers::Command;
-use crate::config::AppConfig;
+use crate::config::{AppConfig, with_profile, is_valid_profile_name, DEFAULT_PROFILE};
 
 mod nodex;
 mod services;
@@ -56,6 +56,37 @@ struct Cli {
     /// Show node ID
     #[clap(long)]
     did: bool,
+
+    #[clap(subcommand)]
+    command: Option<Commands>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Commands {
+    /// Serve an extension library in a helper process of the agent
+    #[clap(hide = true)]
+    ExtensionHost {
+        filename: String,
+    },
+
+    /// Restore an existing identity from its mnemonic phrase
+    Restore {
+        /// DID to restore
+        #[clap(long)]
+        did: String,
+
+        /// Mnemonic phrase, read from stdin when omitted
+        #[clap(long)]
+        mnemonic: Option<String>,
+
+        /// BIP39 passphrase, saved only when the config is encrypted
+        #[clap(long)]
+        passphrase: Option<String>,
+
+        /// Profile to restore the identity into
+        #[clap(long, default_value = DEFAULT_PROFILE)]
+        profile: String,
+    },
 }
 
 #[tokio::main]
@@ -65,6 +96,11 @@ async fn main() -> std::io::Result<()> {
     std::env::set_var("RUST_LOG", "info");
     env_logger::init();
 
+    // NOTE: the helper process of an isolated extension never reads config.json
+    if let Some(Commands::ExtensionHost { filename }) = &cli.command {
+        std::process::exit(nodex::extension::host::serve(filename));
+    }
+
     let hub_did_topic = "nodex/did:nodex:test:EiCW6eklabBIrkTMHFpBln7574xmZlbMakWSCNtBWcunDg";
 
     let config = AppConfig::new();
@@ -73,6 +109,14 @@ async fn main() -> std::io::Result<()> {
         Err(_) => panic!(),
     };
 
+    // NOTE: extension libraries are loaded and checked once before they are used
+    let extensions = app_config().inner.lock().unwrap().get_extensions();
+
+    if nodex::extension::manager::extension_manager().load_all(&extensions).is_err() {
+        eprintln!("unable to load the configured extensions, see the log for details");
+        std::process::exit(1);
+    }
+
     let home_dir = match dirs::home_dir() {
         Some(v) => v,
         None => panic!(),
@@ -90,10 +134,53 @@ async fn main() -> std::io::Result<()> {
         Err(_) => panic!(),
     };
 
+    // NOTE: restore Key Chain
+    if let Some(Commands::Restore { did, mnemonic, passphrase, profile }) = cli.command {
+        if !is_valid_profile_name(&profile) {
+            eprintln!("invalid profile: {}", profile);
+            std::process::exit(1);
+        }
+
+        // NOTE: restoring into a new profile is the way to add one
+        app_config().inner.lock().unwrap().add_profile(&profile);
+
+        let mnemonic = match mnemonic {
+            Some(v) => v,
+            None => {
+                let mut line = String::new();
+
+                match std::io::stdin().read_line(&mut line) {
+                    Ok(_) => line,
+                    Err(_) => panic!(),
+                }
+            },
+        };
+
+        match with_profile(&profile, NodeX::new().restore_identifier(&did, &mnemonic, passphrase.as_deref())).await {
+            Ok(v) => {
+                println!("Node ID: {}", v.did_document.id);
+                return Ok(())
+            },
+            Err(_) => {
+                eprintln!("unable to restore {}, the mnemonic phrase does not match the published keys", did);
+                std::process::exit(1);
+            },
+        }
+    }
+
     // NOTE: generate Key Chain
     let node_x = NodeX::new();
     let did = node_x.create_identifier().await.unwrap();
 
+    // NOTE: every profile has its own identifier
+    let profiles = app_config().inner.lock().unwrap().get_profile_names();
+
+    for profile in profiles.iter().filter(|v| *v != DEFAULT_PROFILE) {
+        if with_profile(profile, node_x.create_identifier()).await.is_err() {
+            log::error!("unable to create the identifier of profile {}", profile);
+        }
+    }
+
     match cli.did {
         true => {
             println!("Node ID: {}", did.did_document.id);
@@ -133,6 +220,8 @@ async fn main() -> std::io::Result<()> {
     let server_task = tokio::spawn(server);
     let sender_task = tokio::spawn(handlers::sender::handler(rx, client, Arc::clone(&db), mqtt_topic));
     let receiver_task = tokio::spawn(handlers::receiver::handler(Arc::clone(&shutdown_marker), eventloop, Arc::clone(&db)));
+    let rotation_task = tokio::spawn(handlers::rotation::handler(Arc::clone(&shutdown_marker)));
+    let operations_task = tokio::spawn(handlers::operations::handler(Arc::clone(&shutdown_marker)));
 
     let shutdown = tokio::spawn(async move {
         tokio::signal::ctrl_c().await.unwrap();
@@ -143,7 +232,7 @@ async fn main() -> std::io::Result<()> {
         server_stop.await;
     });
 
-    match tokio::try_join!(server_task, sender_task, receiver_task, shutdown) {
+    match tokio::try_join!(server_task, sender_task, receiver_task, rotation_task, operations_task, shutdown) {
         Ok(_) => Ok(()),
         Err(_) => panic!(),
     }
@@ -2,7 +2,7 @@ use chrono::Utc;
 use serde::{Serialize, Deserialize};
 use serde_json::{Value, json};
 
-use crate::{nodex::{keyring::secp256k1::Secp256k1, errors::NodeXError, utils, schema::general::GeneralVcDataModel}};
+use crate::{nodex::{keyring::signing_key::SigningKey, errors::NodeXError, utils, schema::general::GeneralVcDataModel}};
 
 use super::jws::Jws;
 
@@ -42,7 +42,7 @@ pub struct ProofContext {
 pub struct CredentialSignerSuite {
     pub did    : Option<String>,
     pub key_id : Option<String>,
-    pub context: Secp256k1,
+    pub context: SigningKey,
 }
 
 pub struct CredentialSigner {}
@@ -74,8 +74,8 @@ impl CredentialSigner {
 
         let proof: ProofContext = ProofContext {
             proof: Some(Proof {
-                r#type: "EcdsaSecp256k1Signature2019".to_string(),
-                proof_purpose: "authentication".to_string(),
+                r#type: suite.context.get_type().proof_type().to_string(),
+                proof_purpose: "assertionMethod".to_string(),
                 created,
                 verification_method: format!("{}#{}", did, key_id),
                 jws,
@@ -114,11 +114,12 @@ impl CredentialSigner {
         //     throw new NodeXNotCompatibleError()
         // }
 
-        // FIXME:
-        // const vm = utils.splitDid(proof.verificationMethod)
-        // if (vm.keyId !== suite.keyId) {
-        //     throw new NodeXNotCompatibleError()
-        // }
+        // NOTE: the proof must be made by the key of the suite
+        if let (Some(did), Some(key_id)) = (&suite.did, &suite.key_id) {
+            if proof.verification_method != format!("{}#{}", did, key_id) {
+                return Err(NodeXError{})
+            }
+        }
 
         let jws = proof.jws;
         let payload = match serde_json::from_value::<GeneralVcDataModel>(serialized) {
@@ -174,7 +175,7 @@ pub mod tests {
             public: public_key(),
             secret: secret_key(),
         }) {
-            Ok(v) => v,
+            Ok(v) => SigningKey::Secp256k1(v),
             Err(_) => panic!()
         };
 
@@ -204,7 +205,7 @@ pub mod tests {
         match result.proof {
             Some(proof) => {
                 assert_eq!(proof.jws, "eyJhbGciOiJFUzI1NksiLCJiNjQiOmZhbHNlLCJjcml0IjpbImI2NCJdfQ..Qc-NyzQu2v735_qPR72j1oqUDK1Ne4XQ7Lc66_x9tlMSeI9xmrgguEA8UmQyTM0cd13xkvpK4g-NEWJBp8_d_w");
-                assert_eq!(proof.proof_purpose, "authentication");
+                assert_eq!(proof.proof_purpose, "assertionMethod");
                 assert_eq!(proof.r#type, "EcdsaSecp256k1Signature2019");
                 assert_eq!(proof.verification_method, "did:nodex:test:000000000000000000000000000000#signingKey");
             },
@@ -218,7 +219,7 @@ pub mod tests {
             public: public_key(),
             secret: secret_key(),
         }) {
-            Ok(v) => v,
+            Ok(v) => SigningKey::Secp256k1(v),
             Err(_) => panic!()
         };
 
@@ -262,4 +263,85 @@ pub mod tests {
         assert!(verified);
         assert_eq!(model, verified_model);
     }
-}
\ No newline at end of file
+
+    #[test]
+    pub fn test_verify_with_other_key() {
+        let context = match keyring::secp256k1::Secp256k1::new(&Secp256k1Context {
+            public: public_key(),
+            secret: secret_key(),
+        }) {
+            Ok(v) => SigningKey::Secp256k1(v),
+            Err(_) => panic!()
+        };
+
+        let model = GeneralVcDataModel {
+            id: None,
+            r#type: vec![ "type".to_string() ],
+            issuer: Issuer { id: "issuer".to_string() },
+            context: vec![ "context".to_string() ],
+            issuance_date: "issuance_date".to_string(),
+            credential_subject: CredentialSubject {
+                id: None,
+                container: json!(r#"{"k":"0123456789abcdef"}"#)
+            },
+            expiration_date: None,
+            proof: None,
+        };
+
+        let vc = match CredentialSigner::sign(&model, &CredentialSignerSuite {
+            did: Some("did:nodex:test:000000000000000000000000000000".to_string()),
+            key_id: Some("signingKey".to_string()),
+            context: context.clone(),
+        }) {
+            Ok(v) => v,
+            Err(_) => panic!(),
+        };
+
+        assert!(CredentialSigner::verify(&vc, &CredentialSignerSuite {
+            did: Some("did:nodex:test:000000000000000000000000000000".to_string()),
+            key_id: Some("otherKey".to_string()),
+            context,
+        }).is_err());
+    }
+
+    #[test]
+    pub fn test_sign_ed25519() {
+        let context = SigningKey::Ed25519(keyring::ed25519::Ed25519::new(&secret_key()).unwrap());
+
+        let model = GeneralVcDataModel {
+            id: None,
+            r#type: vec![ "type".to_string() ],
+            issuer: Issuer { id: "issuer".to_string() },
+            context: vec![ "context".to_string() ],
+            issuance_date: "issuance_date".to_string(),
+            credential_subject: CredentialSubject {
+                id: None,
+                container: json!(r#"{"k":"0123456789abcdef"}"#)
+            },
+            expiration_date: None,
+            proof: None,
+        };
+
+        let vc = match CredentialSigner::sign(&model, &CredentialSignerSuite {
+            did: Some("did:nodex:test:000000000000000000000000000000".to_string()),
+            key_id: Some("signingKey".to_string()),
+            context: context.clone(),
+        }) {
+            Ok(v) => v,
+            Err(_) => panic!(),
+        };
+
+        assert_eq!(vc.proof.as_ref().map(|v| v.r#type.as_str()), Some("Ed25519Signature2018"));
+
+        let (_, verified) = match CredentialSigner::verify(&vc, &CredentialSignerSuite {
+            did: None,
+            key_id: None,
+            context,
+        }) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert!(verified);
+    }
+}
@@ -1,6 +1,6 @@
 use serde::{Serialize, Deserialize};
 use serde_json::{Value, json};
-use crate::nodex::{keyring::secp256k1::Secp256k1, runtime::{self, base64_url::PaddingType}, errors::NodeXError};
+use crate::nodex::{keyring::signing_key::SigningKey, runtime::{self, base64_url::PaddingType}, errors::NodeXError};
 
 use super::signer::Signer;
 
@@ -11,13 +11,18 @@ struct JWSHeader {
     crit: Vec<String>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+struct CompactJWSHeader {
+    alg: String,
+}
+
 pub struct Jws {}
 
 impl Jws {
-    pub fn encode(object: &Value, context: &Secp256k1) -> Result<String, NodeXError> {
+    pub fn encode(object: &Value, context: &SigningKey) -> Result<String, NodeXError> {
         // NOTE: header
         let header = JWSHeader {
-            alg: "ES256K".to_string(),
+            alg: context.get_type().alg().to_string(),
             b64: false,
             crit: vec![ "b64".to_string() ],
         };
@@ -45,8 +50,36 @@ impl Jws {
         Ok([ _header, "".to_string(), _signature ].join("."))
     }
 
+    pub fn encode_compact(object: &Value, context: &SigningKey) -> Result<String, NodeXError> {
+        // NOTE: header
+        let header = CompactJWSHeader {
+            alg: context.get_type().alg().to_string(),
+        };
+        let _header = runtime::base64_url::Base64Url::encode(
+            json!(&header).to_string().as_bytes(), &PaddingType::NoPadding
+        );
+
+        // NOTE: payload
+        let _payload = runtime::base64_url::Base64Url::encode(
+            object.to_string().as_bytes(), &PaddingType::NoPadding
+        );
+
+        // NOTE: message
+        let message = [ _header.clone(), _payload.clone() ].join(".");
+
+        // NOTE: signature
+        let signature = match Signer::sign(&message, context) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+        let _signature = runtime::base64_url::Base64Url::encode(
+            &signature, &PaddingType::NoPadding
+        );
+
+        Ok([ _header, _payload, _signature ].join("."))
+    }
 
-    pub fn verify(object: &Value, jws: &str, context: &Secp256k1) -> Result<bool, NodeXError> {
+    pub fn verify(object: &Value, jws: &str, context: &SigningKey) -> Result<bool, NodeXError> {
         let splitted: Vec<String> = jws.to_string().split('.').map(|v| v.to_string()).collect();
 
         if splitted.len() != 3 {
@@ -68,7 +101,8 @@ impl Jws {
             Err(_) => return Err(NodeXError{})
         };
 
-        if header.alg != *"ES256K" {
+        // NOTE: the algorithm must be the one of the verification key
+        if header.alg != context.get_type().alg() {
             return Err(NodeXError{})
         }
         if header.b64 {
@@ -102,6 +136,52 @@ impl Jws {
             Err(_) => Err(NodeXError{})
         }
     }
+
+    // NOTE: returns the payload of a compact JWS signed by the key
+    pub fn decode_compact(jws: &str, context: &SigningKey) -> Result<Value, NodeXError> {
+        let splitted: Vec<&str> = jws.split('.').collect();
+
+        if splitted.len() != 3 {
+            return Err(NodeXError{})
+        }
+
+        // NOTE: header
+        let header = match runtime::base64_url::Base64Url::decode_as_string(splitted[0], &PaddingType::NoPadding) {
+            Ok(v) => {
+                match serde_json::from_str::<CompactJWSHeader>(&v) {
+                    Ok(v) => v,
+                    Err(_) => return Err(NodeXError{}),
+                }
+            },
+            Err(_) => return Err(NodeXError{})
+        };
+
+        if header.alg != context.get_type().alg() {
+            return Err(NodeXError{})
+        }
+
+        // NOTE: signature
+        let signature = match runtime::base64_url::Base64Url::decode_as_bytes(splitted[2], &PaddingType::NoPadding) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+
+        match Signer::verify(&[ splitted[0], splitted[1] ].join("."), &signature, context) {
+            Ok(true) => {},
+            _ => return Err(NodeXError{})
+        };
+
+        // NOTE: payload
+        match runtime::base64_url::Base64Url::decode_as_string(splitted[1], &PaddingType::NoPadding) {
+            Ok(v) => {
+                match serde_json::from_str::<Value>(&v) {
+                    Ok(v) => Ok(v),
+                    Err(_) => Err(NodeXError{}),
+                }
+            },
+            Err(_) => Err(NodeXError{})
+        }
+    }
 }
 
 #[cfg(test)]
@@ -147,7 +227,7 @@ pub mod tests {
             public: public_key(),
             secret: secret_key(),
         }) {
-            Ok(v) => v,
+            Ok(v) => SigningKey::Secp256k1(v),
             Err(_) => panic!()
         };
 
@@ -170,7 +250,7 @@ pub mod tests {
             public: public_key(),
             secret: secret_key(),
         }) {
-            Ok(v) => v,
+            Ok(v) => SigningKey::Secp256k1(v),
             Err(_) => panic!()
         };
 
@@ -186,4 +266,120 @@ pub mod tests {
 
         assert!(result)
     }
-}
\ No newline at end of file
+
+    #[test]
+    pub fn test_encode_compact() {
+        let context = match keyring::secp256k1::Secp256k1::new(&Secp256k1Context {
+            public: public_key(),
+            secret: secret_key(),
+        }) {
+            Ok(v) => SigningKey::Secp256k1(v),
+            Err(_) => panic!()
+        };
+
+        let json: Value = match serde_json::from_str(&message()) {
+            Ok(v) => v,
+            Err(_) => panic!(),
+        };
+
+        let result = match Jws::encode_compact(&json, &context) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        let splitted: Vec<&str> = result.split('.').collect();
+
+        assert_eq!(splitted.len(), 3);
+        assert_eq!(splitted[0], "eyJhbGciOiJFUzI1NksifQ");
+        assert_eq!(splitted[1], "eyJrIjoiMDEyMzQ1Njc4OWFiY2RlZiJ9");
+
+        let signature = match runtime::base64_url::Base64Url::decode_as_bytes(splitted[2], &PaddingType::NoPadding) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+        let verified = match Signer::verify(&[ splitted[0], splitted[1] ].join("."), &signature, &context) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert!(verified)
+    }
+
+    #[test]
+    pub fn test_decode_compact() {
+        let context = match keyring::secp256k1::Secp256k1::new(&Secp256k1Context {
+            public: public_key(),
+            secret: secret_key(),
+        }) {
+            Ok(v) => SigningKey::Secp256k1(v),
+            Err(_) => panic!()
+        };
+
+        let json: Value = match serde_json::from_str(&message()) {
+            Ok(v) => v,
+            Err(_) => panic!(),
+        };
+
+        let result = match Jws::encode_compact(&json, &context) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert_eq!(Jws::decode_compact(&result, &context).ok(), Some(json));
+
+        // NOTE: a payload which is not the signed one is rejected
+        let splitted: Vec<&str> = result.split('.').collect();
+        let tampered = [ splitted[0], "eyJrIjoiMCJ9", splitted[2] ].join(".");
+
+        assert!(Jws::decode_compact(&tampered, &context).is_err());
+        assert!(Jws::decode_compact(&signature(), &context).is_err());
+    }
+
+    #[test]
+    pub fn test_encode_ed25519() {
+        let context = SigningKey::Ed25519(keyring::ed25519::Ed25519::new(&secret_key()).unwrap());
+
+        let json: Value = match serde_json::from_str(&message()) {
+            Ok(v) => v,
+            Err(_) => panic!(),
+        };
+
+        let result = match Jws::encode(&json, &context) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        // NOTE: {"alg":"EdDSA","b64":false,"crit":["b64"]}
+        assert!(result.starts_with("eyJhbGciOiJFZERTQSIsImI2NCI6ZmFsc2UsImNyaXQiOlsiYjY0Il19.."));
+        assert!(Jws::verify(&json, &result, &context).unwrap());
+
+        // NOTE: a signature of another curve is rejected by its header
+        let secp256k1 = SigningKey::Secp256k1(keyring::secp256k1::Secp256k1::new(&Secp256k1Context {
+            public: public_key(),
+            secret: secret_key(),
+        }).unwrap());
+
+        assert!(Jws::verify(&json, &signature(), &context).is_err());
+        assert!(Jws::verify(&json, &result, &secp256k1).is_err());
+    }
+
+    #[test]
+    pub fn test_encode_p256() {
+        let context = SigningKey::P256(keyring::p256::P256::new(&secret_key()).unwrap());
+
+        let json: Value = match serde_json::from_str(&message()) {
+            Ok(v) => v,
+            Err(_) => panic!(),
+        };
+
+        let result = match Jws::encode(&json, &context) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        // NOTE: {"alg":"ES256","b64":false,"crit":["b64"]}
+        assert!(result.starts_with("eyJhbGciOiJFUzI1NiIsImI2NCI6ZmFsc2UsImNyaXQiOlsiYjY0Il19.."));
+        assert!(Jws::verify(&json, &result, &context).unwrap());
+        assert!(Jws::verify(&json, &signature(), &context).is_err());
+    }
+}
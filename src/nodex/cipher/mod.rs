@@ -1,4 +1,5 @@
 pub mod jws;
 pub mod hasher;
 pub mod signer;
-pub mod credential_signer;
\ No newline at end of file
+pub mod credential_signer;
+pub mod sealer;
\ No newline at end of file
@@ -0,0 +1,122 @@
+use std::fmt;
+use crate::nodex::{errors::NodeXError, runtime::{aes_gcm_siv::AesGcmSiv, base64_url::{Base64Url, PaddingType}, random::Random, scrypt::Scrypt}};
+
+// NOTE: seals secrets kept at rest, a sealed value is "sealed:v1:" followed by base64url(nonce || cipher text)
+pub struct Sealer {
+    key: Vec<u8>,
+}
+
+impl fmt::Debug for Sealer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sealer").finish_non_exhaustive()
+    }
+}
+
+impl Sealer {
+    const PREFIX: &'static str = "sealed:v1:";
+    const KEY_SIZE: usize = 32;
+    const NONCE_SIZE: usize = 12;
+
+    pub fn new(key: &[u8]) -> Result<Self, NodeXError> {
+        if key.len() != Self::KEY_SIZE {
+            return Err(NodeXError{})
+        }
+
+        Ok(Sealer { key: key.to_vec() })
+    }
+
+    pub fn from_passphrase(passphrase: &str, salt: &[u8], log_n: u8) -> Result<Self, NodeXError> {
+        match Scrypt::derive_key(passphrase.as_bytes(), salt, log_n, Self::KEY_SIZE) {
+            Ok(v) => Self::new(&v),
+            Err(_) => Err(NodeXError{})
+        }
+    }
+
+    pub fn from_hex(key: &str) -> Result<Self, NodeXError> {
+        match hex::decode(key.trim()) {
+            Ok(v) => Self::new(&v),
+            Err(_) => Err(NodeXError{})
+        }
+    }
+
+    pub fn is_sealed(value: &str) -> bool {
+        value.starts_with(Self::PREFIX)
+    }
+
+    pub fn seal(&self, plain_text: &[u8]) -> Result<String, NodeXError> {
+        let nonce = match Random::bytes(&Self::NONCE_SIZE) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        let cipher_text = match AesGcmSiv::encrypt(&self.key, &nonce, plain_text) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        Ok(format!("{}{}", Self::PREFIX, Base64Url::encode(&[ &nonce[..], &cipher_text[..] ].concat(), &PaddingType::NoPadding)))
+    }
+
+    pub fn unseal(&self, value: &str) -> Result<Vec<u8>, NodeXError> {
+        let encoded = match value.strip_prefix(Self::PREFIX) {
+            Some(v) => v,
+            None => return Err(NodeXError{})
+        };
+
+        let decoded = match Base64Url::decode_as_bytes(encoded, &PaddingType::NoPadding) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        if decoded.len() < Self::NONCE_SIZE {
+            return Err(NodeXError{})
+        }
+
+        let (nonce, cipher_text) = decoded.split_at(Self::NONCE_SIZE);
+
+        AesGcmSiv::decrypt(&self.key, nonce, cipher_text)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_and_unseal() {
+        let sealer = match Sealer::new(&[0x01; 32]) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        let sealed = match sealer.seal(b"secret") {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert!(Sealer::is_sealed(&sealed));
+        assert!(!sealed.contains("secret"));
+        assert_eq!(sealer.unseal(&sealed).ok(), Some(b"secret".to_vec()));
+
+        // NOTE: every seal uses a fresh nonce
+        assert_ne!(sealer.seal(b"secret").ok(), Some(sealed));
+    }
+
+    #[test]
+    fn test_unseal_with_other_key() {
+        let sealed = match Sealer::new(&[0x01; 32]).and_then(|v| v.seal(b"secret")) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert!(Sealer::new(&[0x02; 32]).and_then(|v| v.unseal(&sealed)).is_err());
+        assert!(Sealer::from_passphrase("passphrase", b"salt", 4).and_then(|v| v.unseal(&sealed)).is_err());
+    }
+
+    #[test]
+    fn test_new_with_invalid_key() {
+        assert!(Sealer::new(&[0x01; 16]).is_err());
+        assert!(Sealer::from_hex("zz").is_err());
+        assert!(Sealer::new(&[0x01; 32]).and_then(|v| v.unseal("secret")).is_err());
+    }
+}
@@ -1,18 +1,25 @@
 
-use crate::nodex::{runtime, keyring::secp256k1::Secp256k1, errors::NodeXError};
+use crate::nodex::{runtime, keyring::signing_key::SigningKey, errors::NodeXError, extension::secure_signer::SecureSigner};
 
 pub struct Signer {}
 
 impl Signer {
-    pub fn sign(message: &str, context: &Secp256k1) -> Result<Vec<u8>, NodeXError> {
-        match runtime::secp256k1::Secp256k1::ecdsa_sign(message.as_bytes(), &context.get_secret_key()) {
+    // NOTE: keys held by a secure element are signed there
+    pub fn sign(message: &str, context: &SigningKey) -> Result<Vec<u8>, NodeXError> {
+        match SecureSigner::new().sign(context, message.as_bytes()) {
             Ok(v) => Ok(v),
             Err(_) => Err(NodeXError{})
         }
     }
 
-    pub fn verify(message: &str, signature: &[u8], context: &Secp256k1) -> Result<bool, NodeXError> {
-        match runtime::secp256k1::Secp256k1::ecdsa_verify(signature, message.as_bytes(), &context.get_public_key()) {
+    pub fn verify(message: &str, signature: &[u8], context: &SigningKey) -> Result<bool, NodeXError> {
+        let verified = match context {
+            SigningKey::Secp256k1(v) => runtime::secp256k1::Secp256k1::ecdsa_verify(signature, message.as_bytes(), &v.get_public_key()),
+            SigningKey::Ed25519(v) => runtime::ed25519::Ed25519::eddsa_verify(signature, message.as_bytes(), &v.get_public_key()),
+            SigningKey::P256(v) => runtime::p256::P256::ecdsa_verify(signature, message.as_bytes(), &v.get_public_key()),
+        };
+
+        match verified {
             Ok(v) => Ok(v),
             Err(_) => Err(NodeXError{})
         }
@@ -70,7 +77,7 @@ pub mod tests {
             public: public_key(),
             secret: secret_key(),
         }) {
-            Ok(v) => v,
+            Ok(v) => SigningKey::Secp256k1(v),
             Err(_) => panic!()
         };
 
@@ -88,7 +95,7 @@ pub mod tests {
             public: public_key(),
             secret: secret_key(),
         }) {
-            Ok(v) => v,
+            Ok(v) => SigningKey::Secp256k1(v),
             Err(_) => panic!()
         };
 
@@ -99,4 +106,36 @@ pub mod tests {
 
         assert!(result)
     }
-}
\ No newline at end of file
+
+    #[test]
+    pub fn test_sign_ed25519() {
+        // NOTE: RFC 8032, 7.1 (TEST 1)
+        let secret = hex::decode("9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60").unwrap();
+        let context = SigningKey::Ed25519(keyring::ed25519::Ed25519::new(&secret).unwrap());
+
+        let result = match Signer::sign("", &context) {
+            Ok(v) => v,
+            Err(_) => panic!(),
+        };
+
+        assert_eq!(hex::encode(&result), "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b");
+        assert!(Signer::verify("", &result, &context).unwrap());
+        assert!(!Signer::verify("x", &result, &context).unwrap_or(false));
+    }
+
+    #[test]
+    pub fn test_sign_p256() {
+        // NOTE: RFC 6979, A.2.5 (P-256, SHA-256, "sample")
+        let secret = hex::decode("c9afa9d845ba75166b5c215767b1d6934e50c3db36e89b127b8a622b120f6721").unwrap();
+        let context = SigningKey::P256(keyring::p256::P256::new(&secret).unwrap());
+
+        let result = match Signer::sign("sample", &context) {
+            Ok(v) => v,
+            Err(_) => panic!(),
+        };
+
+        assert_eq!(hex::encode(&result), "efd48b2aacb6a8fd1140dd9cd45e81d69d2c877b56aaf991c34d0ea84eaf3716f7cb1c942d657c41d436c7a1b6e29f65f3e900dbb9aff4064dc4ab2f843acda8");
+        assert!(Signer::verify("sample", &result, &context).unwrap());
+        assert!(!Signer::verify("test", &result, &context).unwrap_or(false));
+    }
+}
@@ -1,2 +1,13 @@
 #[derive(Debug)]
-pub struct NodeXError {}
\ No newline at end of file
+pub struct NodeXError {}
+
+#[derive(Debug, PartialEq)]
+pub enum TrngError {
+    // NOTE: the extension failed or returned less than requested
+    Read,
+
+    // NOTE: the repetition count or the adaptive proportion test (NIST SP 800-90B, 4.4) failed
+    HealthTest,
+
+    Drbg,
+}
\ No newline at end of file
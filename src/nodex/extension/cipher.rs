@@ -0,0 +1,136 @@
+use crate::{config::Extension, nodex::{cipher::sealer::Sealer, errors::NodeXError, runtime::base64_url::{Base64Url, PaddingType}}};
+
+use super::{host::ExtensionCall, manager::{extension_manager, Capability}};
+
+// NOTE: wraps secrets before they are persisted, a value wrapped by the extension is "sealed:ext:v1:" followed by base64url(cipher text)
+#[derive(Debug, Default)]
+pub struct Cipher {
+    encrypt: Option<Extension>,
+    decrypt: Option<Extension>,
+    sealer: Option<Sealer>,
+}
+
+impl Cipher {
+    const MAX_BUFFER_LENGTH: usize = 1024;
+    const PREFIX: &'static str = "sealed:ext:v1:";
+
+    // NOTE: the sealer is the pure-Rust fallback used when no extension is configured
+    pub fn new(encrypt: Option<Extension>, decrypt: Option<Extension>, sealer: Option<Sealer>) -> Cipher {
+        Cipher { encrypt, decrypt, sealer }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.encrypt.is_some() || self.sealer.is_some()
+    }
+
+    pub fn is_sealed(value: &str) -> bool {
+        value.starts_with(Self::PREFIX) || Sealer::is_sealed(value)
+    }
+
+    fn call_external(&self, extension: &Extension, input: &[u8]) -> Result<Vec<u8>, NodeXError> {
+        if Cipher::MAX_BUFFER_LENGTH < input.len() {
+            return Err(NodeXError {})
+        }
+
+        match extension_manager().call(extension, Capability::Cipher, &ExtensionCall::Bytes { input: hex::encode(input) }) {
+            Ok(v) => {
+                match v.into_iter().next() {
+                    Some(v) => Ok(v),
+                    None => Err(NodeXError {})
+                }
+            },
+            Err(_) => Err(NodeXError {})
+        }
+    }
+
+    fn encrypt_external(&self, extension: &Extension, plain_text: &[u8]) -> Result<String, NodeXError> {
+        log::info!("Called: encrypt_external");
+
+        match self.call_external(extension, plain_text) {
+            Ok(v) => Ok(format!("{}{}", Self::PREFIX, Base64Url::encode(&v, &PaddingType::NoPadding))),
+            Err(_) => Err(NodeXError{})
+        }
+    }
+
+    fn decrypt_external(&self, extension: &Extension, encoded: &str) -> Result<Vec<u8>, NodeXError> {
+        log::info!("Called: decrypt_external");
+
+        let cipher_text = match Base64Url::decode_as_bytes(encoded, &PaddingType::NoPadding) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        self.call_external(extension, &cipher_text)
+    }
+
+    fn encrypt_internal(&self, plain_text: &[u8]) -> Result<String, NodeXError> {
+        match &self.sealer {
+            Some(v) => v.seal(plain_text),
+            None => Err(NodeXError{})
+        }
+    }
+
+    fn decrypt_internal(&self, value: &str) -> Result<Vec<u8>, NodeXError> {
+        match &self.sealer {
+            Some(v) => v.unseal(value),
+            None => Err(NodeXError{})
+        }
+    }
+
+    pub fn encrypt(&self, plain_text: &[u8]) -> Result<String, NodeXError> {
+        match &self.encrypt {
+            Some(v) => {
+                self.encrypt_external(v, plain_text)
+            },
+            _ => {
+                self.encrypt_internal(plain_text)
+            }
+        }
+    }
+
+    // NOTE: values sealed by the fallback stay readable after an extension is configured
+    pub fn decrypt(&self, value: &str) -> Result<Vec<u8>, NodeXError> {
+        match (value.strip_prefix(Self::PREFIX), &self.decrypt) {
+            (Some(encoded), Some(v)) => {
+                self.decrypt_external(v, encoded)
+            },
+            (Some(_), None) => Err(NodeXError{}),
+            _ => {
+                self.decrypt_internal(value)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    pub fn it_should_success_encrypt_internal() {
+        let sealer = match Sealer::new(&[0u8; 32]) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        let cipher = Cipher::new(None, None, Some(sealer));
+
+        let sealed = match cipher.encrypt(b"abandon abandon") {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert!(cipher.is_enabled());
+        assert!(Cipher::is_sealed(&sealed));
+        assert_eq!(cipher.decrypt(&sealed).ok(), Some(b"abandon abandon".to_vec()));
+    }
+
+    #[test]
+    pub fn it_should_fail_without_extension_or_sealer() {
+        let cipher = Cipher::default();
+
+        assert!(!cipher.is_enabled());
+        assert!(cipher.encrypt(b"abandon").is_err());
+        assert!(cipher.decrypt("sealed:ext:v1:AAAA").is_err());
+    }
+}
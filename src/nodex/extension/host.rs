@@ -0,0 +1,518 @@
+use std::{ffi::CStr, fs::File, io::{self, Read, Write}, os::unix::io::{AsRawFd, FromRawFd}, path::PathBuf, process::{Child, ChildStdin, Command, Stdio}, sync::{mpsc, Mutex}, thread, time::Duration};
+use serde::{Deserialize, Serialize};
+use crate::nodex::errors::NodeXError;
+use super::{manager::{ExtensionInfo, ExtensionManager, LoadedExtension}, secure_keystore::{SecureKeyStoreType, SecureKeystoreType}};
+
+const MAX_BUFFER_LENGTH: usize = 1024;
+const MAX_FRAME_LENGTH: usize = 1024 * 1024;
+
+// NOTE: an extension function, binary values are hex encoded
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ExtensionCall {
+    // NOTE: only checks that the symbol is exported
+    #[serde(rename = "probe")]
+    Probe,
+
+    #[serde(rename = "read")]
+    Read { size: usize },
+
+    #[serde(rename = "read_key_pair")]
+    ReadKeyPair { key_type: SecureKeyStoreType },
+
+    #[serde(rename = "generate_key_pair")]
+    GenerateKeyPair { key_type: SecureKeyStoreType },
+
+    #[serde(rename = "bytes")]
+    Bytes { input: String },
+
+    #[serde(rename = "keyed_bytes")]
+    KeyedBytes { key_type: SecureKeyStoreType, input: String },
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct ExtensionRequest {
+    symbol: String,
+    call: ExtensionCall,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct ExtensionReply {
+    ok: bool,
+
+    #[serde(default)]
+    data: Vec<String>,
+
+    // NOTE: sent once by the helper process when the library is loaded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    abi_version: Option<u32>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    capabilities: Option<u32>,
+}
+
+// NOTE: a frame is a 4-byte big-endian length followed by a JSON payload
+pub fn write_frame<W: Write>(writer: &mut W, payload: &[u8]) -> io::Result<()> {
+    writer.write_all(&(payload.len() as u32).to_be_bytes())?;
+    writer.write_all(payload)?;
+    writer.flush()
+}
+
+pub fn read_frame<R: Read>(reader: &mut R) -> io::Result<Vec<u8>> {
+    let mut header = [0u8; 4];
+    reader.read_exact(&mut header)?;
+
+    let len = u32::from_be_bytes(header) as usize;
+
+    if MAX_FRAME_LENGTH < len {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "frame is too large"))
+    }
+
+    let mut payload = vec![0u8; len];
+    reader.read_exact(&mut payload)?;
+
+    Ok(payload)
+}
+
+fn invoke_read(extension: &LoadedExtension, symbol: &str, size: usize) -> Result<Vec<Vec<u8>>, NodeXError> {
+    if MAX_BUFFER_LENGTH < size {
+        return Err(NodeXError {})
+    }
+
+    unsafe {
+        let buffer = [0u8; MAX_BUFFER_LENGTH + 1];
+        let buffer_ptr: *const i8 = buffer.as_ptr().cast();
+
+        let func: libloading::Symbol<unsafe extern "C" fn(buf: *const i8, bufsize: usize, size: usize) -> u32> = match extension.get(symbol.as_bytes()) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        let result = func(buffer_ptr, buffer.len(), size);
+
+        if result != 0 {
+            return Err(NodeXError {})
+        }
+
+        // NOTE: random bytes may contain zeros, so exactly the requested size is returned
+        Ok(vec![ buffer[..size].to_vec() ])
+    }
+}
+
+fn invoke_read_key_pair(extension: &LoadedExtension, symbol: &str, key_type: &SecureKeyStoreType) -> Result<Vec<Vec<u8>>, NodeXError> {
+    unsafe {
+        let secret_key_buffer = [0u8; MAX_BUFFER_LENGTH + 1];
+        let public_key_buffer = [0u8; MAX_BUFFER_LENGTH + 1];
+
+        let secret_key_buffer_ptr: *const i8 = secret_key_buffer.as_ptr().cast();
+        let public_key_buffer_ptr: *const i8 = public_key_buffer.as_ptr().cast();
+
+        let secret_key_len = 0;
+        let public_key_len = 0;
+
+        let func: libloading::Symbol<unsafe extern "C" fn(key_type: SecureKeystoreType, secret_key_buffer: *const i8, public_key_buffer: *const i8, secret_key_buffer_len: usize, public_key_buffer_len: usize, secret_key_len: *const usize, public_key_len: *const usize) -> u32> = match extension.get(symbol.as_bytes()) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        let result = func(key_type.into(), secret_key_buffer_ptr, public_key_buffer_ptr, secret_key_buffer.len(), public_key_buffer.len(), &secret_key_len, &public_key_len);
+
+        if result != 0 {
+            return Err(NodeXError{})
+        }
+
+        let secret_key = match CStr::from_ptr(secret_key_buffer_ptr).to_str() {
+            Ok(v) => {
+                match hex::decode(v) {
+                    Ok(v) => v,
+                    _ => return Err(NodeXError {})
+                }
+            },
+            _ => return Err(NodeXError {})
+        };
+        let public_key = match CStr::from_ptr(public_key_buffer_ptr).to_str() {
+            Ok(v) => {
+                match hex::decode(v) {
+                    Ok(v) => v,
+                    _ => return Err(NodeXError {})
+                }
+            }
+            _ => return Err(NodeXError {})
+        };
+
+        Ok(vec![ secret_key, public_key ])
+    }
+}
+
+// NOTE: the secret key is created and kept by the library, only the public key (hex) comes back
+fn invoke_generate_key_pair(extension: &LoadedExtension, symbol: &str, key_type: &SecureKeyStoreType) -> Result<Vec<Vec<u8>>, NodeXError> {
+    unsafe {
+        let public_key_buffer = [0u8; MAX_BUFFER_LENGTH + 1];
+        let public_key_buffer_ptr: *const i8 = public_key_buffer.as_ptr().cast();
+
+        let public_key_len = 0;
+
+        let func: libloading::Symbol<unsafe extern "C" fn(key_type: SecureKeystoreType, public_key_buffer: *const i8, public_key_buffer_len: usize, public_key_len: *const usize) -> u32> = match extension.get(symbol.as_bytes()) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        let result = func(key_type.into(), public_key_buffer_ptr, public_key_buffer.len(), &public_key_len);
+
+        if result != 0 {
+            return Err(NodeXError{})
+        }
+
+        match CStr::from_ptr(public_key_buffer_ptr).to_str() {
+            Ok(v) => {
+                match hex::decode(v) {
+                    Ok(v) => Ok(vec![ v ]),
+                    _ => Err(NodeXError {})
+                }
+            },
+            _ => Err(NodeXError {})
+        }
+    }
+}
+
+fn invoke_bytes(extension: &LoadedExtension, symbol: &str, key_type: Option<&SecureKeyStoreType>, input: &str) -> Result<Vec<Vec<u8>>, NodeXError> {
+    let input = match hex::decode(input) {
+        Ok(v) => v,
+        Err(_) => return Err(NodeXError{})
+    };
+
+    if MAX_BUFFER_LENGTH < input.len() {
+        return Err(NodeXError {})
+    }
+
+    unsafe {
+        let mut buffer = [0u8; MAX_BUFFER_LENGTH];
+        let mut output_len: usize = 0;
+
+        let result = match key_type {
+            Some(key_type) => {
+                let func: libloading::Symbol<unsafe extern "C" fn(key_type: SecureKeystoreType, input: *const u8, input_len: usize, output: *mut u8, output_buffer_len: usize, output_len: *mut usize) -> u32> = match extension.get(symbol.as_bytes()) {
+                    Ok(v) => v,
+                    Err(_) => return Err(NodeXError{})
+                };
+
+                func(key_type.into(), input.as_ptr(), input.len(), buffer.as_mut_ptr(), buffer.len(), &mut output_len)
+            },
+            None => {
+                let func: libloading::Symbol<unsafe extern "C" fn(input: *const u8, input_len: usize, output: *mut u8, output_buffer_len: usize, output_len: *mut usize) -> u32> = match extension.get(symbol.as_bytes()) {
+                    Ok(v) => v,
+                    Err(_) => return Err(NodeXError{})
+                };
+
+                func(input.as_ptr(), input.len(), buffer.as_mut_ptr(), buffer.len(), &mut output_len)
+            },
+        };
+
+        if result != 0 || buffer.len() < output_len {
+            return Err(NodeXError {})
+        }
+
+        Ok(vec![ buffer[..output_len].to_vec() ])
+    }
+}
+
+// NOTE: calls the library in this process
+pub fn invoke(extension: &LoadedExtension, symbol: &str, call: &ExtensionCall) -> Result<Vec<Vec<u8>>, NodeXError> {
+    match call {
+        ExtensionCall::Probe => {
+            match unsafe { extension.get::<unsafe extern "C" fn()>(symbol.as_bytes()) } {
+                Ok(_) => Ok(vec![]),
+                Err(_) => Err(NodeXError{})
+            }
+        },
+        ExtensionCall::Read { size } => invoke_read(extension, symbol, *size),
+        ExtensionCall::ReadKeyPair { key_type } => invoke_read_key_pair(extension, symbol, key_type),
+        ExtensionCall::GenerateKeyPair { key_type } => invoke_generate_key_pair(extension, symbol, key_type),
+        ExtensionCall::Bytes { input } => invoke_bytes(extension, symbol, None, input),
+        ExtensionCall::KeyedBytes { key_type, input } => invoke_bytes(extension, symbol, Some(key_type), input),
+    }
+}
+
+fn send<W: Write>(writer: &mut W, reply: &ExtensionReply) -> io::Result<()> {
+    match serde_json::to_vec(reply) {
+        Ok(v) => write_frame(writer, &v),
+        Err(e) => Err(io::Error::new(io::ErrorKind::InvalidData, e)),
+    }
+}
+
+// NOTE: a library printing to stdout (or reading stdin) would corrupt the frames, so the frames go over duplicates of the
+//       pipes and fd 1 is redirected to stderr, fd 0 to /dev/null, before the library is loaded
+fn protocol_pipes() -> io::Result<(File, File)> {
+    let null = File::open("/dev/null")?;
+
+    unsafe {
+        let reader = libc::fcntl(libc::STDIN_FILENO, libc::F_DUPFD_CLOEXEC, 0);
+        if reader < 0 {
+            return Err(io::Error::last_os_error())
+        }
+        let reader = File::from_raw_fd(reader);
+
+        let writer = libc::fcntl(libc::STDOUT_FILENO, libc::F_DUPFD_CLOEXEC, 0);
+        if writer < 0 {
+            return Err(io::Error::last_os_error())
+        }
+        let writer = File::from_raw_fd(writer);
+
+        if libc::dup2(libc::STDERR_FILENO, libc::STDOUT_FILENO) < 0 || libc::dup2(null.as_raw_fd(), libc::STDIN_FILENO) < 0 {
+            return Err(io::Error::last_os_error())
+        }
+
+        Ok((reader, writer))
+    }
+}
+
+// NOTE: the helper process, it loads one library and answers requests from the agent until the agent closes the pipe
+pub fn serve(filename: &str) -> i32 {
+    let (mut reader, mut writer) = match protocol_pipes() {
+        Ok(v) => v,
+        Err(_) => return 1,
+    };
+
+    let extension = match ExtensionManager::open(filename) {
+        Ok(v) => v,
+        Err(_) => {
+            let _ = send(&mut writer, &ExtensionReply::default());
+            return 1
+        }
+    };
+
+    let hello = ExtensionReply {
+        ok: true,
+        abi_version: Some(extension.info().abi_version),
+        capabilities: Some(extension.capabilities()),
+        ..Default::default()
+    };
+
+    if send(&mut writer, &hello).is_err() {
+        return 1
+    }
+
+    loop {
+        let frame = match read_frame(&mut reader) {
+            Ok(v) => v,
+            Err(_) => return 0,
+        };
+
+        let reply = match serde_json::from_slice::<ExtensionRequest>(&frame) {
+            Ok(request) => {
+                match invoke(&extension, &request.symbol, &request.call) {
+                    Ok(v) => ExtensionReply {
+                        ok: true,
+                        data: v.iter().map(hex::encode).collect(),
+                        ..Default::default()
+                    },
+                    Err(_) => ExtensionReply::default(),
+                }
+            },
+            Err(_) => ExtensionReply::default(),
+        };
+
+        if send(&mut writer, &reply).is_err() {
+            return 1
+        }
+    }
+}
+
+struct HostProcess {
+    child: Child,
+    stdin: ChildStdin,
+    replies: mpsc::Receiver<ExtensionReply>,
+}
+
+impl HostProcess {
+    fn request(&mut self, request: &ExtensionRequest, timeout: Duration) -> Result<ExtensionReply, NodeXError> {
+        let payload = match serde_json::to_vec(request) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        if write_frame(&mut self.stdin, &payload).is_err() {
+            return Err(NodeXError{})
+        }
+
+        match self.replies.recv_timeout(timeout) {
+            Ok(v) => Ok(v),
+            Err(_) => Err(NodeXError{})
+        }
+    }
+}
+
+impl Drop for HostProcess {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+// NOTE: a library running in a helper process, a crash or a hang of the library never reaches the agent
+pub struct IsolatedExtension {
+    program: PathBuf,
+    args: Vec<String>,
+    timeout: Duration,
+    info: ExtensionInfo,
+    capabilities: u32,
+    process: Mutex<Option<HostProcess>>,
+}
+
+impl IsolatedExtension {
+    fn spawn(program: &PathBuf, args: &[String], timeout: Duration) -> Result<(HostProcess, ExtensionReply), NodeXError> {
+        let mut child = match Command::new(program).args(args).stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::inherit()).spawn() {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        let (stdin, mut stdout) = match (child.stdin.take(), child.stdout.take()) {
+            (Some(stdin), Some(stdout)) => (stdin, stdout),
+            _ => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(NodeXError{})
+            }
+        };
+
+        let (tx, rx) = mpsc::channel::<ExtensionReply>();
+
+        thread::spawn(move || {
+            while let Ok(frame) = read_frame(&mut stdout) {
+                let reply = match serde_json::from_slice::<ExtensionReply>(&frame) {
+                    Ok(v) => v,
+                    Err(_) => break,
+                };
+
+                if tx.send(reply).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let process = HostProcess { child, stdin, replies: rx };
+
+        match process.replies.recv_timeout(timeout) {
+            Ok(hello) if hello.ok => Ok((process, hello)),
+            _ => Err(NodeXError{})
+        }
+    }
+
+    pub fn start(program: PathBuf, args: Vec<String>, filename: &str, timeout: Duration) -> Result<Self, NodeXError> {
+        let (process, hello) = match Self::spawn(&program, &args, timeout) {
+            Ok(v) => v,
+            Err(_) => {
+                log::error!("unable to start the helper process of extension {}", filename);
+                return Err(NodeXError{})
+            }
+        };
+
+        let capabilities = hello.capabilities.unwrap_or_default();
+
+        Ok(IsolatedExtension {
+            program,
+            args,
+            timeout,
+            info: ExtensionInfo::new(filename, hello.abi_version.unwrap_or_default(), capabilities, true),
+            capabilities,
+            process: Mutex::new(Some(process)),
+        })
+    }
+
+    pub fn info(&self) -> &ExtensionInfo {
+        &self.info
+    }
+
+    pub fn capabilities(&self) -> u32 {
+        self.capabilities
+    }
+
+    pub fn call(&self, symbol: &str, call: &ExtensionCall) -> Result<Vec<Vec<u8>>, NodeXError> {
+        let mut process = self.process.lock().unwrap();
+
+        // NOTE: a helper process which crashed or timed out is restarted on the next call
+        if process.is_none() {
+            log::warn!("restarting the helper process of extension {}", self.info.filename);
+
+            *process = match Self::spawn(&self.program, &self.args, self.timeout) {
+                Ok((v, _)) => Some(v),
+                Err(_) => return Err(NodeXError{})
+            };
+        }
+
+        let request = ExtensionRequest {
+            symbol: symbol.to_string(),
+            call: call.clone(),
+        };
+
+        let reply = match process.as_mut() {
+            Some(v) => v.request(&request, self.timeout),
+            None => Err(NodeXError{}),
+        };
+
+        let reply = match reply {
+            Ok(v) => v,
+            Err(_) => {
+                log::error!("the helper process of extension {} stopped responding", self.info.filename);
+                *process = None;
+                return Err(NodeXError{})
+            }
+        };
+
+        if !reply.ok {
+            return Err(NodeXError{})
+        }
+
+        let mut data = vec![];
+
+        for v in reply.data.iter() {
+            match hex::decode(v) {
+                Ok(v) => data.push(v),
+                Err(_) => return Err(NodeXError{})
+            };
+        }
+
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_frame() {
+        let mut buffer = vec![];
+
+        write_frame(&mut buffer, b"{\"ok\":true}").unwrap();
+        write_frame(&mut buffer, b"").unwrap();
+
+        let mut reader = Cursor::new(buffer);
+
+        assert_eq!(read_frame(&mut reader).unwrap(), b"{\"ok\":true}".to_vec());
+        assert_eq!(read_frame(&mut reader).unwrap(), b"".to_vec());
+        assert!(read_frame(&mut reader).is_err());
+
+        let mut reader = Cursor::new(u32::MAX.to_be_bytes().to_vec());
+        assert!(read_frame(&mut reader).is_err());
+    }
+
+    #[test]
+    fn test_call_serialization() {
+        let call = ExtensionCall::KeyedBytes { key_type: SecureKeyStoreType::Sign, input: "00ff".to_string() };
+        let value = serde_json::to_value(&call).unwrap();
+
+        assert_eq!(value["type"], "keyed_bytes");
+        assert_eq!(serde_json::from_value::<ExtensionCall>(value).unwrap(), call);
+    }
+
+    #[test]
+    fn test_start_timeout() {
+        // NOTE: a helper which never answers is given up after the timeout
+        let result = IsolatedExtension::start(PathBuf::from("sleep"), vec![ "5".to_string() ], "libnodex-test.so", Duration::from_millis(100));
+
+        assert!(result.is_err());
+        assert!(IsolatedExtension::start(PathBuf::from("/nonexistent/nodex-agent"), vec![], "libnodex-test.so", Duration::from_millis(100)).is_err());
+    }
+}
@@ -0,0 +1,296 @@
+use std::{collections::HashMap, sync::{Arc, Mutex, OnceLock}, time::Duration};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use crate::{config::{Extension, ExtensionIsolationConfig}, nodex::errors::NodeXError};
+use super::host::{self, ExtensionCall, IsolatedExtension};
+
+// NOTE: every extension library exports these two symbols
+const ABI_VERSION_SYMBOL: &str = "nodex_extension_abi_version";
+const CAPABILITIES_SYMBOL: &str = "nodex_extension_capabilities";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Capability {
+    Trng = 0x01,
+    SecureKeyStore = 0x02,
+    Cipher = 0x04,
+    SecureSigner = 0x08,
+}
+
+impl Capability {
+    const ALL: [Capability; 4] = [ Capability::Trng, Capability::SecureKeyStore, Capability::Cipher, Capability::SecureSigner ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Capability::Trng => "trng",
+            Capability::SecureKeyStore => "secure_keystore",
+            Capability::Cipher => "cipher",
+            Capability::SecureSigner => "secure_signer",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().find(|v| v.name() == name).copied()
+    }
+
+    pub fn names(bits: u32) -> Vec<String> {
+        Self::ALL.iter().filter(|v| bits & (**v as u32) != 0).map(|v| v.name().to_string()).collect()
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ExtensionInfo {
+    #[serde(rename = "filename")]
+    pub filename: String,
+
+    #[serde(rename = "abiVersion")]
+    pub abi_version: u32,
+
+    #[serde(rename = "capabilities")]
+    pub capabilities: Vec<String>,
+
+    #[serde(rename = "isolated")]
+    pub isolated: bool,
+
+    #[serde(rename = "loadedAt")]
+    pub loaded_at: i64,
+}
+
+impl ExtensionInfo {
+    pub fn new(filename: &str, abi_version: u32, capabilities: u32, isolated: bool) -> Self {
+        ExtensionInfo {
+            filename: filename.to_string(),
+            abi_version,
+            capabilities: Capability::names(capabilities),
+            isolated,
+            loaded_at: Utc::now().timestamp(),
+        }
+    }
+}
+
+pub struct LoadedExtension {
+    library: libloading::Library,
+    capabilities: u32,
+    info: ExtensionInfo,
+}
+
+impl LoadedExtension {
+    pub fn info(&self) -> &ExtensionInfo {
+        &self.info
+    }
+
+    pub fn capabilities(&self) -> u32 {
+        self.capabilities
+    }
+
+    /// # Safety
+    ///
+    /// The type must match the signature of the exported symbol.
+    pub unsafe fn get<T>(&self, symbol: &[u8]) -> Result<libloading::Symbol<'_, T>, NodeXError> {
+        match self.library.get(symbol) {
+            Ok(v) => Ok(v),
+            Err(_) => {
+                log::error!("extension {} has no symbol {}", self.info.filename, String::from_utf8_lossy(symbol));
+                Err(NodeXError{})
+            }
+        }
+    }
+}
+
+fn supports(capabilities: u32, capability: Capability) -> bool {
+    capabilities & (capability as u32) != 0
+}
+
+pub struct ExtensionManager {
+    extensions: Mutex<HashMap<String, Arc<LoadedExtension>>>,
+    hosts: Mutex<HashMap<String, Arc<IsolatedExtension>>>,
+    isolation: Mutex<ExtensionIsolationConfig>,
+}
+
+pub fn extension_manager() -> Arc<ExtensionManager> {
+    static SINGLETON: OnceLock<Arc<ExtensionManager>> = OnceLock::new();
+
+    SINGLETON.get_or_init(|| {
+        Arc::new(ExtensionManager::new())
+    }).clone()
+}
+
+impl ExtensionManager {
+    pub const ABI_VERSION: u32 = 1;
+
+    pub fn new() -> Self {
+        ExtensionManager {
+            extensions: Mutex::new(HashMap::new()),
+            hosts: Mutex::new(HashMap::new()),
+            isolation: Mutex::new(ExtensionIsolationConfig::default()),
+        }
+    }
+
+    pub fn configure(&self, isolation: &ExtensionIsolationConfig) {
+        *self.isolation.lock().unwrap() = isolation.clone();
+    }
+
+    pub(super) fn open(filename: &str) -> Result<LoadedExtension, NodeXError> {
+        unsafe {
+            let library = match libloading::Library::new(filename) {
+                Ok(v) => v,
+                Err(e) => {
+                    log::error!("unable to load extension {}: {}", filename, e);
+                    return Err(NodeXError{})
+                }
+            };
+
+            let abi_version = match library.get::<unsafe extern "C" fn() -> u32>(ABI_VERSION_SYMBOL.as_bytes()) {
+                Ok(v) => v(),
+                Err(_) => {
+                    log::error!("extension {} does not export {}", filename, ABI_VERSION_SYMBOL);
+                    return Err(NodeXError{})
+                }
+            };
+
+            if abi_version != Self::ABI_VERSION {
+                log::error!("extension {} implements ABI version {}, but version {} is required", filename, abi_version, Self::ABI_VERSION);
+                return Err(NodeXError{})
+            }
+
+            let capabilities = match library.get::<unsafe extern "C" fn() -> u32>(CAPABILITIES_SYMBOL.as_bytes()) {
+                Ok(v) => v(),
+                Err(_) => {
+                    log::error!("extension {} does not export {}", filename, CAPABILITIES_SYMBOL);
+                    return Err(NodeXError{})
+                }
+            };
+
+            log::info!("loaded extension {} (ABI version: {}, capabilities: {:?})", filename, abi_version, Capability::names(capabilities));
+
+            Ok(LoadedExtension {
+                library,
+                capabilities,
+                info: ExtensionInfo::new(filename, abi_version, capabilities, false),
+            })
+        }
+    }
+
+    // NOTE: a library is opened and checked once, then shared by every extension using it
+    pub fn load(&self, extension: &Extension, capability: Capability) -> Result<Arc<LoadedExtension>, NodeXError> {
+        let mut extensions = self.extensions.lock().unwrap();
+
+        let loaded = match extensions.get(&extension.filename) {
+            Some(v) => Arc::clone(v),
+            None => {
+                let loaded = match Self::open(&extension.filename) {
+                    Ok(v) => Arc::new(v),
+                    Err(_) => return Err(NodeXError{})
+                };
+
+                extensions.insert(extension.filename.clone(), Arc::clone(&loaded));
+                loaded
+            },
+        };
+
+        if !supports(loaded.capabilities(), capability) {
+            log::error!("extension {} does not support {}", extension.filename, capability.name());
+            return Err(NodeXError{})
+        }
+
+        Ok(loaded)
+    }
+
+    // NOTE: the agent binary itself serves the library in the helper process
+    fn host(&self, extension: &Extension, capability: Capability, timeout: Duration) -> Result<Arc<IsolatedExtension>, NodeXError> {
+        let mut hosts = self.hosts.lock().unwrap();
+
+        let isolated = match hosts.get(&extension.filename) {
+            Some(v) => Arc::clone(v),
+            None => {
+                let program = match std::env::current_exe() {
+                    Ok(v) => v,
+                    Err(_) => return Err(NodeXError{})
+                };
+                let args = vec![ "extension-host".to_string(), extension.filename.clone() ];
+
+                let isolated = match IsolatedExtension::start(program, args, &extension.filename, timeout) {
+                    Ok(v) => Arc::new(v),
+                    Err(_) => return Err(NodeXError{})
+                };
+
+                hosts.insert(extension.filename.clone(), Arc::clone(&isolated));
+                isolated
+            },
+        };
+
+        if !supports(isolated.capabilities(), capability) {
+            log::error!("extension {} does not support {}", extension.filename, capability.name());
+            return Err(NodeXError{})
+        }
+
+        Ok(isolated)
+    }
+
+    pub fn call(&self, extension: &Extension, capability: Capability, call: &ExtensionCall) -> Result<Vec<Vec<u8>>, NodeXError> {
+        let isolation = self.isolation.lock().unwrap().clone();
+
+        if isolation.enabled {
+            return match self.host(extension, capability, Duration::from_millis(isolation.timeout)) {
+                Ok(v) => v.call(&extension.symbol, call),
+                Err(_) => Err(NodeXError{})
+            }
+        }
+
+        match self.load(extension, capability) {
+            Ok(v) => host::invoke(&v, &extension.symbol, call),
+            Err(_) => Err(NodeXError{})
+        }
+    }
+
+    // NOTE: called at startup, so a missing symbol or an ABI mismatch stops the agent before any extension is called
+    pub fn load_all(&self, extensions: &[(String, Extension)]) -> Result<(), NodeXError> {
+        for (name, extension) in extensions {
+            let capability = match name.split('.').next().and_then(Capability::from_name) {
+                Some(v) => v,
+                None => return Err(NodeXError{})
+            };
+
+            if self.call(extension, capability, &ExtensionCall::Probe).is_err() {
+                log::error!("unable to load {} from extension {}", name, extension.filename);
+                return Err(NodeXError{})
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn list(&self) -> Vec<ExtensionInfo> {
+        let mut list: Vec<ExtensionInfo> = self.extensions.lock().unwrap().values().map(|v| v.info().clone()).collect();
+
+        list.extend(self.hosts.lock().unwrap().values().map(|v| v.info().clone()));
+        list.sort_by(|a, b| a.filename.cmp(&b.filename));
+        list
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case(0x00, vec![])]
+    #[case(0x01, vec![ "trng" ])]
+    #[case(0x0e, vec![ "secure_keystore", "cipher", "secure_signer" ])]
+    fn test_capability_names(#[case] bits: u32, #[case] expected: Vec<&str>) {
+        assert_eq!(Capability::names(bits), expected);
+    }
+
+    #[test]
+    fn test_load_missing_library() {
+        let manager = ExtensionManager::new();
+        let extension = Extension {
+            filename: "/nonexistent/libnodex-extension.so".to_string(),
+            symbol: "read".to_string(),
+        };
+
+        assert!(manager.load(&extension, Capability::Trng).is_err());
+        assert!(manager.load_all(&[ ("unknown.read".to_string(), extension) ]).is_err());
+        assert!(manager.list().is_empty());
+    }
+}
@@ -1,2 +1,6 @@
+pub mod cipher;
+pub mod host;
+pub mod manager;
 pub mod secure_keystore;
+pub mod secure_signer;
 pub mod trng;
\ No newline at end of file
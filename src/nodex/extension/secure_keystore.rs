@@ -1,263 +1,252 @@
-use std::ffi::CStr;
+use serde::{Deserialize, Serialize};
 
-use crate::{config::{KeyPair, Extension}, nodex::errors::NodeXError, app_config};
+use crate::{config::{KeyPair, Extension}, nodex::{errors::NodeXError, keyring::signing_key::SigningKey, keystore::{file::FileKeyStore, KeyStore}, runtime}, app_config};
+
+use super::{host::ExtensionCall, manager::{extension_manager, Capability}};
 
 #[repr(C)]
-enum SecureKeystoreType {
+pub(super) enum SecureKeystoreType {
     Sign,
     Update,
     Recover,
     Encrypt,
+    SignP256,
+    EncryptP256,
 }
 
-#[derive(Debug)]
+// NOTE: a TPM or a secure element holding P-256 keys only is told the curve by the slot,
+//       the P-256 slots share the storage of Sign and Encrypt in config.json
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum SecureKeyStoreType {
     Sign,
     Update,
     Recover,
     Encrypt,
+    SignP256,
+    EncryptP256,
+}
+
+impl From<&SecureKeyStoreType> for SecureKeystoreType {
+    fn from(key_type: &SecureKeyStoreType) -> Self {
+        match key_type {
+            SecureKeyStoreType::Sign => SecureKeystoreType::Sign,
+            SecureKeyStoreType::Update => SecureKeystoreType::Update,
+            SecureKeyStoreType::Recover => SecureKeystoreType::Recover,
+            SecureKeyStoreType::Encrypt => SecureKeystoreType::Encrypt,
+            SecureKeyStoreType::SignP256 => SecureKeystoreType::SignP256,
+            SecureKeyStoreType::EncryptP256 => SecureKeystoreType::EncryptP256,
+        }
+    }
 }
 
-pub struct SecureKeyStore {
+// NOTE: the secure_keystore extension generates and keeps the key pairs, the secure_signer extension signs with the keys it keeps
+pub struct ExtensionKeyStore {
 }
 
-impl SecureKeyStore {
+impl ExtensionKeyStore {
     const MAX_BUFFER_LENGTH: usize = 1024;
 
-    pub fn new() -> SecureKeyStore {
-        SecureKeyStore {}
+    pub fn new() -> ExtensionKeyStore {
+        ExtensionKeyStore {}
     }
 
-    fn write_external(&self, extension: &Extension, key_type: &SecureKeyStoreType, key_pair: &KeyPair) -> Result<(), NodeXError> {
-        log::info!("Called: write_external (type: {:?})", key_type);
-
-        unsafe {
-            let encoded_secret_key = hex::encode(&key_pair.secret_key);
-            let encoded_public_key = hex::encode(&key_pair.public_key);
-            let secret_key = [ encoded_secret_key.as_bytes(), b"\0" ].concat();
-            let public_key = [ encoded_public_key.as_bytes(), b"\0" ].concat();
-
-            let secret_key_ptr: *const i8 = secret_key.as_ptr().cast();
-            let public_key_ptr: *const i8 = public_key.as_ptr().cast();
-
-            let lib = match libloading::Library::new(&extension.filename) {
-                Ok(v) => v,
-                Err(_) => return Err(NodeXError{})
-            };
-
-            let func: libloading::Symbol<unsafe extern fn(key_type: SecureKeystoreType, secret_key_buffer: *const i8, public_key_buffer: *const i8, secret_key_buffer_len: usize, public_key_buffer_len: usize) -> u32> = match lib.get(extension.symbol.as_bytes()) {
-                Ok(v) => v,
-                Err(_) => return Err(NodeXError{})
-            };
-
-            let result = match key_type {
-                SecureKeyStoreType::Sign => {
-                    func(SecureKeystoreType::Sign, secret_key_ptr, public_key_ptr, encoded_secret_key.len(), encoded_public_key.len())
-                },
-                SecureKeyStoreType::Update => {
-                    func(SecureKeystoreType::Update, secret_key_ptr, public_key_ptr, encoded_secret_key.len(), encoded_public_key.len())
-                },
-                SecureKeyStoreType::Recover => {
-                    func(SecureKeystoreType::Recover, secret_key_ptr, public_key_ptr, encoded_secret_key.len(), encoded_public_key.len())
-                },
-                SecureKeyStoreType::Encrypt => {
-                    func(SecureKeystoreType::Encrypt, secret_key_ptr, public_key_ptr, encoded_secret_key.len(), encoded_public_key.len())
-                },
-            };
-
-            if result != 0 {
-                Err(NodeXError {})
-            } else {
-                Ok(())
-            }
-        }
+    // NOTE: the secure element holds the keys it signs and agrees on keys with, the Sidetree keys are restored from the
+    //       mnemonic and an X25519 key cannot be held by it, so those stay in config.json
+    fn is_held(key_type: &SecureKeyStoreType) -> bool {
+        matches!(key_type, SecureKeyStoreType::Sign | SecureKeyStoreType::SignP256 | SecureKeyStoreType::EncryptP256)
     }
 
-    fn write_internal(&self, key_type: &SecureKeyStoreType, key_pair: &KeyPair) -> Result<(), NodeXError> {
-        log::info!("Called: write_internal (type: {:?})", key_type);
+    fn call_signer(&self, extension: &Extension, key_type: &SecureKeyStoreType, input: &[u8]) -> Result<Vec<u8>, NodeXError> {
+        if ExtensionKeyStore::MAX_BUFFER_LENGTH < input.len() {
+            return Err(NodeXError {})
+        }
 
-        let config = app_config();
+        let call = ExtensionCall::KeyedBytes {
+            key_type: *key_type,
+            input: hex::encode(input),
+        };
 
-        match key_type {
-            SecureKeyStoreType::Sign => {
-                match config.inner.lock() {
-                    Ok(mut config) => {
-                        config.save_sign_key_pair(key_pair)
-                    },
-                    _ => Err(NodeXError {}),
-                }
-            },
-            SecureKeyStoreType::Update => {
-                match config.inner.lock() {
-                    Ok(mut config) => {
-                        config.save_update_key_pair(key_pair)
-                    },
-                    _ => Err(NodeXError {}),
-                }
-            },
-            SecureKeyStoreType::Recover => {
-                match config.inner.lock() {
-                    Ok(mut config) => {
-                        config.save_recover_key_pair(key_pair)
-                    },
-                    _ => Err(NodeXError {}),
-                }
-            },
-            SecureKeyStoreType::Encrypt => {
-                match config.inner.lock() {
-                    Ok(mut config) => {
-                        config.save_encrypt_key_pair(key_pair)
-                    },
-                    _ => Err(NodeXError {}),
+        match extension_manager().call(extension, Capability::SecureSigner, &call) {
+            Ok(v) => {
+                match v.into_iter().next() {
+                    Some(v) => Ok(v),
+                    None => Err(NodeXError {})
                 }
             },
+            Err(_) => Err(NodeXError {})
+        }
+    }
+}
+
+impl KeyStore for ExtensionKeyStore {
+    // NOTE: a key generated by the secure element is already kept there, a secret key from the process is refused
+    fn write(&self, key_type: &SecureKeyStoreType, key_pair: &KeyPair) -> Result<(), NodeXError> {
+        log::info!("Called: write_external (type: {:?})", key_type);
+
+        if !ExtensionKeyStore::is_held(key_type) {
+            return FileKeyStore::new().write(key_type, key_pair)
         }
+
+        if !key_pair.secret_key.is_empty() {
+            log::error!("a secret key of the type {:?} must be generated by the secure element", key_type);
+            return Err(NodeXError {})
+        }
+
+        Ok(())
     }
 
-    fn read_external(&self, extension: &Extension, key_type: &SecureKeyStoreType) -> Result<Option<KeyPair>, NodeXError> {
+    fn read(&self, key_type: &SecureKeyStoreType) -> Result<Option<KeyPair>, NodeXError> {
         log::info!("Called: read_external (type: {:?})", key_type);
 
-        unsafe {
-            let secret_key_buffer = [0u8; SecureKeyStore::MAX_BUFFER_LENGTH + 1];
-            let public_key_buffer = [0u8; SecureKeyStore::MAX_BUFFER_LENGTH + 1];
-
-            let secret_key_buffer_ptr: *const i8 = secret_key_buffer.as_ptr().cast();
-            let public_key_buffer_ptr: *const i8 = public_key_buffer.as_ptr().cast();
-
-            let secret_key_len = 0;
-            let public_key_len = 0;
-
-            let lib = match libloading::Library::new(&extension.filename) {
-                Ok(v) => v,
-                Err(_) => return Err(NodeXError{})
-            };
-
-            let func: libloading::Symbol<unsafe extern fn(key_type: SecureKeystoreType, secret_key_buffer: *const i8, public_key_buffer: *const i8, secret_key_buffer_len: usize, public_key_buffer_len: usize, secret_key_len: *const usize, public_key_len: *const usize) -> u32> = match lib.get(extension.symbol.as_bytes()) {
-                Ok(v) => v,
-                Err(_) => return Err(NodeXError{})
-            };
-
-            let result = match key_type {
-                SecureKeyStoreType::Sign => {
-                    func(SecureKeystoreType::Sign, secret_key_buffer_ptr, public_key_buffer_ptr, secret_key_buffer.len(), public_key_buffer.len(), &secret_key_len, &public_key_len)
-                },
-                SecureKeyStoreType::Update => {
-                    func(SecureKeystoreType::Update, secret_key_buffer_ptr, public_key_buffer_ptr, secret_key_buffer.len(), public_key_buffer.len(), &secret_key_len, &public_key_len)
-                },
-                SecureKeyStoreType::Recover => {
-                    func(SecureKeystoreType::Recover, secret_key_buffer_ptr, public_key_buffer_ptr, secret_key_buffer.len(), public_key_buffer.len(), &secret_key_len, &public_key_len)
-                },
-                SecureKeyStoreType::Encrypt => {
-                    func(SecureKeystoreType::Encrypt, secret_key_buffer_ptr, public_key_buffer_ptr, secret_key_buffer.len(), public_key_buffer.len(), &secret_key_len, &public_key_len)
-                },
-            };
-
-            let secret_key = match CStr::from_ptr(secret_key_buffer_ptr).to_str() {
-                Ok(v) => {
-                    match hex::decode(v) {
-                        Ok(v) => v,
-                        _ => return Err(NodeXError {})
-                    }
-                },
-                _ => return Err(NodeXError {})
-            };
-            let public_key = match CStr::from_ptr(public_key_buffer_ptr).to_str() {
-                Ok(v) => {
-                    match hex::decode(v) {
-                        Ok(v) => v,
-                        _ => return Err(NodeXError {})
-                    }
-                }
-                _ => return Err(NodeXError {})
-            };
+        if !ExtensionKeyStore::is_held(key_type) {
+            return FileKeyStore::new().read(key_type)
+        }
+
+        let config = app_config();
+        let extension = match config.inner.lock() {
+            Ok(config) => {
+                config.load_secure_keystore_read_sig()
+            },
+            _ => return Err(NodeXError {}),
+        };
 
-            if result == 0 {
+        let extension = match extension {
+            Some(v) => v,
+            None => return Err(NodeXError {}),
+        };
+
+        match extension_manager().call(&extension, Capability::SecureKeyStore, &ExtensionCall::ReadKeyPair { key_type: *key_type }) {
+            Ok(v) if v.len() == 2 => {
                 Ok(Some(KeyPair {
-                    public_key,
-                    secret_key,
+                    secret_key: v[0].clone(),
+                    public_key: v[1].clone(),
                 }))
-            } else {
-                Err(NodeXError{})
-            }
+            },
+            _ => Err(NodeXError {})
         }
     }
 
-    fn read_internal(&self, key_type: &SecureKeyStoreType) -> Result<Option<KeyPair>, NodeXError> {
-        log::info!("Called: read_internal (type: {:?})", key_type);
+    fn generates(&self, key_type: &SecureKeyStoreType) -> bool {
+        ExtensionKeyStore::is_held(key_type)
+    }
 
-        let config = app_config();
+    fn generate(&self, key_type: &SecureKeyStoreType) -> Result<Option<KeyPair>, NodeXError> {
+        log::info!("Called: generate_external (type: {:?})", key_type);
 
-        match key_type {
-            SecureKeyStoreType::Sign => {
-                match config.inner.lock() {
-                    Ok(config) => {
-                        Ok(config.load_sign_key_pair())
-                    },
-                    _ => Err(NodeXError {}),
-                }
-            },
-            SecureKeyStoreType::Update => {
-                match config.inner.lock() {
-                    Ok(config) => {
-                        Ok(config.load_update_key_pair())
-                    },
-                    _ => Err(NodeXError {}),
-                }
-            },
-            SecureKeyStoreType::Recover => {
-                match config.inner.lock() {
-                    Ok(config) => {
-                        Ok(config.load_recovery_key_pair())
-                    },
-                    _ => Err(NodeXError {}),
-                }
+        if !ExtensionKeyStore::is_held(key_type) {
+            return Ok(None)
+        }
+
+        let config = app_config();
+        let extension = match config.inner.lock() {
+            Ok(config) => {
+                config.load_secure_keystore_generate_sig()
             },
-            SecureKeyStoreType::Encrypt => {
-                match config.inner.lock() {
-                    Ok(config) => {
-                        Ok(config.load_encrypt_key_pair())
-                    },
-                    _ => Err(NodeXError {}),
-                }
+            _ => return Err(NodeXError {}),
+        };
+
+        let extension = match extension {
+            Some(v) => v,
+            None => return Err(NodeXError {}),
+        };
+
+        match extension_manager().call(&extension, Capability::SecureKeyStore, &ExtensionCall::GenerateKeyPair { key_type: *key_type }) {
+            Ok(v) if v.len() == 1 => {
+                Ok(Some(KeyPair {
+                    secret_key: vec![],
+                    public_key: v[0].clone(),
+                }))
             },
+            _ => Err(NodeXError {})
         }
     }
 
-    pub fn write(&self, key_type: &SecureKeyStoreType, key_pair: &KeyPair) -> Result<(), NodeXError> {
+    // NOTE: for secp256k1 and P-256 keys the extension signs the SHA-256 digest of the message and returns r || s (64 bytes),
+    //       Ed25519 (PureEdDSA) keys sign the message itself and return R || S (64 bytes)
+    fn sign(&self, key_type: &SecureKeyStoreType, context: &SigningKey, message: &[u8]) -> Result<Vec<u8>, NodeXError> {
+        log::info!("Called: sign_external (type: {:?}, curve: {:?})", key_type, context.get_type());
+
         let config = app_config();
         let extension = match config.inner.lock() {
             Ok(config) => {
-                config.load_secure_keystore_write_sig()
+                config.load_secure_signer_sign_sig()
             },
             _ => return Err(NodeXError {})
         };
 
-        match extension {
-            Some(v) => {
-                self.write_external(&v, key_type, key_pair)
+        let extension = match extension {
+            Some(v) => v,
+            None => return Err(NodeXError {}),
+        };
+
+        match context {
+            SigningKey::Secp256k1(_) => {
+                let digest = runtime::sha2::SHA256::digest(message);
+
+                match self.call_signer(&extension, key_type, &digest) {
+                    Ok(v) => runtime::secp256k1::Secp256k1::normalize_signature(&v),
+                    Err(_) => Err(NodeXError{})
+                }
+            },
+            SigningKey::Ed25519(_) => {
+                match self.call_signer(&extension, key_type, message) {
+                    Ok(v) if v.len() == 64 => Ok(v),
+                    _ => Err(NodeXError{})
+                }
+            },
+            // NOTE: ES256 accepts both the low and the high S, the signature of a TPM is used as it is
+            SigningKey::P256(_) => {
+                let digest = runtime::sha2::SHA256::digest(message);
+
+                match self.call_signer(&extension, key_type, &digest) {
+                    Ok(v) if v.len() == 64 => Ok(v),
+                    _ => Err(NodeXError{})
+                }
             },
-            _ => {
-                self.write_internal(key_type, key_pair)
-            }
         }
     }
 
-    pub fn read(&self, key_type: &SecureKeyStoreType) -> Result<Option<KeyPair>, NodeXError> {
+    // NOTE: the extension takes the peer public key (SEC1) and returns the x coordinate of the shared point (32 bytes)
+    fn ecdh(&self, key_type: &SecureKeyStoreType, public_key: &[u8]) -> Result<Vec<u8>, NodeXError> {
+        log::info!("Called: ecdh_external (type: {:?})", key_type);
+
         let config = app_config();
         let extension = match config.inner.lock() {
             Ok(config) => {
-                config.load_secure_keystore_read_sig()
+                config.load_secure_signer_ecdh_sig()
             },
-            _ => return Err(NodeXError {}),
+            _ => return Err(NodeXError {})
         };
 
-        match extension {
-            Some(v) => {
-                self.read_external(&v, key_type)
-            },
-            _ => {
-                self.read_internal(key_type)
-            }
+        let extension = match extension {
+            Some(v) => v,
+            None => return Err(NodeXError {}),
+        };
+
+        match self.call_signer(&extension, key_type, public_key) {
+            Ok(v) if v.len() == 32 => Ok(v),
+            _ => Err(NodeXError{})
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn test_held_key_pairs() {
+        let key_store = ExtensionKeyStore::new();
+
+        assert!(key_store.generates(&SecureKeyStoreType::Sign));
+        assert!(key_store.generates(&SecureKeyStoreType::EncryptP256));
+        assert!(!key_store.generates(&SecureKeyStoreType::Update));
+        assert!(!key_store.generates(&SecureKeyStoreType::Encrypt));
+
+        // NOTE: a secret key from the process is never written to the secure element
+        assert!(key_store.write(&SecureKeyStoreType::Sign, &KeyPair { public_key: vec![ 0x02; 33 ], secret_key: vec![ 0x01; 32 ] }).is_err());
+        assert!(key_store.write(&SecureKeyStoreType::SignP256, &KeyPair { public_key: vec![ 0x04; 65 ], secret_key: vec![] }).is_ok());
+
+        assert!(matches!(key_store.generate(&SecureKeyStoreType::Recover), Ok(None)));
+    }
+}
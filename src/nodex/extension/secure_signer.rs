@@ -0,0 +1,64 @@
+use crate::nodex::{errors::NodeXError, keyring::{p256::P256, secp256k1::Secp256k1, signing_key::SigningKey}, keystore::key_store, runtime};
+
+pub struct SecureSigner {
+}
+
+impl SecureSigner {
+    pub fn new() -> SecureSigner {
+        SecureSigner {}
+    }
+
+    fn sign_internal(&self, context: &SigningKey, message: &[u8]) -> Result<Vec<u8>, NodeXError> {
+        match context {
+            SigningKey::Secp256k1(v) => runtime::secp256k1::Secp256k1::ecdsa_sign(message, &v.get_secret_key()),
+            SigningKey::Ed25519(v) => runtime::ed25519::Ed25519::eddsa_sign(message, &v.get_secret_key()),
+            SigningKey::P256(v) => runtime::p256::P256::ecdsa_sign(message, &v.get_secret_key()),
+        }
+    }
+
+    fn ecdh_internal(&self, context: &P256, public_key: &[u8]) -> Result<Vec<u8>, NodeXError> {
+        runtime::p256::P256::ecdh(&context.get_secret_key(), public_key)
+    }
+
+    fn ecdh_secp256k1_internal(&self, context: &Secp256k1, public_key: &[u8]) -> Result<Vec<u8>, NodeXError> {
+        runtime::secp256k1::Secp256k1::ecdh(&context.get_secret_key(), public_key)
+    }
+
+    pub fn sign(&self, context: &SigningKey, message: &[u8]) -> Result<Vec<u8>, NodeXError> {
+        let key_type = match context.get_key_type() {
+            Some(v) => v,
+            None => return self.sign_internal(context, message),
+        };
+
+        match key_store() {
+            Ok(v) => v.sign(&key_type, context, message),
+            Err(_) => Err(NodeXError {})
+        }
+    }
+
+    // NOTE: X25519 key agreement keys never live in a secure element, only P-256 keys (ECDH-ES of DIDComm) are agreed on here
+    pub fn ecdh(&self, context: &P256, public_key: &[u8]) -> Result<Vec<u8>, NodeXError> {
+        let key_type = match context.get_key_type() {
+            Some(v) => v,
+            None => return self.ecdh_internal(context, public_key),
+        };
+
+        match key_store() {
+            Ok(v) => v.ecdh(&key_type, public_key),
+            Err(_) => Err(NodeXError {})
+        }
+    }
+
+    // NOTE: a DID without a key agreement key agrees on DIDComm keys with its secp256k1 signing key
+    pub fn ecdh_secp256k1(&self, context: &Secp256k1, public_key: &[u8]) -> Result<Vec<u8>, NodeXError> {
+        let key_type = match context.get_key_type() {
+            Some(v) => v,
+            None => return self.ecdh_secp256k1_internal(context, public_key),
+        };
+
+        match key_store() {
+            Ok(v) => v.ecdh(&key_type, public_key),
+            Err(_) => Err(NodeXError {})
+        }
+    }
+}
@@ -1,60 +1,194 @@
-use crate::{config::Extension, nodex::{errors::NodeXError, runtime::random::Random}, app_config};
-use std::ffi::CStr;
+use std::sync::{Mutex, OnceLock};
+use crate::{config::Extension, nodex::{errors::TrngError, runtime::{hmac_drbg::HmacDrbg, random::Random}}, app_config};
+use super::{host::ExtensionCall, manager::{extension_manager, Capability}};
+
+// NOTE: NIST SP 800-90B continuous health tests (4.4), a sample is a byte with an assessed min-entropy of 1 bit
+struct HealthTests {
+    last: Option<u8>,
+    repetitions: u32,
+    window_sample: u8,
+    window_count: u32,
+    window_index: u32,
+}
+
+impl HealthTests {
+    // NOTE: 1 + ceil(20 / H), false positive probability 2^-20
+    const REPETITION_COUNT_CUTOFF: u32 = 21;
+    const ADAPTIVE_PROPORTION_WINDOW: u32 = 512;
+    const ADAPTIVE_PROPORTION_CUTOFF: u32 = 410;
+
+    fn new() -> Self {
+        HealthTests {
+            last: None,
+            repetitions: 0,
+            window_sample: 0,
+            window_count: 0,
+            window_index: 0,
+        }
+    }
+
+    fn test(&mut self, sample: u8) -> bool {
+        // NOTE: repetition count test
+        match self.last {
+            Some(v) if v == sample => {
+                self.repetitions += 1;
+
+                if Self::REPETITION_COUNT_CUTOFF <= self.repetitions {
+                    return false
+                }
+            },
+            _ => {
+                self.last = Some(sample);
+                self.repetitions = 1;
+            },
+        }
+
+        // NOTE: adaptive proportion test
+        if self.window_index == 0 {
+            self.window_sample = sample;
+            self.window_count = 1;
+        } else if self.window_sample == sample {
+            self.window_count += 1;
+
+            if Self::ADAPTIVE_PROPORTION_CUTOFF <= self.window_count {
+                return false
+            }
+        }
+
+        self.window_index = (self.window_index + 1) % Self::ADAPTIVE_PROPORTION_WINDOW;
+
+        true
+    }
+
+    fn check(&mut self, samples: &[u8]) -> bool {
+        samples.iter().all(|v| self.test(*v))
+    }
+}
+
+struct TrngState {
+    health_tests: HealthTests,
+    started: bool,
+    drbg: Option<HmacDrbg>,
+}
+
+fn trng_state() -> &'static Mutex<TrngState> {
+    static SINGLETON: OnceLock<Mutex<TrngState>> = OnceLock::new();
+
+    SINGLETON.get_or_init(|| {
+        Mutex::new(TrngState {
+            health_tests: HealthTests::new(),
+            started: false,
+            drbg: None,
+        })
+    })
+}
 
 pub struct Trng {
 }
 
 impl Trng {
     const MAX_BUFFER_LENGTH: usize = 1024;
+    const STARTUP_SAMPLES: usize = 1024;
+    // NOTE: 256 bits of min-entropy at 1 bit per byte
+    const ENTROPY_INPUT_LENGTH: usize = 256;
+    const NONCE_LENGTH: usize = 32;
+    const PERSONALIZATION: &'static [u8] = b"nodex-trng";
 
     pub fn new() -> Trng {
         Trng {}
     }
 
-    fn read_external(&self, extension: &Extension, size: &usize) -> Result<Vec<u8>, NodeXError> {
+    fn read_raw(&self, extension: &Extension, size: usize) -> Result<Vec<u8>, TrngError> {
+        match extension_manager().call(extension, Capability::Trng, &ExtensionCall::Read { size }) {
+            Ok(v) => {
+                match v.into_iter().next() {
+                    Some(v) if v.len() == size => Ok(v),
+                    _ => Err(TrngError::Read)
+                }
+            },
+            Err(_) => Err(TrngError::Read)
+        }
+    }
+
+    fn read_external(&self, extension: &Extension, size: &usize) -> Result<Vec<u8>, TrngError> {
         log::info!("Called: read_external");
         
         if Trng::MAX_BUFFER_LENGTH < *size {
-            return Err(NodeXError {})
+            return Err(TrngError::Read)
         }
 
-        unsafe {
-            let buffer = [0u8; Trng::MAX_BUFFER_LENGTH + 1];
-            let buffer_ptr: *const i8 = buffer.as_ptr().cast();
+        let mut state = match trng_state().lock() {
+            Ok(v) => v,
+            Err(_) => return Err(TrngError::Drbg)
+        };
+
+        // NOTE: startup tests run once before any output of the extension is used
+        if !state.started {
+            let samples = self.read_raw(extension, Trng::STARTUP_SAMPLES)?;
 
-            let lib = match libloading::Library::new(&extension.filename) {
-                Ok(v) => v,
-                Err(_) => return Err(NodeXError{})
-            };
+            if !state.health_tests.check(&samples) {
+                state.health_tests = HealthTests::new();
+                log::error!("TRNG startup health test failed");
+                return Err(TrngError::HealthTest)
+            }
 
-            let func: libloading::Symbol<unsafe extern fn(buf: *const i8, bufsize: usize, size: usize) -> u32> = match lib.get(extension.symbol.as_bytes()) {
-                Ok(v) => v,
-                Err(_) => return Err(NodeXError{})
-            };
+            state.started = true;
+        }
 
-            let result = func(buffer_ptr, buffer.len(), *size);
+        let entropy = self.read_raw(extension, Trng::ENTROPY_INPUT_LENGTH)?;
 
-            if result != 0 {
-                return Err(NodeXError {})
-            }
+        if !state.health_tests.check(&entropy) {
+            state.health_tests = HealthTests::new();
+            state.started = false;
+            log::error!("TRNG continuous health test failed");
+            return Err(TrngError::HealthTest)
+        }
+
+        // NOTE: the extension output is conditioned together with OS randomness
+        let nonce = match Random::bytes(&Trng::NONCE_LENGTH) {
+            Ok(v) => v,
+            Err(_) => return Err(TrngError::Read)
+        };
+
+        let drbg = match state.drbg.take() {
+            Some(mut v) => {
+                match v.reseed(&entropy, &nonce) {
+                    Ok(_) => v,
+                    Err(_) => return Err(TrngError::Drbg)
+                }
+            },
+            None => {
+                match HmacDrbg::new(&entropy, &nonce, Trng::PERSONALIZATION) {
+                    Ok(v) => v,
+                    Err(_) => return Err(TrngError::Drbg)
+                }
+            },
+        };
+
+        let drbg = state.drbg.insert(drbg);
 
-            Ok(CStr::from_ptr(buffer_ptr).to_bytes().to_vec())
+        match drbg.generate(*size, &[]) {
+            Ok(v) => Ok(v),
+            Err(_) => Err(TrngError::Drbg)
         }
     }
 
-    fn read_internal(&self, size: &usize) -> Result<Vec<u8>, NodeXError> {
+    fn read_internal(&self, size: &usize) -> Result<Vec<u8>, TrngError> {
         log::info!("Called: read_internal");
 
-        Random::bytes(size)
+        match Random::bytes(size) {
+            Ok(v) => Ok(v),
+            Err(_) => Err(TrngError::Read)
+        }
     }
 
-    pub fn read(&self, size: &usize) -> Result<Vec<u8>, NodeXError> {
+    pub fn read(&self, size: &usize) -> Result<Vec<u8>, TrngError> {
         let config = app_config();
         let extension = match config.inner.lock() {
             Ok(config) => {
                 config.load_trng_read_sig()
             },
-            _ => return Err(NodeXError {})
+            _ => return Err(TrngError::Read)
         };
 
         match extension {
@@ -66,4 +200,36 @@ impl Trng {
             }
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_health_tests_with_random() {
+        let samples = match Random::bytes(&4096) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert!(HealthTests::new().check(&samples));
+    }
+
+    #[test]
+    pub fn test_repetition_count() {
+        let mut health_tests = HealthTests::new();
+
+        assert!(health_tests.check(&[ 0x00; 20 ]));
+        assert!(!health_tests.check(&[ 0x00 ]));
+    }
+
+    #[test]
+    pub fn test_adaptive_proportion() {
+        // NOTE: 7 of every 8 samples are the same, without long repetitions
+        let samples: Vec<u8> = (0..512).map(|i| if i % 8 == 7 { 0xff } else { 0x00 }).collect();
+
+        assert!(!HealthTests::new().check(&samples));
+        assert!(HealthTests::new().check(&samples[..400]));
+    }
+}
@@ -0,0 +1,217 @@
+use crate::nodex::sidetree::payload::PublicKeyPayload;
+use crate::nodex::{errors::NodeXError, extension::secure_keystore::SecureKeyStoreType, runtime::{self, base64_url::PaddingType}};
+
+use super::secp256k1::KeyPairSecp256K1;
+
+#[derive(Clone)]
+pub struct Ed25519 {
+    public : Vec<u8>,
+    private: Vec<u8>,
+    // NOTE: set when the secret key never leaves the secure element
+    key_type: Option<SecureKeyStoreType>,
+}
+
+impl Ed25519 {
+    const KEY_SIZE: usize = 32;
+
+    // NOTE: multicodec prefix of an Ed25519 public key (unsigned varint)
+    const MULTICODEC_PREFIX: [u8; 2] = [0xed, 0x01];
+
+    pub fn new(secret: &[u8]) -> Result<Self, NodeXError> {
+        let public = match runtime::ed25519::Ed25519::generate_public_key(secret) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        Ok(Ed25519 {
+            public,
+            private: secret.to_vec(),
+            key_type: None,
+        })
+    }
+
+    pub fn from_public_key(public: &[u8]) -> Result<Self, NodeXError> {
+        if public.len() != Self::KEY_SIZE {
+            return Err(NodeXError{})
+        }
+
+        Ok(Ed25519 {
+            public : public.to_vec(),
+            private: vec![],
+            key_type: None,
+        })
+    }
+
+    pub fn new_external(public: &[u8], key_type: &SecureKeyStoreType) -> Result<Self, NodeXError> {
+        match Ed25519::from_public_key(public) {
+            Ok(v) => {
+                Ok(Ed25519 {
+                    key_type: Some(*key_type),
+                    ..v
+                })
+            },
+            Err(_) => Err(NodeXError{})
+        }
+    }
+
+    // NOTE: "publicKeyMultibase" of Ed25519VerificationKey2020 (base58btc)
+    pub fn from_multibase(value: &str) -> Result<Self, NodeXError> {
+        let encoded = match value.strip_prefix('z') {
+            Some(v) => v,
+            None => return Err(NodeXError{})
+        };
+
+        let decoded = match bs58::decode(encoded).into_vec() {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        match decoded.strip_prefix(&Self::MULTICODEC_PREFIX[..]) {
+            Some(v) => Ed25519::from_public_key(v),
+            None => Err(NodeXError{})
+        }
+    }
+
+    pub fn get_key_type(&self) -> Option<SecureKeyStoreType> {
+        self.key_type
+    }
+
+    pub fn get_public_key(&self) -> Vec<u8> {
+        self.public.clone()
+    }
+
+    pub fn get_secret_key(&self) -> Vec<u8> {
+        self.private.clone()
+    }
+
+    pub fn from_jwk(jwk: &KeyPairSecp256K1) -> Result<Self, NodeXError> {
+        if jwk.kty != "OKP" || jwk.crv != "Ed25519" {
+            return Err(NodeXError{})
+        }
+
+        let public = match runtime::base64_url::Base64Url::decode_as_bytes(&jwk.x, &PaddingType::NoPadding) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        let context = match Ed25519::from_public_key(&public) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        let d = match &jwk.d {
+            Some(v) => v,
+            None => return Ok(context),
+        };
+
+        let private = match runtime::base64_url::Base64Url::decode_as_bytes(d, &PaddingType::NoPadding) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        Ok(Ed25519 {
+            private,
+            ..context
+        })
+    }
+
+    pub fn to_jwk(&self, included_private_key: bool) -> Result<KeyPairSecp256K1, NodeXError> {
+        if self.public.len() != Self::KEY_SIZE {
+            return Err(NodeXError{})
+        }
+
+        let d = if included_private_key {
+            Some(runtime::base64_url::Base64Url::encode(&self.get_secret_key(), &PaddingType::NoPadding))
+        } else {
+            None
+        };
+
+        Ok(KeyPairSecp256K1 {
+            kty: "OKP".to_string(),
+            crv: "Ed25519".to_string(),
+            x  : runtime::base64_url::Base64Url::encode(&self.public, &PaddingType::NoPadding),
+            y  : "".to_string(),
+            d,
+            kid: None,
+        })
+    }
+
+    pub fn to_public_key(&self, key_id: &str, purpose: &[&str]) -> Result<PublicKeyPayload, NodeXError> {
+        let jwk = match self.to_jwk(false) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        Ok(PublicKeyPayload {
+            id: key_id.to_string(),
+            r#type: "JsonWebKey2020".to_string(),
+            jwk,
+            purpose: purpose.iter().map(|value| value.to_string()).collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_new() {
+        // NOTE: RFC 8032, 7.1 (TEST 1)
+        let secret = match hex::decode("9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60") {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        let result = match Ed25519::new(&secret) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert_eq!(hex::encode(result.get_public_key()), "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a");
+        assert!(result.get_key_type().is_none());
+        assert!(Ed25519::new(&secret[..31]).is_err());
+    }
+
+    #[test]
+    pub fn test_to_jwk() {
+        let context = match Ed25519::new(&[ 0x01; 32 ]) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        let jwk = match context.to_jwk(true) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert_eq!(jwk.kty, "OKP");
+        assert_eq!(jwk.crv, "Ed25519");
+
+        let clone = match Ed25519::from_jwk(&jwk) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert_eq!(clone.get_public_key(), context.get_public_key());
+        assert_eq!(clone.get_secret_key(), context.get_secret_key());
+
+        let public = match context.to_jwk(false).and_then(|v| Ed25519::from_jwk(&v)) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert!(public.get_secret_key().is_empty());
+    }
+
+    #[test]
+    pub fn test_from_multibase() {
+        let result = match Ed25519::from_multibase("z6MkhaXgBZDvotDkL5257faiztiGiC2QtKLGpbnnEGta2doK") {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert_eq!(result.to_jwk(false).ok().map(|v| v.x), Some("Lm_M42cB3HkUiODQsXRcweM6TByfzEHGO9ND274JcOY".to_string()));
+        assert!(Ed25519::from_multibase("zQ3shokFTS3brHcDQrn82RUDfCZESWL1ZdCEJwekUDPQiYBme").is_err());
+    }
+}
@@ -0,0 +1,86 @@
+use crate::nodex::{errors::NodeXError, sidetree::payload::PublicKeyPayload};
+
+use super::{p256::P256, secp256k1::KeyPairSecp256K1, x25519::X25519};
+
+// NOTE: the key agreement key, X25519 for ECDH-1PU or P-256 for ECDH-ES
+#[derive(Clone)]
+pub enum EncryptionKey {
+    X25519(X25519),
+    P256(P256),
+}
+
+impl EncryptionKey {
+    pub fn from_jwk(jwk: &KeyPairSecp256K1) -> Result<Self, NodeXError> {
+        match (jwk.kty.as_str(), jwk.crv.as_str()) {
+            ("OKP", "X25519") => X25519::from_jwk(jwk).map(EncryptionKey::X25519),
+            ("EC", "P-256") => P256::from_jwk(jwk).map(EncryptionKey::P256),
+            _ => Err(NodeXError{}),
+        }
+    }
+
+    pub fn get_public_key(&self) -> Vec<u8> {
+        match self {
+            EncryptionKey::X25519(v) => v.get_public_key(),
+            EncryptionKey::P256(v) => v.get_public_key(),
+        }
+    }
+
+    pub fn get_secret_key(&self) -> Vec<u8> {
+        match self {
+            EncryptionKey::X25519(v) => v.get_secret_key(),
+            EncryptionKey::P256(v) => v.get_secret_key(),
+        }
+    }
+
+    pub fn to_jwk(&self) -> Result<KeyPairSecp256K1, NodeXError> {
+        match self {
+            EncryptionKey::X25519(v) => v.to_jwk(),
+            EncryptionKey::P256(v) => v.to_jwk(false),
+        }
+    }
+
+    // NOTE: a P-256 key agreement key is published as JsonWebKey2020, EcdsaSecp256r1VerificationKey2019 is for signing
+    pub fn to_public_key(&self, key_id: &str, purpose: &[&str]) -> Result<PublicKeyPayload, NodeXError> {
+        if let EncryptionKey::X25519(v) = self {
+            return v.to_public_key(key_id, purpose)
+        }
+
+        let jwk = match self.to_jwk() {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        Ok(PublicKeyPayload {
+            id: key_id.to_string(),
+            r#type: "JsonWebKey2020".to_string(),
+            jwk,
+            purpose: purpose.iter().map(|value| value.to_string()).collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_from_jwk() {
+        let p256 = EncryptionKey::P256(P256::new(&[ 0x01; 32 ]).unwrap());
+        let x25519 = EncryptionKey::X25519(X25519::new(&[ 0x01; 32 ]).unwrap());
+
+        for key in [ p256, x25519 ].iter() {
+            let result = match EncryptionKey::from_jwk(&key.to_jwk().unwrap()) {
+                Ok(v) => v,
+                Err(_) => panic!()
+            };
+
+            assert_eq!(result.get_public_key(), key.get_public_key());
+            assert_eq!(key.to_public_key("encryptionKey", &["keyAgreement"]).unwrap().r#type, "JsonWebKey2020");
+        }
+
+        let mut jwk = P256::new(&[ 0x01; 32 ]).unwrap().to_jwk(false).unwrap();
+        jwk.crv = "secp256k1".to_string();
+
+        assert!(EncryptionKey::from_jwk(&jwk).is_err());
+    }
+}
@@ -1,77 +1,115 @@
 use std::cmp::Ordering;
 
-use crate::{nodex::{errors::NodeXError, extension::secure_keystore::{SecureKeyStore, SecureKeyStoreType}, runtime}, config::KeyPair, app_config, SingletonAppConfig};
+use chrono::Utc;
 
-use super::secp256k1::{Secp256k1, Secp256k1Context};
+use crate::{nodex::{errors::NodeXError, extension::secure_keystore::SecureKeyStoreType, keystore::{key_store, KeyStore}, runtime}, config::KeyPair, app_config, SingletonAppConfig};
+
+use super::{ed25519::Ed25519, encryption_key::EncryptionKey, p256::P256, secp256k1::{Secp256k1, Secp256k1Context}, signing_key::{KeyType, SigningKey}, x25519::X25519};
 
 pub struct MnemonicKeyring {
     mnemonic: String,
-    sign    : Secp256k1,
+    passphrase: Option<String>,
+    // NOTE: the mnemonic is protected by a passphrase, which may not be known after a restart
+    has_passphrase: bool,
+    sign    : SigningKey,
     update  : Secp256k1,
     recovery: Secp256k1,
-    encrypt : Secp256k1,
+    encrypt : EncryptionKey,
     config  : Box<SingletonAppConfig>,
-    secure_keystore: SecureKeyStore
+    key_store: Box<dyn KeyStore>
 }
 
 impl MnemonicKeyring {
-    const SIGN_DERIVATION_PATH: &'static str     = "m/44'/0'/0'/0/10";
-    const UPDATE_DERIVATION_PATH: &'static str   = "m/44'/0'/0'/0/20";
-    const RECOVERY_DERIVATION_PATH: &'static str = "m/44'/0'/0'/0/30";
+    const SIGN_DERIVATION_KEY: u32               = 10;
+    const UPDATE_DERIVATION_KEY: u32             = 20;
+    const RECOVERY_DERIVATION_KEY: u32           = 30;
     const ENCRYPT_DERIVATION_PATH: &'static str  = "m/44'/0'/0'/0/40";
 
+    // NOTE: the highest rotation index searched when keys are restored
+    const MAX_DERIVATION_INDEX: u32 = 256;
+
+    // NOTE: a secure element keeping the secret key returns the public key only
+    fn key_pair(key_pair: KeyPair, key_type: &SecureKeyStoreType) -> Result<Secp256k1, NodeXError> {
+        if key_pair.secret_key.is_empty() {
+            return Secp256k1::new_external(&key_pair.public_key, key_type)
+        }
+
+        Secp256k1::new(&Secp256k1Context {
+            public: key_pair.public_key,
+            secret: key_pair.secret_key,
+        })
+    }
+
+    // NOTE: a key ring saved before the key agreement key was introduced keeps the secp256k1 secret key
+    //       derived at the same path, which is also the secret of the X25519 key
+    fn encrypt_key_pair(key_pair: KeyPair, mnemonic: &str, passphrase: Option<&str>, has_passphrase: bool) -> Result<X25519, NodeXError> {
+        if !key_pair.secret_key.is_empty() {
+            return X25519::new(&key_pair.secret_key)
+        }
+
+        // NOTE: a secure element cannot agree on X25519 keys, so the key is derived from the mnemonic again
+        let seed = match Self::mnemonic_to_seed(mnemonic, passphrase, has_passphrase) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        Self::generate_x25519(&seed, Self::ENCRYPT_DERIVATION_PATH)
+    }
+
+    fn encryption_key(key_type: KeyType, key_pair: KeyPair, mnemonic: &str, passphrase: Option<&str>, has_passphrase: bool) -> Result<EncryptionKey, NodeXError> {
+        match (key_type, key_pair.secret_key.is_empty()) {
+            (KeyType::P256, true) => P256::new_external(&key_pair.public_key, &key_type.encrypt_store_type()).map(EncryptionKey::P256),
+            (KeyType::P256, false) => P256::new(&key_pair.secret_key).map(EncryptionKey::P256),
+            _ => Self::encrypt_key_pair(key_pair, mnemonic, passphrase, has_passphrase).map(EncryptionKey::X25519),
+        }
+    }
+
     pub fn load_keyring() -> Result<Self, NodeXError> {
         let config = app_config();
-        let secure_keystore = SecureKeyStore::new();
+        let key_store = match key_store() {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
 
         let mnemonic = config.inner.lock().unwrap().get_mnemonic();
         let mnemonic = match mnemonic {
             Some(v) => v,
             None => return Err(NodeXError{})
         };
+        let passphrase = config.inner.lock().unwrap().get_passphrase();
+        let has_passphrase = config.inner.lock().unwrap().has_passphrase();
+        let sign_key_type = config.inner.lock().unwrap().get_key_pair_type();
 
-        let sign = match secure_keystore.read(&SecureKeyStoreType::Sign) {
+        let sign = match key_store.read(&sign_key_type.sign_store_type()) {
             Ok(Some(v)) => {
-                match Secp256k1::new(&Secp256k1Context {
-                    public: v.public_key,
-                    secret: v.secret_key,
-                }) {
+                match SigningKey::from_key_pair(sign_key_type, v, &sign_key_type.sign_store_type()) {
                     Ok(v) => v,
                     _ => return Err(NodeXError{}),
                 }
             },
             _ => return Err(NodeXError{}),
         };
-        let update = match secure_keystore.read(&SecureKeyStoreType::Update) {
+        let update = match key_store.read(&SecureKeyStoreType::Update) {
             Ok(Some(v)) => {
-                match Secp256k1::new(&Secp256k1Context {
-                    public: v.public_key,
-                    secret: v.secret_key,
-                }) {
+                match Self::key_pair(v, &SecureKeyStoreType::Update) {
                     Ok(v) => v,
                     _ => return Err(NodeXError{}),
                 }
             },
             _ => return Err(NodeXError{}),
         };
-        let recovery = match secure_keystore.read(&SecureKeyStoreType::Recover) {
+        let recovery = match key_store.read(&SecureKeyStoreType::Recover) {
             Ok(Some(v)) => {
-                match Secp256k1::new(&Secp256k1Context {
-                    public: v.public_key,
-                    secret: v.secret_key,
-                }) {
+                match Self::key_pair(v, &SecureKeyStoreType::Recover) {
                     Ok(v) => v,
                     _ => return Err(NodeXError{}),
                 }
             },
             _ => return Err(NodeXError{}),
         };
-        let encrypt = match secure_keystore.read(&SecureKeyStoreType::Encrypt) {
+        let encrypt = match key_store.read(&sign_key_type.encrypt_store_type()) {
             Ok(Some(v)) => {
-                match Secp256k1::new(&Secp256k1Context {
-                    public: v.public_key,
-                    secret: v.secret_key,
-                }) {
+                match Self::encryption_key(sign_key_type, v, &mnemonic, passphrase.as_deref(), has_passphrase) {
                     Ok(v) => v,
                     _ => return Err(NodeXError{}),
                 }
@@ -81,57 +119,131 @@ impl MnemonicKeyring {
 
         Ok(MnemonicKeyring {
             mnemonic,
+            passphrase,
+            has_passphrase,
             sign,
             update,
             recovery,
             encrypt,
             config,
-            secure_keystore,
+            key_store,
         })
     }
 
     pub fn create_keyring() -> Result<Self, NodeXError> {
-        let config = app_config();
-        let secure_keystore = SecureKeyStore::new();
-
         let mnemonic = match runtime::bip39::BIP39::generate_mnemonic(&runtime::bip39::MnemonicType::Words24) {
             Ok(v) => v,
             Err(_) => return Err(NodeXError{})
         };
-        let seed = match runtime::bip39::BIP39::mnemonic_to_seed(&mnemonic, None) {
+
+        let mut keyring = match Self::restore_keyring(&mnemonic, None) {
             Ok(v) => v,
             Err(_) => return Err(NodeXError{})
         };
 
-        let sign = match Self::generate_secp256k1(&seed, Self::SIGN_DERIVATION_PATH) {
+        if keyring.generate_keys().is_err() {
+            return Err(NodeXError{})
+        }
+
+        Ok(keyring)
+    }
+
+    // NOTE: a key store generating keys (a secure element) replaces the derived signing and P-256 key agreement keys,
+    //       their secret keys are then never in this process
+    fn generate_keys(&mut self) -> Result<(), NodeXError> {
+        let key_type = self.sign.get_type();
+
+        match self.key_store.generate(&key_type.sign_store_type()) {
+            Ok(Some(v)) => {
+                self.sign = match SigningKey::from_key_pair(key_type, v, &key_type.sign_store_type()) {
+                    Ok(v) => v,
+                    Err(_) => return Err(NodeXError{})
+                };
+            },
+            Ok(None) => (),
+            Err(_) => return Err(NodeXError{})
+        };
+
+        match self.key_store.generate(&key_type.encrypt_store_type()) {
+            Ok(Some(v)) => {
+                self.encrypt = match Self::encryption_key(key_type, v, &self.mnemonic, self.passphrase.as_deref(), self.has_passphrase) {
+                    Ok(v) => v,
+                    Err(_) => return Err(NodeXError{})
+                };
+            },
+            Ok(None) => (),
+            Err(_) => return Err(NodeXError{})
+        };
+
+        Ok(())
+    }
+
+    // NOTE: a key held by the key store cannot be derived again, the key kept there is used or another one is generated
+    fn held_key_pair(&self, key_type: &SecureKeyStoreType) -> Result<KeyPair, NodeXError> {
+        match self.key_store.read(key_type) {
+            Ok(Some(v)) => Ok(v),
+            _ => {
+                match self.key_store.generate(key_type) {
+                    Ok(Some(v)) => Ok(v),
+                    _ => Err(NodeXError{})
+                }
+            },
+        }
+    }
+
+    pub fn holds_sign_key(&self) -> bool {
+        self.key_store.generates(&self.sign.get_type().sign_store_type())
+    }
+
+    // NOTE: the key ring of an existing mnemonic, the rotated keys are set by restore()
+    pub fn restore_keyring(mnemonic: &str, passphrase: Option<&str>) -> Result<Self, NodeXError> {
+        let config = app_config();
+        let key_store = match key_store() {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        let mnemonic = mnemonic.split_whitespace().collect::<Vec<&str>>().join(" ");
+        let passphrase = passphrase.map(|v| v.to_string());
+        let has_passphrase = passphrase.is_some();
+        let sign_key_type = config.inner.lock().unwrap().get_sign_key_type();
+
+        let seed = match runtime::bip39::BIP39::mnemonic_to_seed(&mnemonic, passphrase.as_deref()) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        let sign = match Self::generate_signing_key(&seed, &Self::derivation_path(Self::SIGN_DERIVATION_KEY, 0), sign_key_type) {
             Ok(v) => v,
             Err(_) => return Err(NodeXError{})
         };
-        let update = match Self::generate_secp256k1(&seed, Self::UPDATE_DERIVATION_PATH) {
+        let update = match Self::generate_secp256k1(&seed, &Self::derivation_path(Self::UPDATE_DERIVATION_KEY, 0)) {
             Ok(v) => v,
             Err(_) => return Err(NodeXError{})
         };
-        let recovery = match Self::generate_secp256k1(&seed, Self::RECOVERY_DERIVATION_PATH) {
+        let recovery = match Self::generate_secp256k1(&seed, &Self::derivation_path(Self::RECOVERY_DERIVATION_KEY, 0)) {
             Ok(v) => v,
             Err(_) => return Err(NodeXError{})
         };
-        let encrypt = match Self::generate_secp256k1(&seed, Self::ENCRYPT_DERIVATION_PATH) {
+        let encrypt = match Self::generate_encryption_key(&seed, Self::ENCRYPT_DERIVATION_PATH, sign_key_type) {
             Ok(v) => v,
             Err(_) => return Err(NodeXError{})
         };
 
         Ok(MnemonicKeyring {
             mnemonic,
+            passphrase,
+            has_passphrase,
             sign,
             update,
             recovery,
             encrypt,
             config,
-            secure_keystore,
+            key_store,
         })
     }
 
-    pub fn get_sign_key_pair(&self) -> Secp256k1 {
+    pub fn get_sign_key_pair(&self) -> SigningKey {
         self.sign.clone()
     }
 
@@ -143,7 +255,7 @@ impl MnemonicKeyring {
         self.recovery.clone()
     }
 
-    pub fn get_encrypt_key_pair(&self) -> Secp256k1 {
+    pub fn get_encrypt_key_pair(&self) -> EncryptionKey {
         self.encrypt.clone()
     }
 
@@ -162,55 +274,346 @@ impl MnemonicKeyring {
         }
     }
 
-    pub fn save(&mut self, did: &str) {
-        match self.secure_keystore.write(&SecureKeyStoreType::Sign, &KeyPair {
+    pub fn generate_x25519(seed: &[u8], derivation_path: &str) -> Result<X25519, NodeXError> {
+        let node = match runtime::bip32::BIP32::get_node(seed, derivation_path) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        X25519::new(&node.private_key)
+    }
+
+    // NOTE: the private key of the BIP32 node is the seed of the Ed25519 key (SLIP-0010 is not used,
+    //       so the key ring keeps a single derivation tree for every curve)
+    pub fn generate_ed25519(seed: &[u8], derivation_path: &str) -> Result<Ed25519, NodeXError> {
+        let node = match runtime::bip32::BIP32::get_node(seed, derivation_path) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        Ed25519::new(&node.private_key)
+    }
+
+    // NOTE: the private key of the BIP32 node is used as the P-256 scalar, a node out of the range of P-256 is an error
+    pub fn generate_p256(seed: &[u8], derivation_path: &str) -> Result<P256, NodeXError> {
+        let node = match runtime::bip32::BIP32::get_node(seed, derivation_path) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        P256::new(&node.private_key)
+    }
+
+    pub fn generate_signing_key(seed: &[u8], derivation_path: &str, key_type: KeyType) -> Result<SigningKey, NodeXError> {
+        match key_type {
+            KeyType::Secp256k1 => Self::generate_secp256k1(seed, derivation_path).map(SigningKey::Secp256k1),
+            KeyType::Ed25519 => Self::generate_ed25519(seed, derivation_path).map(SigningKey::Ed25519),
+            KeyType::P256 => Self::generate_p256(seed, derivation_path).map(SigningKey::P256),
+        }
+    }
+
+    pub fn generate_encryption_key(seed: &[u8], derivation_path: &str, key_type: KeyType) -> Result<EncryptionKey, NodeXError> {
+        match key_type {
+            KeyType::P256 => Self::generate_p256(seed, derivation_path).map(EncryptionKey::P256),
+            _ => Self::generate_x25519(seed, derivation_path).map(EncryptionKey::X25519),
+        }
+    }
+
+    // NOTE: the signing, update and recovery keys are rotated, the account level of their path is the rotation index
+    //       (index 0 is "m/44'/0'/0'/0/10", "m/44'/0'/0'/0/20" and "m/44'/0'/0'/0/30")
+    fn derivation_path(key: u32, index: u32) -> String {
+        format!("m/44'/0'/{}'/0/{}", index, key)
+    }
+
+    // NOTE: without the passphrase another key ring would be derived, so a passphrase which is not known is an error
+    fn mnemonic_to_seed(mnemonic: &str, passphrase: Option<&str>, has_passphrase: bool) -> Result<Vec<u8>, NodeXError> {
+        if has_passphrase && passphrase.is_none() {
+            log::error!("the BIP39 passphrase is not saved, restore the identity again to derive new keys");
+            return Err(NodeXError{})
+        }
+
+        match runtime::bip39::BIP39::mnemonic_to_seed(mnemonic, passphrase) {
+            Ok(v) => Ok(v),
+            Err(_) => Err(NodeXError{})
+        }
+    }
+
+    fn seed(&self) -> Result<Vec<u8>, NodeXError> {
+        Self::mnemonic_to_seed(&self.mnemonic, self.passphrase.as_deref(), self.has_passphrase)
+    }
+
+    // NOTE: a rotated signing key keeps the curve of the current one
+    pub fn derive_sign_key_pair(&self, index: u32) -> Result<SigningKey, NodeXError> {
+        let seed = match self.seed() {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        Self::generate_signing_key(&seed, &Self::derivation_path(Self::SIGN_DERIVATION_KEY, index), self.sign.get_type())
+    }
+
+    // NOTE: the signing key replacing the current one, a key store holding the signing key generates it
+    pub fn next_sign_key_pair(&self, index: u32) -> Result<SigningKey, NodeXError> {
+        let key_type = self.sign.get_type();
+
+        match self.key_store.generate(&key_type.sign_store_type()) {
+            Ok(Some(v)) => SigningKey::from_key_pair(key_type, v, &key_type.sign_store_type()),
+            Ok(None) => self.derive_sign_key_pair(index),
+            Err(_) => Err(NodeXError{})
+        }
+    }
+
+    pub fn derive_update_key_pair(&self, index: u32) -> Result<Secp256k1, NodeXError> {
+        let seed = match self.seed() {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        Self::generate_secp256k1(&seed, &Self::derivation_path(Self::UPDATE_DERIVATION_KEY, index))
+    }
+
+    pub fn derive_recovery_key_pair(&self, index: u32) -> Result<Secp256k1, NodeXError> {
+        let seed = match self.seed() {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        Self::generate_secp256k1(&seed, &Self::derivation_path(Self::RECOVERY_DERIVATION_KEY, index))
+    }
+
+    fn find_key_index<K, G: Fn(&[u8], &str) -> Result<K, NodeXError>, F: Fn(u32, &K) -> bool>(&self, key: u32, generate: G, predicate: F) -> Option<u32> {
+        let seed = match self.seed() {
+            Ok(v) => v,
+            Err(_) => return None
+        };
+
+        (0..=Self::MAX_DERIVATION_INDEX).find(|index| {
+            match generate(&seed, &Self::derivation_path(key, *index)) {
+                Ok(v) => predicate(*index, &v),
+                Err(_) => false,
+            }
+        })
+    }
+
+    pub fn find_sign_key_index<F: Fn(u32, &SigningKey) -> bool>(&self, predicate: F) -> Option<u32> {
+        let key_type = self.sign.get_type();
+
+        self.find_key_index(Self::SIGN_DERIVATION_KEY, |seed, path| Self::generate_signing_key(seed, path, key_type), predicate)
+    }
+
+    pub fn find_update_key_index<F: Fn(u32, &Secp256k1) -> bool>(&self, predicate: F) -> Option<u32> {
+        self.find_key_index(Self::UPDATE_DERIVATION_KEY, Self::generate_secp256k1, predicate)
+    }
+
+    pub fn find_recovery_key_index<F: Fn(u32, &Secp256k1) -> bool>(&self, predicate: F) -> Option<u32> {
+        self.find_key_index(Self::RECOVERY_DERIVATION_KEY, Self::generate_secp256k1, predicate)
+    }
+
+    // NOTE: the rotation index of a published signing key by its id, for a key which is not derived
+    pub fn find_sign_key_id_index<F: Fn(&str) -> bool>(predicate: F) -> Option<u32> {
+        (0..=Self::MAX_DERIVATION_INDEX).find(|index| predicate(&Self::sign_key_id(*index)))
+    }
+
+    // NOTE: the first signing key keeps the id it has always been published with
+    pub fn sign_key_id(index: u32) -> String {
+        match index {
+            0 => "signingKey".to_string(),
+            v => format!("signingKey-{}", v),
+        }
+    }
+
+    pub fn get_sign_key_id(&self) -> String {
+        Self::sign_key_id(self.get_sign_key_index())
+    }
+
+    pub fn get_sign_key_index(&self) -> u32 {
+        self.config.inner.lock().unwrap().get_sign_key_index()
+    }
+
+    pub fn get_update_key_index(&self) -> u32 {
+        self.config.inner.lock().unwrap().get_update_key_index()
+    }
+
+    pub fn get_recovery_key_index(&self) -> u32 {
+        self.config.inner.lock().unwrap().get_recovery_key_index()
+    }
+
+    pub fn save_sign_key_pair(&mut self, sign: &SigningKey, index: u32) -> Result<(), NodeXError> {
+        match self.key_store.write(&sign.get_type().sign_store_type(), &KeyPair {
+            public_key: sign.get_public_key(),
+            secret_key: sign.get_secret_key(),
+        }) {
+            Ok(_) => (),
+            Err(_) => return Err(NodeXError{}),
+        };
+
+        match self.config.inner.lock() {
+            Ok(mut config) => {
+                if config.save_sign_key_index(index, Utc::now().timestamp()).is_err() {
+                    return Err(NodeXError{})
+                }
+            },
+            _ => return Err(NodeXError{}),
+        };
+
+        self.sign = sign.clone();
+
+        Ok(())
+    }
+
+    pub fn save_update_key_pair(&mut self, update: &Secp256k1, index: u32) -> Result<(), NodeXError> {
+        match self.key_store.write(&SecureKeyStoreType::Update, &KeyPair {
+            public_key: update.get_public_key(),
+            secret_key: update.get_secret_key(),
+        }) {
+            Ok(_) => (),
+            Err(_) => return Err(NodeXError{}),
+        };
+
+        match self.config.inner.lock() {
+            Ok(mut config) => {
+                if config.save_update_key_index(index).is_err() {
+                    return Err(NodeXError{})
+                }
+            },
+            _ => return Err(NodeXError{}),
+        };
+
+        self.update = update.clone();
+
+        Ok(())
+    }
+
+    pub fn save_recovery_key_pair(&mut self, recovery: &Secp256k1, index: u32) -> Result<(), NodeXError> {
+        match self.key_store.write(&SecureKeyStoreType::Recover, &KeyPair {
+            public_key: recovery.get_public_key(),
+            secret_key: recovery.get_secret_key(),
+        }) {
+            Ok(_) => (),
+            Err(_) => return Err(NodeXError{}),
+        };
+
+        match self.config.inner.lock() {
+            Ok(mut config) => {
+                if config.save_recovery_key_index(index).is_err() {
+                    return Err(NodeXError{})
+                }
+            },
+            _ => return Err(NodeXError{}),
+        };
+
+        self.recovery = recovery.clone();
+
+        Ok(())
+    }
+
+    pub fn save(&mut self, did: &str) -> Result<(), NodeXError> {
+        match self.key_store.write(&self.sign.get_type().sign_store_type(), &KeyPair {
             public_key: self.get_sign_key_pair().get_public_key(),
             secret_key: self.get_sign_key_pair().get_secret_key(),
         }) {
             Ok(_) => (),
-            _ => panic!(),
+            _ => return Err(NodeXError{}),
         };
-        match self.secure_keystore.write(&SecureKeyStoreType::Update, &KeyPair {
+        match self.key_store.write(&SecureKeyStoreType::Update, &KeyPair {
             public_key: self.get_update_key_pair().get_public_key(),
             secret_key: self.get_update_key_pair().get_secret_key()
         }) {
             Ok(_) => (),
-            _ => panic!(),
+            _ => return Err(NodeXError{}),
         };
-        match self.secure_keystore.write(&SecureKeyStoreType::Recover, &KeyPair {
+        match self.key_store.write(&SecureKeyStoreType::Recover, &KeyPair {
             public_key: self.get_recovery_key_pair().get_public_key(),
             secret_key: self.get_recovery_key_pair().get_secret_key(),
         }) {
             Ok(_) => (),
-            _ => panic!(),
+            _ => return Err(NodeXError{}),
         };
-        match self.secure_keystore.write(&SecureKeyStoreType::Encrypt, &KeyPair {
+        match self.key_store.write(&self.sign.get_type().encrypt_store_type(), &KeyPair {
             public_key: self.get_encrypt_key_pair().get_public_key(),
             secret_key: self.get_encrypt_key_pair().get_secret_key(),
         }) {
             Ok(_) => (),
-            _ => panic!(),
+            _ => return Err(NodeXError{}),
         };
 
-        match self.config.inner.lock() {
-            Ok(mut config) => {
-                config.save_did(did)
-            },
-            _ => panic!(),
+        let mut config = match self.config.inner.lock() {
+            Ok(v) => v,
+            _ => return Err(NodeXError{}),
         };
 
-        match self.config.inner.lock() {
-            Ok(mut config) => {
-                config.save_mnemonic(&self.mnemonic);
-            },
-            _ => panic!(),
+        let saved = config.save_key_pair_type(self.sign.get_type())
+            .and_then(|_| config.save_did(did))
+            .and_then(|_| config.save_mnemonic(&self.mnemonic))
+            .and_then(|_| config.save_passphrase(self.passphrase.as_deref()))
+            .and_then(|_| config.save_is_initialized(true))
+            .and_then(|_| config.save_is_deactivated(false))
+            .and_then(|_| config.reset_key_indexes())
+            .and_then(|_| config.save_rotated_at(Utc::now().timestamp()));
+
+        match saved {
+            Ok(_) => Ok(()),
+            Err(_) => Err(NodeXError{}),
+        }
+    }
+
+    // NOTE: save the key ring with the keys currently published at the given rotation indexes
+    pub fn restore(&mut self, did: &str, sign_index: u32, update_index: u32, recovery_index: u32) -> Result<(), NodeXError> {
+        let key_type = self.sign.get_type();
+
+        let sign = if self.key_store.generates(&key_type.sign_store_type()) {
+            self.held_key_pair(&key_type.sign_store_type()).and_then(|v| SigningKey::from_key_pair(key_type, v, &key_type.sign_store_type()))
+        } else {
+            self.derive_sign_key_pair(sign_index)
+        };
+
+        self.sign = match sign {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        if self.key_store.generates(&key_type.encrypt_store_type()) {
+            self.encrypt = match self.held_key_pair(&key_type.encrypt_store_type()).and_then(|v| Self::encryption_key(key_type, v, &self.mnemonic, self.passphrase.as_deref(), self.has_passphrase)) {
+                Ok(v) => v,
+                Err(_) => return Err(NodeXError{})
+            };
+        }
+        self.update = match self.derive_update_key_pair(update_index) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+        self.recovery = match self.derive_recovery_key_pair(recovery_index) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
         };
 
+        if self.save(did).is_err() {
+            return Err(NodeXError{})
+        }
+
+        let mut config = match self.config.inner.lock() {
+            Ok(v) => v,
+            _ => return Err(NodeXError{}),
+        };
+
+        let saved = config.save_sign_key_index(sign_index, Utc::now().timestamp())
+            .and_then(|_| config.save_update_key_index(update_index))
+            .and_then(|_| config.save_recovery_key_index(recovery_index));
+
+        match saved {
+            Ok(_) => Ok(()),
+            Err(_) => Err(NodeXError{}),
+        }
+    }
+
+    pub fn is_deactivated(&self) -> bool {
+        self.config.inner.lock().unwrap().get_is_deactivated()
+    }
+
+    pub fn mark_deactivated(&mut self) -> Result<(), NodeXError> {
         match self.config.inner.lock() {
-            Ok(mut config) => {
-                config.save_is_initialized(true);
-            },
-            _ => panic!(),
+            Ok(mut config) => config.save_is_deactivated(true),
+            _ => Err(NodeXError{}),
         }
     }
 
@@ -223,12 +626,10 @@ impl MnemonicKeyring {
         }
     }
 
-    #[allow(dead_code)]
     pub fn get_mnemonic_phrase(&self) -> Result<Vec<String>, NodeXError> {
         Ok(self.mnemonic.split(' ').map(|v| v.to_string()).collect())
     }
 
-    #[allow(dead_code)]
     pub fn verify_mnemonic_phrase(&self, phrase: &Vec<String>) -> Result<bool, NodeXError> {
         let mnemonic = match self.get_mnemonic_phrase() {
             Ok(v) => v,
@@ -255,6 +656,7 @@ pub mod tests {
         assert_eq!(keyring.get_update_key_pair().get_secret_key().len(), 32);
         assert_eq!(keyring.get_recovery_key_pair().get_secret_key().len(), 32);
         assert_eq!(keyring.get_encrypt_key_pair().get_secret_key().len(), 32);
+        assert_eq!(keyring.get_encrypt_key_pair().get_public_key().len(), 32);
     }
 
     #[test]
@@ -291,4 +693,144 @@ pub mod tests {
 
         assert!(result)
     }
-}
\ No newline at end of file
+
+    #[test]
+    pub fn test_derive_key_pair() {
+        let keyring = match MnemonicKeyring::create_keyring() {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        // NOTE: index 0 is the key ring created with the mnemonic
+        assert_eq!(keyring.derive_sign_key_pair(0).unwrap().get_secret_key(), keyring.get_sign_key_pair().get_secret_key());
+        assert_eq!(keyring.derive_update_key_pair(0).unwrap().get_secret_key(), keyring.get_update_key_pair().get_secret_key());
+        assert_eq!(keyring.derive_recovery_key_pair(0).unwrap().get_secret_key(), keyring.get_recovery_key_pair().get_secret_key());
+
+        let sign = keyring.derive_sign_key_pair(1).unwrap();
+        let update = keyring.derive_update_key_pair(1).unwrap();
+
+        assert_ne!(sign.get_secret_key(), keyring.get_sign_key_pair().get_secret_key());
+        assert_ne!(update.get_secret_key(), keyring.get_update_key_pair().get_secret_key());
+        assert_ne!(sign.get_secret_key(), update.get_secret_key());
+        assert_eq!(keyring.derive_sign_key_pair(1).unwrap().get_secret_key(), sign.get_secret_key());
+    }
+
+    #[test]
+    pub fn test_sign_key_id() {
+        assert_eq!(MnemonicKeyring::sign_key_id(0), "signingKey");
+        assert_eq!(MnemonicKeyring::sign_key_id(3), "signingKey-3");
+    }
+
+    #[test]
+    pub fn test_restore_keyring() {
+        let keyring = match MnemonicKeyring::create_keyring() {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        let restored = match MnemonicKeyring::restore_keyring(&format!(" {}\n", keyring.mnemonic), None) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert_eq!(restored.get_sign_key_pair().get_secret_key(), keyring.get_sign_key_pair().get_secret_key());
+        assert_eq!(restored.get_recovery_key_pair().get_secret_key(), keyring.get_recovery_key_pair().get_secret_key());
+        assert_eq!(restored.get_encrypt_key_pair().get_secret_key(), keyring.get_encrypt_key_pair().get_secret_key());
+
+        // NOTE: a key ring saved with the secp256k1 key at the same path loads the same X25519 key
+        let seed = runtime::bip39::BIP39::mnemonic_to_seed(&keyring.mnemonic, None).unwrap();
+        let secp256k1 = MnemonicKeyring::generate_secp256k1(&seed, MnemonicKeyring::ENCRYPT_DERIVATION_PATH).unwrap();
+        let migrated = MnemonicKeyring::encrypt_key_pair(KeyPair {
+            public_key: secp256k1.get_public_key(),
+            secret_key: secp256k1.get_secret_key(),
+        }, &keyring.mnemonic, None, false).unwrap();
+
+        assert_ne!(secp256k1.get_public_key(), keyring.get_encrypt_key_pair().get_public_key());
+        assert_eq!(migrated.get_public_key(), keyring.get_encrypt_key_pair().get_public_key());
+
+        // NOTE: a passphrase derives another key ring
+        let protected = MnemonicKeyring::restore_keyring(&keyring.mnemonic, Some("password")).unwrap();
+        assert_ne!(protected.get_recovery_key_pair().get_secret_key(), keyring.get_recovery_key_pair().get_secret_key());
+
+        assert!(MnemonicKeyring::restore_keyring("fiction ladder avocado", None).is_err());
+    }
+
+    #[test]
+    pub fn test_derive_without_passphrase() {
+        let mut keyring = match MnemonicKeyring::create_keyring() {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        // NOTE: a passphrase which is not saved is not known after a restart
+        keyring.passphrase = Some("password".to_string());
+        keyring.has_passphrase = true;
+
+        assert!(keyring.derive_update_key_pair(1).is_ok());
+
+        keyring.passphrase = None;
+
+        assert!(keyring.derive_update_key_pair(1).is_err());
+        assert!(keyring.derive_sign_key_pair(1).is_err());
+        assert_eq!(keyring.find_update_key_index(|_, _| true), None);
+    }
+
+    #[test]
+    pub fn test_find_key_index() {
+        let keyring = match MnemonicKeyring::create_keyring() {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        let rotated = keyring.derive_update_key_pair(3).unwrap().get_public_key();
+
+        assert_eq!(keyring.find_update_key_index(|_, v| v.get_public_key() == rotated), Some(3));
+        assert_eq!(keyring.find_sign_key_index(|_, v| v.get_public_key() == rotated), None);
+        assert_eq!(keyring.find_recovery_key_index(|_, v| v.get_public_key() == rotated), None);
+
+        let recovery = keyring.derive_recovery_key_pair(2).unwrap().get_public_key();
+
+        assert_eq!(keyring.find_recovery_key_index(|_, v| v.get_public_key() == recovery), Some(2));
+    }
+
+    #[test]
+    pub fn test_generate_signing_key() {
+        let keyring = match MnemonicKeyring::create_keyring() {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        let seed = runtime::bip39::BIP39::mnemonic_to_seed(&keyring.mnemonic, None).unwrap();
+        let path = MnemonicKeyring::derivation_path(MnemonicKeyring::SIGN_DERIVATION_KEY, 0);
+
+        let ed25519 = match MnemonicKeyring::generate_signing_key(&seed, &path, KeyType::Ed25519) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert_eq!(ed25519.get_type(), KeyType::Ed25519);
+        assert_eq!(ed25519.get_public_key().len(), 32);
+
+        // NOTE: both curves share the secret key of the BIP32 node
+        assert_eq!(ed25519.get_secret_key(), keyring.get_sign_key_pair().get_secret_key());
+        assert_ne!(ed25519.get_public_key(), keyring.get_sign_key_pair().get_public_key());
+
+        let p256 = match MnemonicKeyring::generate_signing_key(&seed, &path, KeyType::P256) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert_eq!(p256.get_type(), KeyType::P256);
+        assert_eq!(p256.get_public_key().len(), 65);
+        assert_eq!(p256.get_secret_key(), keyring.get_sign_key_pair().get_secret_key());
+
+        // NOTE: a P-256 key ring agrees on keys with P-256
+        let encrypt = match MnemonicKeyring::generate_encryption_key(&seed, MnemonicKeyring::ENCRYPT_DERIVATION_PATH, KeyType::P256) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert!(matches!(encrypt, EncryptionKey::P256(_)));
+        assert_eq!(encrypt.get_secret_key(), MnemonicKeyring::generate_secp256k1(&seed, MnemonicKeyring::ENCRYPT_DERIVATION_PATH).unwrap().get_secret_key());
+    }
+}
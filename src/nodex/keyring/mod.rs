@@ -1,2 +1,7 @@
 pub mod secp256k1;
+pub mod ed25519;
+pub mod p256;
+pub mod signing_key;
+pub mod encryption_key;
+pub mod x25519;
 pub mod mnemonic;
\ No newline at end of file
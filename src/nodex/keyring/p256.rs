@@ -0,0 +1,208 @@
+use crate::nodex::sidetree::payload::PublicKeyPayload;
+use crate::nodex::{errors::NodeXError, extension::secure_keystore::SecureKeyStoreType, runtime::{self, base64_url::PaddingType}};
+
+use super::secp256k1::KeyPairSecp256K1;
+
+#[derive(Clone)]
+pub struct P256 {
+    public : Vec<u8>,
+    private: Vec<u8>,
+    // NOTE: set when the secret key never leaves the secure element
+    key_type: Option<SecureKeyStoreType>,
+}
+
+impl P256 {
+    const PRIVATE_KEY_SIZE: usize = 32;
+
+    pub fn new(secret: &[u8]) -> Result<Self, NodeXError> {
+        if secret.len() != Self::PRIVATE_KEY_SIZE {
+            return Err(NodeXError{})
+        }
+
+        let public = match runtime::p256::P256::generate_public_key(secret) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        Ok(P256 {
+            public,
+            private: secret.to_vec(),
+            key_type: None,
+        })
+    }
+
+    // NOTE: the public key is either compressed or uncompressed (SEC1), it is kept uncompressed
+    pub fn from_public_key(public: &[u8]) -> Result<Self, NodeXError> {
+        let public = match runtime::p256::P256::convert_public_key(public, false) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        Ok(P256 {
+            public,
+            private: vec![],
+            key_type: None,
+        })
+    }
+
+    pub fn new_external(public: &[u8], key_type: &SecureKeyStoreType) -> Result<Self, NodeXError> {
+        match P256::from_public_key(public) {
+            Ok(v) => {
+                Ok(P256 {
+                    key_type: Some(*key_type),
+                    ..v
+                })
+            },
+            Err(_) => Err(NodeXError{})
+        }
+    }
+
+    pub fn get_key_type(&self) -> Option<SecureKeyStoreType> {
+        self.key_type
+    }
+
+    pub fn get_public_key(&self) -> Vec<u8> {
+        self.public.clone()
+    }
+
+    pub fn get_secret_key(&self) -> Vec<u8> {
+        self.private.clone()
+    }
+
+    pub fn from_jwk(jwk: &KeyPairSecp256K1) -> Result<Self, NodeXError> {
+        if jwk.kty != "EC" || jwk.crv != "P-256" {
+            return Err(NodeXError{})
+        }
+
+        let x = match runtime::base64_url::Base64Url::decode_as_bytes(&jwk.x, &PaddingType::NoPadding) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+        let y = match runtime::base64_url::Base64Url::decode_as_bytes(&jwk.y, &PaddingType::NoPadding) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        let context = match P256::from_public_key(&[ &[ 0x04 ], &x[..], &y[..] ].concat()) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        let d = match &jwk.d {
+            Some(v) => v,
+            None => return Ok(context),
+        };
+
+        let private = match runtime::base64_url::Base64Url::decode_as_bytes(d, &PaddingType::NoPadding) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        Ok(P256 {
+            private,
+            ..context
+        })
+    }
+
+    pub fn to_jwk(&self, included_private_key: bool) -> Result<KeyPairSecp256K1, NodeXError> {
+        // NOTE: 0x04 || x (32 bytes) || y (32 bytes)
+        if self.public.len() != 65 || self.public[0] != 0x04 {
+            return Err(NodeXError{})
+        }
+
+        let d = if included_private_key {
+            Some(runtime::base64_url::Base64Url::encode(&self.get_secret_key(), &PaddingType::NoPadding))
+        } else {
+            None
+        };
+
+        Ok(KeyPairSecp256K1 {
+            kty: "EC".to_string(),
+            crv: "P-256".to_string(),
+            x  : runtime::base64_url::Base64Url::encode(&self.public[1..33], &PaddingType::NoPadding),
+            y  : runtime::base64_url::Base64Url::encode(&self.public[33..], &PaddingType::NoPadding),
+            d,
+            kid: None,
+        })
+    }
+
+    pub fn to_public_key(&self, key_id: &str, purpose: &[&str]) -> Result<PublicKeyPayload, NodeXError> {
+        let jwk = match self.to_jwk(false) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        Ok(PublicKeyPayload {
+            id: key_id.to_string(),
+            r#type: "EcdsaSecp256r1VerificationKey2019".to_string(),
+            jwk,
+            purpose: purpose.iter().map(|value| value.to_string()).collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_to_jwk() {
+        // NOTE: RFC 7518, Appendix C (the key of Bob)
+        let secret = match runtime::base64_url::Base64Url::decode_as_bytes("VEmDZpDXXK8p8N0Cndsxs924q6nS1RXFASRl6BfUqdw", &PaddingType::NoPadding) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        let context = match P256::new(&secret) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        let jwk = match context.to_jwk(true) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert_eq!(jwk.crv, "P-256");
+        assert_eq!(jwk.x, "weNJy2HscCSM6AEDTDg04biOvhFhyyWvOHQfeF_PxMQ");
+        assert_eq!(jwk.y, "e8lnCO-AlStT-NJVX-crhB7QRYhiix03illJOVAOyck");
+
+        let clone = match P256::from_jwk(&jwk) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert_eq!(clone.get_public_key(), context.get_public_key());
+        assert_eq!(clone.get_secret_key(), secret);
+
+        let public = match context.to_jwk(false).and_then(|v| P256::from_jwk(&v)) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert!(public.get_secret_key().is_empty());
+    }
+
+    #[test]
+    pub fn test_new_external() {
+        let context = match P256::new(&[ 0x01; 32 ]) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        let compressed = match runtime::p256::P256::convert_public_key(&context.get_public_key(), true) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        let result = match P256::new_external(&compressed, &SecureKeyStoreType::SignP256) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert_eq!(result.get_public_key(), context.get_public_key());
+        assert_eq!(result.get_key_type(), Some(SecureKeyStoreType::SignP256));
+        assert!(result.get_secret_key().is_empty());
+        assert!(P256::new_external(&[ 0x04; 65 ], &SecureKeyStoreType::SignP256).is_err());
+    }
+}
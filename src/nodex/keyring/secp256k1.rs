@@ -1,5 +1,5 @@
 use crate::nodex::sidetree::payload::PublicKeyPayload;
-use crate::nodex::{errors::NodeXError, runtime::base64_url::PaddingType};
+use crate::nodex::{errors::NodeXError, extension::secure_keystore::SecureKeyStoreType, runtime::base64_url::PaddingType};
 use crate::nodex::runtime;
 use std::cmp::Ordering;
 use std::u8;
@@ -18,7 +18,8 @@ pub struct KeyPairSecp256K1 {
     #[serde(rename = "x")]
     pub x: String,
 
-    #[serde(rename = "y")]
+    // NOTE: absent for OKP keys
+    #[serde(rename = "y", default, skip_serializing_if = "String::is_empty")]
     pub y: String,
 
     #[serde(rename = "d", skip_serializing_if = "Option::is_none")]
@@ -43,6 +44,8 @@ pub struct Secp256k1HexKeyPair {
 pub struct Secp256k1 {
     public : Vec<u8>,
     private: Vec<u8>,
+    // NOTE: set when the secret key never leaves the secure element
+    key_type: Option<SecureKeyStoreType>,
 }
 
 impl Secp256k1 {
@@ -64,6 +67,7 @@ impl Secp256k1 {
             return Ok(Secp256k1 {
                 public,
                 private: context.secret.clone(),
+                key_type: None,
             })
         }
 
@@ -71,12 +75,49 @@ impl Secp256k1 {
             return Ok(Secp256k1 {
                 public : context.public.clone(),
                 private: context.secret.clone(),
+                key_type: None,
             })
         }
 
         Err(NodeXError{})
     }
 
+    // NOTE: a key of another party, there is no secret key
+    pub fn from_public_key(public: &[u8]) -> Result<Self, NodeXError> {
+        let public = match public.len() {
+            Self::COMPRESSED_PUBLIC_KEY_SIZE => {
+                match Secp256k1::transform_uncompressed_public_key(public) {
+                    Ok(v) => v,
+                    Err(_) => return Err(NodeXError{})
+                }
+            },
+            Self::UNCOMPRESSED_PUBLIC_KEY_SIZE => public.to_vec(),
+            _ => return Err(NodeXError{})
+        };
+
+        Ok(Secp256k1 {
+            public,
+            private: vec![],
+            key_type: None,
+        })
+    }
+
+    pub fn new_external(public: &[u8], key_type: &SecureKeyStoreType) -> Result<Self, NodeXError> {
+        match Secp256k1::from_public_key(public) {
+            Ok(v) => {
+                Ok(Secp256k1 {
+                    key_type: Some(*key_type),
+                    ..v
+                })
+            },
+            Err(_) => Err(NodeXError{})
+        }
+    }
+
+    pub fn get_key_type(&self) -> Option<SecureKeyStoreType> {
+        self.key_type
+    }
+
     pub fn get_public_key(&self) -> Vec<u8> {
         self.public.clone()
     }
@@ -120,6 +161,7 @@ impl Secp256k1 {
         Ok(Secp256k1 {
             public,
             private,
+            key_type: None,
         })
     }
 
@@ -455,4 +497,60 @@ pub mod tests {
         assert_eq!(clone.get_public_key(), node.get_public_key());
         assert_eq!(clone.get_secret_key(), node.get_secret_key());
     }
+
+    #[test]
+    pub fn test_new_external() {
+        let node = match Secp256k1::new(&Secp256k1Context {
+            public: public_key(),
+            secret: private_key(),
+        }) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        let external = match Secp256k1::new_external(&public_key(), &SecureKeyStoreType::Sign) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert_eq!(external.get_public_key(), node.get_public_key());
+        assert!(external.get_secret_key().is_empty());
+        assert_eq!(external.get_key_type(), Some(SecureKeyStoreType::Sign));
+        assert_eq!(node.get_key_type(), None);
+    }
+
+    #[test]
+    pub fn test_from_public_key() {
+        let node = match Secp256k1::new(&Secp256k1Context {
+            public: public_key(),
+            secret: private_key(),
+        }) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        let public = match Secp256k1::from_public_key(&public_key()) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert_eq!(public.get_public_key(), node.get_public_key());
+        assert!(public.get_secret_key().is_empty());
+        assert_eq!(public.get_key_type(), None);
+
+        let jwk = match public.to_jwk(false) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        let expected = match node.to_jwk(false) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert_eq!(jwk.x, expected.x);
+        assert_eq!(jwk.y, expected.y);
+
+        assert!(Secp256k1::from_public_key(&[0x04; 10]).is_err());
+    }
 }
\ No newline at end of file
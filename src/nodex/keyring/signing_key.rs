@@ -0,0 +1,169 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{config::KeyPair, nodex::{errors::NodeXError, extension::secure_keystore::SecureKeyStoreType, sidetree::payload::PublicKeyPayload}};
+
+use super::{ed25519::Ed25519, p256::P256, secp256k1::{KeyPairSecp256K1, Secp256k1, Secp256k1Context}};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum KeyType {
+    #[default]
+    #[serde(rename = "secp256k1")]
+    Secp256k1,
+    #[serde(rename = "ed25519")]
+    Ed25519,
+    #[serde(rename = "p256")]
+    P256,
+}
+
+impl KeyType {
+    pub fn alg(&self) -> &'static str {
+        match self {
+            KeyType::Secp256k1 => "ES256K",
+            KeyType::Ed25519 => "EdDSA",
+            KeyType::P256 => "ES256",
+        }
+    }
+
+    pub fn proof_type(&self) -> &'static str {
+        match self {
+            KeyType::Secp256k1 => "EcdsaSecp256k1Signature2019",
+            KeyType::Ed25519 => "Ed25519Signature2018",
+            KeyType::P256 => "EcdsaSecp256r1Signature2019",
+        }
+    }
+
+    // NOTE: the slots of the key store holding the keys of the curve, a P-256 key ring agrees on keys with P-256 too
+    pub fn sign_store_type(&self) -> SecureKeyStoreType {
+        match self {
+            KeyType::P256 => SecureKeyStoreType::SignP256,
+            _ => SecureKeyStoreType::Sign,
+        }
+    }
+
+    pub fn encrypt_store_type(&self) -> SecureKeyStoreType {
+        match self {
+            KeyType::P256 => SecureKeyStoreType::EncryptP256,
+            _ => SecureKeyStoreType::Encrypt,
+        }
+    }
+}
+
+// NOTE: the key signing JWS, credentials and DIDComm messages
+#[derive(Clone)]
+pub enum SigningKey {
+    Secp256k1(Secp256k1),
+    Ed25519(Ed25519),
+    P256(P256),
+}
+
+impl SigningKey {
+    // NOTE: a secure element keeping the secret key returns the public key only
+    pub fn from_key_pair(key_type: KeyType, key_pair: KeyPair, store_type: &SecureKeyStoreType) -> Result<Self, NodeXError> {
+        match (key_type, key_pair.secret_key.is_empty()) {
+            (KeyType::Secp256k1, true) => Secp256k1::new_external(&key_pair.public_key, store_type).map(SigningKey::Secp256k1),
+            (KeyType::Secp256k1, false) => {
+                Secp256k1::new(&Secp256k1Context {
+                    public: key_pair.public_key,
+                    secret: key_pair.secret_key,
+                }).map(SigningKey::Secp256k1)
+            },
+            (KeyType::Ed25519, true) => Ed25519::new_external(&key_pair.public_key, store_type).map(SigningKey::Ed25519),
+            (KeyType::Ed25519, false) => Ed25519::new(&key_pair.secret_key).map(SigningKey::Ed25519),
+            (KeyType::P256, true) => P256::new_external(&key_pair.public_key, store_type).map(SigningKey::P256),
+            (KeyType::P256, false) => P256::new(&key_pair.secret_key).map(SigningKey::P256),
+        }
+    }
+
+    pub fn from_jwk(jwk: &KeyPairSecp256K1) -> Result<Self, NodeXError> {
+        match (jwk.kty.as_str(), jwk.crv.as_str()) {
+            ("EC", "secp256k1") => Secp256k1::from_jwk(jwk).map(SigningKey::Secp256k1),
+            ("OKP", "Ed25519") => Ed25519::from_jwk(jwk).map(SigningKey::Ed25519),
+            ("EC", "P-256") => P256::from_jwk(jwk).map(SigningKey::P256),
+            _ => Err(NodeXError{}),
+        }
+    }
+
+    pub fn get_type(&self) -> KeyType {
+        match self {
+            SigningKey::Secp256k1(_) => KeyType::Secp256k1,
+            SigningKey::Ed25519(_) => KeyType::Ed25519,
+            SigningKey::P256(_) => KeyType::P256,
+        }
+    }
+
+    pub fn get_key_type(&self) -> Option<SecureKeyStoreType> {
+        match self {
+            SigningKey::Secp256k1(v) => v.get_key_type(),
+            SigningKey::Ed25519(v) => v.get_key_type(),
+            SigningKey::P256(v) => v.get_key_type(),
+        }
+    }
+
+    pub fn get_public_key(&self) -> Vec<u8> {
+        match self {
+            SigningKey::Secp256k1(v) => v.get_public_key(),
+            SigningKey::Ed25519(v) => v.get_public_key(),
+            SigningKey::P256(v) => v.get_public_key(),
+        }
+    }
+
+    pub fn get_secret_key(&self) -> Vec<u8> {
+        match self {
+            SigningKey::Secp256k1(v) => v.get_secret_key(),
+            SigningKey::Ed25519(v) => v.get_secret_key(),
+            SigningKey::P256(v) => v.get_secret_key(),
+        }
+    }
+
+    pub fn to_jwk(&self, included_private_key: bool) -> Result<KeyPairSecp256K1, NodeXError> {
+        match self {
+            SigningKey::Secp256k1(v) => v.to_jwk(included_private_key),
+            SigningKey::Ed25519(v) => v.to_jwk(included_private_key),
+            SigningKey::P256(v) => v.to_jwk(included_private_key),
+        }
+    }
+
+    pub fn to_public_key(&self, key_id: &str, purpose: &[&str]) -> Result<PublicKeyPayload, NodeXError> {
+        match self {
+            SigningKey::Secp256k1(v) => v.to_public_key(key_id, purpose),
+            SigningKey::Ed25519(v) => v.to_public_key(key_id, purpose),
+            SigningKey::P256(v) => v.to_public_key(key_id, purpose),
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_key_type() {
+        assert_eq!(KeyType::default(), KeyType::Secp256k1);
+        assert_eq!(KeyType::Ed25519.alg(), "EdDSA");
+        assert_eq!(KeyType::Secp256k1.proof_type(), "EcdsaSecp256k1Signature2019");
+        assert_eq!(serde_json::to_string(&KeyType::Ed25519).unwrap(), "\"ed25519\"");
+        assert_eq!(serde_json::from_str::<KeyType>("\"p256\"").unwrap().alg(), "ES256");
+        assert_eq!(KeyType::P256.sign_store_type(), SecureKeyStoreType::SignP256);
+        assert_eq!(KeyType::Ed25519.encrypt_store_type(), SecureKeyStoreType::Encrypt);
+    }
+
+    #[test]
+    pub fn test_from_jwk() {
+        let ed25519 = SigningKey::Ed25519(Ed25519::new(&[ 0x01; 32 ]).unwrap());
+
+        let result = match SigningKey::from_jwk(&ed25519.to_jwk(false).unwrap()) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert_eq!(result.get_type(), KeyType::Ed25519);
+        assert_eq!(result.get_public_key(), ed25519.get_public_key());
+
+        let secp256k1 = SigningKey::from_key_pair(KeyType::Secp256k1, KeyPair {
+            public_key: vec![],
+            secret_key: vec![],
+        }, &SecureKeyStoreType::Sign);
+
+        assert!(secp256k1.is_err());
+    }
+}
@@ -0,0 +1,146 @@
+use std::convert::TryInto;
+use x25519_dalek::{PublicKey, StaticSecret};
+use crate::nodex::sidetree::payload::PublicKeyPayload;
+use crate::nodex::{errors::NodeXError, runtime::{self, base64_url::PaddingType}};
+
+use super::secp256k1::KeyPairSecp256K1;
+
+// NOTE: the key agreement key, it is published as an OKP JWK and never signs
+#[derive(Clone)]
+pub struct X25519 {
+    public : Vec<u8>,
+    private: Vec<u8>,
+}
+
+impl X25519 {
+    const KEY_SIZE: usize = 32;
+
+    pub fn new(secret: &[u8]) -> Result<Self, NodeXError> {
+        let secret: [u8; Self::KEY_SIZE] = match secret.try_into() {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        let sk = StaticSecret::from(secret);
+        let pk = PublicKey::from(&sk);
+
+        Ok(X25519 {
+            public : pk.as_bytes().to_vec(),
+            private: sk.to_bytes().to_vec(),
+        })
+    }
+
+    pub fn from_jwk(jwk: &KeyPairSecp256K1) -> Result<Self, NodeXError> {
+        if jwk.kty != "OKP" || jwk.crv != "X25519" {
+            return Err(NodeXError{})
+        }
+
+        let public = match runtime::base64_url::Base64Url::decode_as_bytes(&jwk.x, &PaddingType::NoPadding) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        if public.len() != Self::KEY_SIZE {
+            return Err(NodeXError{})
+        }
+
+        Ok(X25519 {
+            public,
+            private: vec![],
+        })
+    }
+
+    pub fn get_public_key(&self) -> Vec<u8> {
+        self.public.clone()
+    }
+
+    pub fn get_secret_key(&self) -> Vec<u8> {
+        self.private.clone()
+    }
+
+    pub fn ecdh(&self, public_key: &[u8]) -> Result<Vec<u8>, NodeXError> {
+        let (secret, public): ([u8; Self::KEY_SIZE], [u8; Self::KEY_SIZE]) = match (self.private.as_slice().try_into(), public_key.try_into()) {
+            (Ok(u), Ok(v)) => (u, v),
+            _ => return Err(NodeXError{})
+        };
+
+        Ok(StaticSecret::from(secret).diffie_hellman(&PublicKey::from(public)).as_bytes().to_vec())
+    }
+
+    pub fn to_jwk(&self) -> Result<KeyPairSecp256K1, NodeXError> {
+        if self.public.len() != Self::KEY_SIZE {
+            return Err(NodeXError{})
+        }
+
+        Ok(KeyPairSecp256K1 {
+            kty: "OKP".to_string(),
+            crv: "X25519".to_string(),
+            x  : runtime::base64_url::Base64Url::encode(&self.public, &PaddingType::NoPadding),
+            y  : "".to_string(),
+            d  : None,
+            kid: None,
+        })
+    }
+
+    pub fn to_public_key(&self, key_id: &str, purpose: &[&str]) -> Result<PublicKeyPayload, NodeXError> {
+        let jwk = match self.to_jwk() {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        Ok(PublicKeyPayload {
+            id: key_id.to_string(),
+            r#type: "JsonWebKey2020".to_string(),
+            jwk,
+            purpose: purpose.iter().map(|value| value.to_string()).collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_new() {
+        // NOTE: RFC 7748, 6.1
+        let secret = match hex::decode("77076d0a7318a57d3c16c17251b26645df4c2f87ebc0992ab177fba51db92c2a") {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        let result = match X25519::new(&secret) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert_eq!(hex::encode(result.get_public_key()), "8520f0098930a754748b7ddcb43ef75a0dbf3a0d26381af4eba4a98eaa9b4e6a");
+        assert!(X25519::new(&secret[..31]).is_err());
+    }
+
+    #[test]
+    pub fn test_jwk() {
+        let key = match X25519::new(&[ 0x01; 32 ]) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        let jwk = match key.to_jwk() {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert_eq!(serde_json::to_value(&jwk).ok().map(|v| v["y"].clone()), Some(serde_json::Value::Null));
+
+        let result = match X25519::from_jwk(&jwk) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert_eq!(result.get_public_key(), key.get_public_key());
+        assert!(result.get_secret_key().is_empty());
+
+        let secp256k1 = KeyPairSecp256K1 { kty: "EC".to_string(), crv: "secp256k1".to_string(), ..jwk };
+        assert!(X25519::from_jwk(&secp256k1).is_err());
+    }
+}
@@ -0,0 +1,97 @@
+use crate::{config::KeyPair, nodex::{errors::NodeXError, extension::secure_keystore::SecureKeyStoreType}, app_config};
+
+use super::KeyStore;
+
+// NOTE: the key pairs are kept in config.json, sealed when the encryption of config.json is enabled
+pub struct FileKeyStore {
+}
+
+impl FileKeyStore {
+    pub fn new() -> FileKeyStore {
+        FileKeyStore {}
+    }
+}
+
+impl KeyStore for FileKeyStore {
+    fn write(&self, key_type: &SecureKeyStoreType, key_pair: &KeyPair) -> Result<(), NodeXError> {
+        log::info!("Called: write_internal (type: {:?})", key_type);
+
+        let config = app_config();
+
+        match key_type {
+            SecureKeyStoreType::Sign | SecureKeyStoreType::SignP256 => {
+                match config.inner.lock() {
+                    Ok(mut config) => {
+                        config.save_sign_key_pair(key_pair)
+                    },
+                    _ => Err(NodeXError {}),
+                }
+            },
+            SecureKeyStoreType::Update => {
+                match config.inner.lock() {
+                    Ok(mut config) => {
+                        config.save_update_key_pair(key_pair)
+                    },
+                    _ => Err(NodeXError {}),
+                }
+            },
+            SecureKeyStoreType::Recover => {
+                match config.inner.lock() {
+                    Ok(mut config) => {
+                        config.save_recover_key_pair(key_pair)
+                    },
+                    _ => Err(NodeXError {}),
+                }
+            },
+            SecureKeyStoreType::Encrypt | SecureKeyStoreType::EncryptP256 => {
+                match config.inner.lock() {
+                    Ok(mut config) => {
+                        config.save_encrypt_key_pair(key_pair)
+                    },
+                    _ => Err(NodeXError {}),
+                }
+            },
+        }
+    }
+
+    fn read(&self, key_type: &SecureKeyStoreType) -> Result<Option<KeyPair>, NodeXError> {
+        log::info!("Called: read_internal (type: {:?})", key_type);
+
+        let config = app_config();
+
+        match key_type {
+            SecureKeyStoreType::Sign | SecureKeyStoreType::SignP256 => {
+                match config.inner.lock() {
+                    Ok(config) => {
+                        Ok(config.load_sign_key_pair())
+                    },
+                    _ => Err(NodeXError {}),
+                }
+            },
+            SecureKeyStoreType::Update => {
+                match config.inner.lock() {
+                    Ok(config) => {
+                        Ok(config.load_update_key_pair())
+                    },
+                    _ => Err(NodeXError {}),
+                }
+            },
+            SecureKeyStoreType::Recover => {
+                match config.inner.lock() {
+                    Ok(config) => {
+                        Ok(config.load_recovery_key_pair())
+                    },
+                    _ => Err(NodeXError {}),
+                }
+            },
+            SecureKeyStoreType::Encrypt | SecureKeyStoreType::EncryptP256 => {
+                match config.inner.lock() {
+                    Ok(config) => {
+                        Ok(config.load_encrypt_key_pair())
+                    },
+                    _ => Err(NodeXError {}),
+                }
+            },
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use crate::{config::{current_profile, KeyPair, KeyStoreBackend}, nodex::{errors::NodeXError, extension::secure_keystore::{ExtensionKeyStore, SecureKeyStoreType}, keyring::signing_key::SigningKey}, app_config};
+
+use self::{file::FileKeyStore, pkcs11::Pkcs11KeyStore};
+
+pub mod file;
+pub mod pkcs11;
+
+// NOTE: a backend keeping the secret key in a token returns an empty secret key, the key ring then refers to the key
+//       by its slot (the handle) and the backend signs and agrees on keys with it
+pub trait KeyStore: Send + Sync {
+    fn write(&self, key_type: &SecureKeyStoreType, key_pair: &KeyPair) -> Result<(), NodeXError>;
+
+    fn read(&self, key_type: &SecureKeyStoreType) -> Result<Option<KeyPair>, NodeXError>;
+
+    // NOTE: a backend creating the key itself keeps it and returns the public key, None lets the key ring derive the key
+    fn generate(&self, _key_type: &SecureKeyStoreType) -> Result<Option<KeyPair>, NodeXError> {
+        Ok(None)
+    }
+
+    fn generates(&self, _key_type: &SecureKeyStoreType) -> bool {
+        false
+    }
+
+    fn sign(&self, _key_type: &SecureKeyStoreType, _context: &SigningKey, _message: &[u8]) -> Result<Vec<u8>, NodeXError> {
+        Err(NodeXError{})
+    }
+
+    fn ecdh(&self, _key_type: &SecureKeyStoreType, _public_key: &[u8]) -> Result<Vec<u8>, NodeXError> {
+        Err(NodeXError{})
+    }
+}
+
+// NOTE: the backend selected in config.json, the keys of the PKCS#11 token are labelled with the running profile
+pub fn key_store() -> Result<Box<dyn KeyStore>, NodeXError> {
+    let config = app_config();
+    let (backend, pkcs11) = match config.inner.lock() {
+        Ok(config) => {
+            (config.load_key_store_backend(), config.load_pkcs11_config())
+        },
+        _ => return Err(NodeXError{}),
+    };
+
+    match backend {
+        KeyStoreBackend::File => Ok(Box::new(FileKeyStore::new())),
+        KeyStoreBackend::Extension => Ok(Box::new(ExtensionKeyStore::new())),
+        KeyStoreBackend::Pkcs11 => {
+            match pkcs11 {
+                Some(v) => Ok(Box::new(Pkcs11KeyStore::new(v, &current_profile()))),
+                None => Err(NodeXError{}),
+            }
+        },
+    }
+}
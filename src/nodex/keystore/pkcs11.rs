@@ -0,0 +1,624 @@
+use std::{collections::BTreeMap, convert::TryFrom, ffi::c_void, ptr, sync::{Mutex, OnceLock}};
+
+use cryptoki::{
+    context::{CInitializeArgs, Pkcs11},
+    error::{Error, RvError},
+    mechanism::{Mechanism, elliptic_curve::{Ecdh1DeriveParams, EcKdfType}},
+    object::{Attribute, AttributeType, KeyType, ObjectClass, ObjectHandle},
+    session::{Session, UserType},
+    types::Ulong,
+};
+
+use crate::{config::{KeyPair, Pkcs11Config}, nodex::{errors::NodeXError, extension::secure_keystore::SecureKeyStoreType, keyring::signing_key::SigningKey, runtime}};
+
+use super::KeyStore;
+
+// NOTE: DER encoded OIDs of the curves (CKA_EC_PARAMS)
+const SECP256K1_PARAMS: [u8; 7] = [0x06, 0x05, 0x2b, 0x81, 0x04, 0x00, 0x0a];
+const P256_PARAMS: [u8; 10] = [0x06, 0x08, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07];
+
+// NOTE: a module is initialized once per process, C_Finalize is called when the context is dropped
+static CONTEXTS: OnceLock<Mutex<BTreeMap<String, Pkcs11>>> = OnceLock::new();
+
+fn context(module: &str) -> Result<Pkcs11, NodeXError> {
+    let mut contexts = match CONTEXTS.get_or_init(|| Mutex::new(BTreeMap::new())).lock() {
+        Ok(v) => v,
+        Err(_) => return Err(NodeXError{}),
+    };
+
+    if let Some(v) = contexts.get(module) {
+        return Ok(v.clone())
+    }
+
+    let mut context = match Pkcs11::new(module) {
+        Ok(v) => v,
+        Err(e) => {
+            log::error!("{:?}", e);
+            return Err(NodeXError{})
+        },
+    };
+    if let Err(e) = context.initialize(CInitializeArgs::OsThreads) {
+        log::error!("{:?}", e);
+        return Err(NodeXError{})
+    }
+
+    contexts.insert(module.to_string(), context.clone());
+
+    Ok(context)
+}
+
+// NOTE: the key store is import-only (generates() is false), every key pair is derived on the host and written here.
+//       secp256k1 and P-256 keys are imported as non-extractable keys and referred to by their label,
+//       the token signs and agrees on keys with them. Ed25519 and X25519 keys, which the mechanisms of
+//       most tokens do not cover, are kept as private data objects, their secret keys are read back by the key ring
+pub struct Pkcs11KeyStore {
+    config: Pkcs11Config,
+    profile: String,
+}
+
+impl Pkcs11KeyStore {
+    pub fn new(config: Pkcs11Config, profile: &str) -> Pkcs11KeyStore {
+        Pkcs11KeyStore {
+            config,
+            profile: profile.to_string(),
+        }
+    }
+
+    fn label(&self, key_type: &SecureKeyStoreType) -> Vec<u8> {
+        let slot = match key_type {
+            SecureKeyStoreType::Sign => "sign",
+            SecureKeyStoreType::Update => "update",
+            SecureKeyStoreType::Recover => "recover",
+            SecureKeyStoreType::Encrypt => "encrypt",
+            SecureKeyStoreType::SignP256 => "sign_p256",
+            SecureKeyStoreType::EncryptP256 => "encrypt_p256",
+        };
+
+        format!("nodex:{}:{}", self.profile, slot).into_bytes()
+    }
+
+    // NOTE: a key pair is written under this label first, so the key pair it replaces is kept until it is written
+    fn staged_label(&self, key_type: &SecureKeyStoreType) -> Vec<u8> {
+        [self.label(key_type), b":staged".to_vec()].concat()
+    }
+
+    // NOTE: the staged key pair is the only one left when a write stopped after the previous key pair was destroyed
+    fn labels(&self, key_type: &SecureKeyStoreType) -> [Vec<u8>; 2] {
+        [self.label(key_type), self.staged_label(key_type)]
+    }
+
+    // NOTE: the Sign slot holds either a secp256k1 (65 bytes) or an Ed25519 (32 bytes) public key
+    fn ec_params(key_type: &SecureKeyStoreType, public_key: &[u8]) -> Option<&'static [u8]> {
+        match key_type {
+            SecureKeyStoreType::Update | SecureKeyStoreType::Recover => Some(&SECP256K1_PARAMS),
+            SecureKeyStoreType::Sign if public_key.len() != 32 => Some(&SECP256K1_PARAMS),
+            SecureKeyStoreType::SignP256 | SecureKeyStoreType::EncryptP256 => Some(&P256_PARAMS),
+            _ => None,
+        }
+    }
+
+    // NOTE: CKA_EC_POINT is the DER encoding of the point (OCTET STRING), some tokens return the raw point
+    fn wrap_ec_point(public_key: &[u8]) -> Vec<u8> {
+        [vec![0x04, public_key.len() as u8], public_key.to_vec()].concat()
+    }
+
+    fn unwrap_ec_point(value: &[u8]) -> Vec<u8> {
+        let length = value.len().saturating_sub(2);
+
+        match value {
+            [0x04, v, ..] if *v as usize == length && [32, 33, 65].contains(&length) => value[2..].to_vec(),
+            _ => value.to_vec(),
+        }
+    }
+
+    fn session(&self) -> Result<Session, NodeXError> {
+        let context = match context(&self.config.module) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+
+        let slots = match context.get_slots_with_token() {
+            Ok(v) => v,
+            Err(e) => {
+                log::error!("{:?}", e);
+                return Err(NodeXError{})
+            },
+        };
+        let slot = slots.into_iter().find(|v| {
+            match context.get_token_info(*v) {
+                Ok(v) => v.label().trim() == self.config.token_label.trim(),
+                Err(_) => false,
+            }
+        });
+        let slot = match slot {
+            Some(v) => v,
+            None => {
+                log::error!("token not found: {}", self.config.token_label);
+                return Err(NodeXError{})
+            },
+        };
+
+        let session = match context.open_rw_session(slot) {
+            Ok(v) => v,
+            Err(e) => {
+                log::error!("{:?}", e);
+                return Err(NodeXError{})
+            },
+        };
+
+        let pin = match std::env::var("NODEX_PKCS11_PIN") {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+
+        // NOTE: the login state is shared by the sessions of the application
+        match session.login(UserType::User, Some(&pin)) {
+            Ok(_) | Err(Error::Pkcs11(RvError::UserAlreadyLoggedIn)) => Ok(session),
+            Err(e) => {
+                log::error!("{:?}", e);
+                Err(NodeXError{})
+            },
+        }
+    }
+
+    fn find(session: &Session, template: &[Attribute]) -> Result<Option<ObjectHandle>, NodeXError> {
+        match session.find_objects(template) {
+            Ok(v) => Ok(v.into_iter().next()),
+            Err(e) => {
+                log::error!("{:?}", e);
+                Err(NodeXError{})
+            },
+        }
+    }
+
+    fn value(session: &Session, object: ObjectHandle, attribute: AttributeType) -> Result<Vec<u8>, NodeXError> {
+        let attributes = match session.get_attributes(object, &[attribute]) {
+            Ok(v) => v,
+            Err(e) => {
+                log::error!("{:?}", e);
+                return Err(NodeXError{})
+            },
+        };
+
+        match attributes.into_iter().next() {
+            Some(Attribute::Value(v)) | Some(Attribute::EcPoint(v)) => Ok(v),
+            _ => Err(NodeXError{}),
+        }
+    }
+
+    fn create(session: &Session, template: &[Attribute]) -> Result<(), NodeXError> {
+        match session.create_object(template) {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                log::error!("{:?}", e);
+                Err(NodeXError{})
+            },
+        }
+    }
+
+    fn objects(session: &Session, label: &[u8]) -> Result<Vec<ObjectHandle>, NodeXError> {
+        match session.find_objects(&[Attribute::Label(label.to_vec())]) {
+            Ok(v) => Ok(v),
+            Err(e) => {
+                log::error!("{:?}", e);
+                Err(NodeXError{})
+            },
+        }
+    }
+
+    fn destroy(session: &Session, objects: Vec<ObjectHandle>) -> Result<(), NodeXError> {
+        for object in objects {
+            if let Err(e) = session.destroy_object(object) {
+                log::error!("{:?}", e);
+                return Err(NodeXError{})
+            }
+        }
+
+        Ok(())
+    }
+
+    fn private_key(&self, session: &Session, key_type: &SecureKeyStoreType) -> Result<ObjectHandle, NodeXError> {
+        for label in self.labels(key_type) {
+            let template = [
+                Attribute::Class(ObjectClass::PRIVATE_KEY),
+                Attribute::Label(label),
+            ];
+
+            match Self::find(session, &template) {
+                Ok(Some(v)) => return Ok(v),
+                Ok(None) => {},
+                Err(_) => return Err(NodeXError{}),
+            }
+        }
+
+        Err(NodeXError{})
+    }
+
+    // NOTE: the secret key and the public key are created together, the first one is destroyed when the second one fails
+    fn create_key_pair(session: &Session, key_type: &SecureKeyStoreType, label: &[u8], key_pair: &KeyPair) -> Result<(), NodeXError> {
+        let (secret_key, public_key) = match Self::ec_params(key_type, &key_pair.public_key) {
+            Some(params) => {
+                (vec![
+                    Attribute::Class(ObjectClass::PRIVATE_KEY),
+                    Attribute::KeyType(KeyType::EC),
+                    Attribute::Label(label.to_vec()),
+                    Attribute::Token(true),
+                    Attribute::Private(true),
+                    Attribute::Sensitive(true),
+                    Attribute::Extractable(false),
+                    Attribute::Sign(true),
+                    Attribute::Derive(true),
+                    Attribute::EcParams(params.to_vec()),
+                    Attribute::Value(key_pair.secret_key.clone()),
+                ], vec![
+                    Attribute::Class(ObjectClass::PUBLIC_KEY),
+                    Attribute::KeyType(KeyType::EC),
+                    Attribute::Label(label.to_vec()),
+                    Attribute::Token(true),
+                    Attribute::Private(false),
+                    Attribute::Verify(true),
+                    Attribute::EcParams(params.to_vec()),
+                    Attribute::EcPoint(Self::wrap_ec_point(&key_pair.public_key)),
+                ])
+            },
+            None => {
+                (vec![
+                    Attribute::Class(ObjectClass::DATA),
+                    Attribute::Label(label.to_vec()),
+                    Attribute::Token(true),
+                    Attribute::Private(true),
+                    Attribute::Value(key_pair.secret_key.clone()),
+                ], vec![
+                    Attribute::Class(ObjectClass::DATA),
+                    Attribute::Label(label.to_vec()),
+                    Attribute::Token(true),
+                    Attribute::Private(false),
+                    Attribute::Value(key_pair.public_key.clone()),
+                ])
+            },
+        };
+
+        let secret_key = match session.create_object(&secret_key) {
+            Ok(v) => v,
+            Err(e) => {
+                log::error!("{:?}", e);
+                return Err(NodeXError{})
+            },
+        };
+
+        match Self::create(session, &public_key) {
+            Ok(_) => Ok(()),
+            Err(_) => {
+                let _ = session.destroy_object(secret_key);
+                Err(NodeXError{})
+            },
+        }
+    }
+
+    fn read_key_pair(session: &Session, label: &[u8]) -> Result<Option<KeyPair>, NodeXError> {
+        let public_key = [
+            Attribute::Class(ObjectClass::PUBLIC_KEY),
+            Attribute::Label(label.to_vec()),
+        ];
+        match Self::find(session, &public_key) {
+            Ok(Some(v)) => {
+                return match Self::value(session, v, AttributeType::EcPoint) {
+                    Ok(v) => Ok(Some(KeyPair {
+                        public_key: Self::unwrap_ec_point(&v),
+                        secret_key: vec![],
+                    })),
+                    Err(_) => Err(NodeXError{}),
+                }
+            },
+            Ok(None) => {},
+            Err(_) => return Err(NodeXError{}),
+        }
+
+        let mut values = vec![];
+        for private in [false, true] {
+            let template = [
+                Attribute::Class(ObjectClass::DATA),
+                Attribute::Label(label.to_vec()),
+                Attribute::Private(private),
+            ];
+
+            match Self::find(session, &template) {
+                Ok(Some(v)) => {
+                    match Self::value(session, v, AttributeType::Value) {
+                        Ok(v) => values.push(v),
+                        Err(_) => return Err(NodeXError{}),
+                    }
+                },
+                Ok(None) => return Ok(None),
+                Err(_) => return Err(NodeXError{}),
+            }
+        }
+
+        Ok(Some(KeyPair {
+            public_key: values[0].clone(),
+            secret_key: values[1].clone(),
+        }))
+    }
+}
+
+impl KeyStore for Pkcs11KeyStore {
+    fn write(&self, key_type: &SecureKeyStoreType, key_pair: &KeyPair) -> Result<(), NodeXError> {
+        log::info!("Called: write_pkcs11 (type: {:?})", key_type);
+
+        let session = match self.session() {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+        let (label, staged) = (self.label(key_type), self.staged_label(key_type));
+
+        // NOTE: a key pair read from the token has no secret key, it is kept as it is
+        if key_pair.secret_key.is_empty() {
+            return match self.private_key(&session, key_type) {
+                Ok(_) => Ok(()),
+                Err(_) => Err(NodeXError{}),
+            }
+        }
+
+        let current = match Self::objects(&session, &label) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+
+        // NOTE: the key pair is staged before the current one is destroyed, a failure leaves the current one as it is.
+        //       The objects cannot be relabelled (C_SetAttributeValue), they are created again under the label
+        if !current.is_empty() {
+            let stale = match Self::objects(&session, &staged) {
+                Ok(v) => v,
+                Err(_) => return Err(NodeXError{}),
+            };
+
+            if Self::destroy(&session, stale).is_err() {
+                return Err(NodeXError{})
+            }
+            if Self::create_key_pair(&session, key_type, &staged, key_pair).is_err() {
+                return Err(NodeXError{})
+            }
+            if Self::destroy(&session, current).is_err() {
+                return Err(NodeXError{})
+            }
+        }
+
+        if Self::create_key_pair(&session, key_type, &label, key_pair).is_err() {
+            return Err(NodeXError{})
+        }
+
+        match Self::objects(&session, &staged) {
+            Ok(v) => Self::destroy(&session, v),
+            Err(_) => Err(NodeXError{}),
+        }
+    }
+
+    fn read(&self, key_type: &SecureKeyStoreType) -> Result<Option<KeyPair>, NodeXError> {
+        log::info!("Called: read_pkcs11 (type: {:?})", key_type);
+
+        let session = match self.session() {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+
+        for label in self.labels(key_type) {
+            match Self::read_key_pair(&session, &label) {
+                Ok(Some(v)) => return Ok(Some(v)),
+                Ok(None) => {},
+                Err(_) => return Err(NodeXError{}),
+            }
+        }
+
+        Ok(None)
+    }
+
+    // NOTE: CKM_ECDSA signs the SHA-256 digest of the message and returns r || s (64 bytes)
+    fn sign(&self, key_type: &SecureKeyStoreType, context: &SigningKey, message: &[u8]) -> Result<Vec<u8>, NodeXError> {
+        log::info!("Called: sign_pkcs11 (type: {:?}, curve: {:?})", key_type, context.get_type());
+
+        if let SigningKey::Ed25519(_) = context {
+            return Err(NodeXError{})
+        }
+
+        let session = match self.session() {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+        let key = match self.private_key(&session, key_type) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+
+        let digest = runtime::sha2::SHA256::digest(message);
+        let signature = match session.sign(&Mechanism::Ecdsa, key, &digest) {
+            Ok(v) if v.len() == 64 => v,
+            Ok(_) => return Err(NodeXError{}),
+            Err(e) => {
+                log::error!("{:?}", e);
+                return Err(NodeXError{})
+            },
+        };
+
+        match context {
+            SigningKey::Secp256k1(_) => runtime::secp256k1::Secp256k1::normalize_signature(&signature),
+            _ => Ok(signature),
+        }
+    }
+
+    // NOTE: CKM_ECDH1_DERIVE without a KDF derives the x coordinate of the shared point (32 bytes)
+    fn ecdh(&self, key_type: &SecureKeyStoreType, public_key: &[u8]) -> Result<Vec<u8>, NodeXError> {
+        log::info!("Called: ecdh_pkcs11 (type: {:?})", key_type);
+
+        let session = match self.session() {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+        let key = match self.private_key(&session, key_type) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+
+        let (shared_data_len, public_data_len, value_len) = match (Ulong::try_from(0usize), Ulong::try_from(public_key.len()), Ulong::try_from(32usize)) {
+            (Ok(u), Ok(v), Ok(w)) => (u, v, w),
+            _ => return Err(NodeXError{}),
+        };
+        let params = Ecdh1DeriveParams {
+            kdf: EcKdfType::NULL,
+            shared_data_len,
+            shared_data: ptr::null(),
+            public_data_len,
+            public_data: public_key.as_ptr() as *const c_void,
+        };
+        let template = [
+            Attribute::Class(ObjectClass::SECRET_KEY),
+            Attribute::KeyType(KeyType::GENERIC_SECRET),
+            Attribute::Token(false),
+            Attribute::Sensitive(false),
+            Attribute::Extractable(true),
+            Attribute::ValueLen(value_len),
+        ];
+
+        let shared = match session.derive_key(&Mechanism::Ecdh1Derive(params), key, &template) {
+            Ok(v) => v,
+            Err(e) => {
+                log::error!("{:?}", e);
+                return Err(NodeXError{})
+            },
+        };
+        let value = Self::value(&session, shared, AttributeType::Value);
+        let _ = session.destroy_object(shared);
+
+        match value {
+            Ok(v) if v.len() == 32 => Ok(v),
+            _ => Err(NodeXError{}),
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use rstest::*;
+
+    #[fixture]
+    fn store() -> Pkcs11KeyStore {
+        Pkcs11KeyStore::new(Pkcs11Config {
+            module: std::env::var("NODEX_PKCS11_MODULE").unwrap_or_else(|_| String::from("/usr/lib/softhsm/libsofthsm2.so")),
+            token_label: String::from("nodex"),
+        }, "test")
+    }
+
+    #[rstest]
+    pub fn test_label(store: Pkcs11KeyStore) {
+        assert_eq!(store.label(&SecureKeyStoreType::Sign), b"nodex:test:sign".to_vec());
+        assert_eq!(store.label(&SecureKeyStoreType::EncryptP256), b"nodex:test:encrypt_p256".to_vec());
+        assert_eq!(store.staged_label(&SecureKeyStoreType::Sign), b"nodex:test:sign:staged".to_vec());
+    }
+
+    #[test]
+    pub fn test_ec_params() {
+        assert_eq!(Pkcs11KeyStore::ec_params(&SecureKeyStoreType::Sign, &[0u8; 65]), Some(&SECP256K1_PARAMS[..]));
+        assert_eq!(Pkcs11KeyStore::ec_params(&SecureKeyStoreType::Sign, &[0u8; 32]), None);
+        assert_eq!(Pkcs11KeyStore::ec_params(&SecureKeyStoreType::Recover, &[0u8; 65]), Some(&SECP256K1_PARAMS[..]));
+        assert_eq!(Pkcs11KeyStore::ec_params(&SecureKeyStoreType::SignP256, &[0u8; 65]), Some(&P256_PARAMS[..]));
+        assert_eq!(Pkcs11KeyStore::ec_params(&SecureKeyStoreType::Encrypt, &[0u8; 32]), None);
+    }
+
+    #[test]
+    pub fn test_ec_point() {
+        let point = [vec![0x04], vec![0x01; 64]].concat();
+        let wrapped = Pkcs11KeyStore::wrap_ec_point(&point);
+
+        assert_eq!(wrapped[..2], [0x04, 65]);
+        assert_eq!(Pkcs11KeyStore::unwrap_ec_point(&wrapped), point);
+        assert_eq!(Pkcs11KeyStore::unwrap_ec_point(&point), point);
+    }
+
+    // NOTE: softhsm2-util --init-token --free --label nodex --pin 1234 --so-pin 1234
+    //       NODEX_PKCS11_PIN=1234 cargo test -- --ignored test_softhsm
+    #[rstest]
+    #[ignore]
+    pub fn test_softhsm(store: Pkcs11KeyStore) {
+        let secret_key = vec![0x01; 32];
+        let public_key = match runtime::p256::P256::generate_public_key(&secret_key) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert!(store.write(&SecureKeyStoreType::SignP256, &KeyPair {
+            public_key: public_key.clone(),
+            secret_key: secret_key.clone(),
+        }).is_ok());
+
+        let key_pair = match store.read(&SecureKeyStoreType::SignP256) {
+            Ok(Some(v)) => v,
+            _ => panic!()
+        };
+
+        assert_eq!(key_pair.public_key, public_key);
+        assert!(key_pair.secret_key.is_empty());
+
+        let context = match crate::nodex::keyring::p256::P256::new_external(&public_key, &SecureKeyStoreType::SignP256) {
+            Ok(v) => SigningKey::P256(v),
+            Err(_) => panic!()
+        };
+
+        let signature = match store.sign(&SecureKeyStoreType::SignP256, &context, b"message") {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert_eq!(runtime::p256::P256::ecdsa_verify(&signature, b"message", &public_key).ok(), Some(true));
+
+        let peer = match runtime::p256::P256::generate_public_key(&[0x02; 32]) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        let shared_key = match runtime::p256::P256::ecdh(&secret_key, &peer) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert_eq!(store.ecdh(&SecureKeyStoreType::SignP256, &peer).ok(), Some(shared_key));
+
+        // NOTE: the key pair is replaced, nothing is left under the staged label
+        let next_public_key = match runtime::p256::P256::generate_public_key(&[0x05; 32]) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert!(store.write(&SecureKeyStoreType::SignP256, &KeyPair {
+            public_key: next_public_key.clone(),
+            secret_key: vec![0x05; 32],
+        }).is_ok());
+
+        let key_pair = match store.read(&SecureKeyStoreType::SignP256) {
+            Ok(Some(v)) => v,
+            _ => panic!()
+        };
+
+        assert_eq!(key_pair.public_key, next_public_key);
+
+        let session = match store.session() {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert_eq!(Pkcs11KeyStore::objects(&session, &store.label(&SecureKeyStoreType::SignP256)).ok().map(|v| v.len()), Some(2));
+        assert_eq!(Pkcs11KeyStore::objects(&session, &store.staged_label(&SecureKeyStoreType::SignP256)).ok().map(|v| v.len()), Some(0));
+
+        assert!(store.write(&SecureKeyStoreType::Encrypt, &KeyPair {
+            public_key: vec![0x03; 32],
+            secret_key: vec![0x04; 32],
+        }).is_ok());
+
+        let key_pair = match store.read(&SecureKeyStoreType::Encrypt) {
+            Ok(Some(v)) => v,
+            _ => panic!()
+        };
+
+        assert_eq!(key_pair.public_key, vec![0x03; 32]);
+        assert_eq!(key_pair.secret_key, vec![0x04; 32]);
+    }
+}
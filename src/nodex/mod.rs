@@ -3,7 +3,9 @@ pub mod errors;
 pub mod runtime;
 pub mod keyring;
 pub mod sidetree;
+pub mod resolver;
 pub mod cipher;
 pub mod schema;
 pub mod extension;
-pub mod agent;
\ No newline at end of file
+pub mod agent;
+pub mod keystore;
\ No newline at end of file
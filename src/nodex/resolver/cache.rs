@@ -0,0 +1,392 @@
+use std::{collections::HashMap, fs, path::PathBuf, sync::{Arc, Mutex, OnceLock, atomic::{AtomicU64, Ordering}}};
+use async_trait::async_trait;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use crate::{nodex::{errors::NodeXError, sidetree::payload::DIDResolutionResponse}, config::ResolverConfig, app_config};
+use super::{DidResolver, DidResolverRegistry, dereferencer::DocumentVersion};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct CacheEntry {
+    // NOTE: None means the DID could not be resolved (negative cache)
+    response: Option<DIDResolutionResponse>,
+    fetched_at: i64,
+}
+
+// NOTE: the snapshot is written off the async workers, a write superseded by a later one is skipped
+struct Snapshot {
+    path: PathBuf,
+    generation: AtomicU64,
+    lock: Mutex<()>,
+}
+
+impl Snapshot {
+    fn write(&self, generation: u64, entries: HashMap<String, CacheEntry>) {
+        let _guard = self.lock.lock().unwrap();
+
+        if self.generation.load(Ordering::SeqCst) != generation {
+            return
+        }
+
+        let content = match serde_json::to_string(&entries) {
+            Ok(v) => v,
+            Err(_) => return,
+        };
+
+        if let Some(dir) = self.path.parent() {
+            if fs::create_dir_all(dir).is_err() {
+                return
+            }
+        }
+
+        let tmp = self.path.with_extension("json.tmp");
+
+        if fs::write(&tmp, content).is_err() || fs::rename(&tmp, &self.path).is_err() {
+            log::warn!("unable to write resolver snapshot: {:?}", &self.path);
+        }
+    }
+}
+
+// NOTE: the in-flight entry is removed even when the lookup holding it is cancelled
+struct InflightGuard<'a> {
+    inflight: &'a Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+    did: &'a str,
+}
+
+impl Drop for InflightGuard<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut inflight) = self.inflight.lock() {
+            inflight.remove(self.did);
+        }
+    }
+}
+
+pub struct CachingDidResolver {
+    inner: Box<dyn DidResolver>,
+    config: ResolverConfig,
+    snapshot: Option<Arc<Snapshot>>,
+    entries: Mutex<HashMap<String, CacheEntry>>,
+    inflight: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+}
+
+pub fn did_resolver() -> Arc<CachingDidResolver> {
+    static SINGLETON: OnceLock<Arc<CachingDidResolver>> = OnceLock::new();
+
+    SINGLETON.get_or_init(|| {
+        let config = app_config().inner.lock().unwrap().load_resolver_config();
+        let snapshot_path = dirs::home_dir().map(|v| v.join(".nodex").join("cache").join("resolver.json"));
+
+        Arc::new(CachingDidResolver::new(Box::new(DidResolverRegistry::new()), config, snapshot_path))
+    }).clone()
+}
+
+impl CachingDidResolver {
+    pub fn new(inner: Box<dyn DidResolver>, config: ResolverConfig, snapshot_path: Option<PathBuf>) -> Self {
+        let entries = match &snapshot_path {
+            Some(path) => Self::read_snapshot(path),
+            None => HashMap::new(),
+        };
+
+        let snapshot = snapshot_path.map(|path| Arc::new(Snapshot {
+            path,
+            generation: AtomicU64::new(0),
+            lock: Mutex::new(()),
+        }));
+
+        CachingDidResolver {
+            inner,
+            config,
+            snapshot,
+            entries: Mutex::new(entries),
+            inflight: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn read_snapshot(path: &PathBuf) -> HashMap<String, CacheEntry> {
+        let content = match fs::read_to_string(path) {
+            Ok(v) => v,
+            Err(_) => return HashMap::new(),
+        };
+
+        match serde_json::from_str::<HashMap<String, CacheEntry>>(&content) {
+            Ok(v) => v,
+            Err(_) => {
+                log::warn!("ignoring broken resolver snapshot: {:?}", path);
+                HashMap::new()
+            }
+        }
+    }
+
+    fn write_snapshot(&self) {
+        let snapshot = match &self.snapshot {
+            Some(v) => Arc::clone(v),
+            None => return,
+        };
+
+        // NOTE: only successful resolutions are worth keeping across restarts
+        let entries: HashMap<String, CacheEntry> = self.entries.lock().unwrap().iter()
+            .filter(|(_, v)| v.response.is_some())
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+
+        let generation = snapshot.generation.fetch_add(1, Ordering::SeqCst) + 1;
+
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn_blocking(move || snapshot.write(generation, entries));
+            },
+            Err(_) => snapshot.write(generation, entries),
+        }
+    }
+
+    fn lookup(&self, did: &str) -> Option<Option<DIDResolutionResponse>> {
+        let entries = self.entries.lock().unwrap();
+        let entry = entries.get(did)?;
+        let age = Utc::now().timestamp() - entry.fetched_at;
+
+        match &entry.response {
+            Some(v) if age < self.config.cache_ttl as i64 => Some(Some(v.clone())),
+            None if age < self.config.negative_cache_ttl as i64 => Some(None),
+            _ => None,
+        }
+    }
+
+    // NOTE: keys rotated or deactivated during an outage must stop verifying after max_stale
+    fn stale(&self, did: &str) -> Option<DIDResolutionResponse> {
+        match self.entries.lock().unwrap().get(did) {
+            Some(v) if Utc::now().timestamp() - v.fetched_at < self.config.max_stale as i64 => v.response.clone(),
+            _ => None,
+        }
+    }
+
+    fn store(&self, did: &str, response: Option<DIDResolutionResponse>) {
+        let persist = response.is_some();
+
+        self.entries.lock().unwrap().insert(did.to_string(), CacheEntry {
+            response,
+            fetched_at: Utc::now().timestamp(),
+        });
+
+        if persist {
+            self.write_snapshot();
+        }
+    }
+
+    pub fn invalidate(&self, did: &str) {
+        let removed = self.entries.lock().unwrap().remove(did);
+
+        if let Some(CacheEntry { response: Some(_), .. }) = removed {
+            self.write_snapshot();
+        }
+    }
+
+    async fn resolve_cached(&self, did: &str) -> Result<Option<DIDResolutionResponse>, NodeXError> {
+        if let Some(v) = self.lookup(did) {
+            return Ok(v)
+        }
+
+        // NOTE: concurrent lookups of the same DID wait for the first one
+        let lock = self.inflight.lock().unwrap()
+            .entry(did.to_string())
+            .or_insert_with(|| Arc::new(tokio::sync::Mutex::new(())))
+            .clone();
+        let _guard = lock.lock().await;
+        let _inflight = InflightGuard { inflight: &self.inflight, did };
+
+        if let Some(v) = self.lookup(did) {
+            return Ok(v)
+        }
+
+        match self.inner.find(did).await {
+            Ok(Some(v)) => {
+                self.store(did, Some(v.clone()));
+                Ok(Some(v))
+            },
+            // NOTE: only a DID the method reports as unknown is cached as not found
+            Ok(None) => {
+                self.store(did, None);
+                Ok(None)
+            },
+            Err(_) => {
+                match self.stale(did) {
+                    Some(v) if self.config.offline_fallback => {
+                        log::warn!("unable to resolve {}, using the cached document", &did);
+                        Ok(Some(v))
+                    },
+                    _ => Err(NodeXError{}),
+                }
+            },
+        }
+    }
+}
+
+#[async_trait]
+impl DidResolver for CachingDidResolver {
+    async fn resolve(&self, did: &str) -> Result<DIDResolutionResponse, NodeXError> {
+        match self.resolve_cached(did).await {
+            Ok(Some(v)) => Ok(v),
+            _ => Err(NodeXError{})
+        }
+    }
+
+    async fn find(&self, did: &str) -> Result<Option<DIDResolutionResponse>, NodeXError> {
+        self.resolve_cached(did).await
+    }
+
+    // NOTE: historical documents are not cached
+    async fn resolve_version(&self, did: &str, version: &DocumentVersion) -> Result<DIDResolutionResponse, NodeXError> {
+        self.inner.resolve_version(did, version).await
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, AtomicUsize};
+    use crate::nodex::resolver::key::KeyDidResolver;
+
+    const DID: &str = "did:key:zQ3shokFTS3brHcDQrn82RUDfCZESWL1ZdCEJwekUDPQiYBme";
+
+    struct CountingResolver {
+        calls: Arc<AtomicUsize>,
+        offline: Arc<AtomicBool>,
+    }
+
+    #[async_trait]
+    impl DidResolver for CountingResolver {
+        async fn resolve(&self, did: &str) -> Result<DIDResolutionResponse, NodeXError> {
+            match self.find(did).await {
+                Ok(Some(v)) => Ok(v),
+                _ => Err(NodeXError{})
+            }
+        }
+
+        async fn find(&self, did: &str) -> Result<Option<DIDResolutionResponse>, NodeXError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+
+            if self.offline.load(Ordering::SeqCst) {
+                return Err(NodeXError{})
+            }
+
+            Ok(KeyDidResolver::resolve_document(did).ok())
+        }
+    }
+
+    fn counting_resolver(config: ResolverConfig, snapshot_path: Option<PathBuf>) -> (CachingDidResolver, Arc<AtomicUsize>, Arc<AtomicBool>) {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let offline = Arc::new(AtomicBool::new(false));
+
+        let inner = CountingResolver { calls: Arc::clone(&calls), offline: Arc::clone(&offline) };
+
+        (CachingDidResolver::new(Box::new(inner), config, snapshot_path), calls, offline)
+    }
+
+    #[actix_rt::test]
+    async fn test_resolve_cached() {
+        let (resolver, calls, _) = counting_resolver(ResolverConfig::default(), None);
+
+        assert!(resolver.resolve(DID).await.is_ok());
+        assert!(resolver.resolve(DID).await.is_ok());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        resolver.invalidate(DID);
+
+        assert!(resolver.resolve(DID).await.is_ok());
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[actix_rt::test]
+    async fn test_resolve_with_expired_ttl() {
+        let (resolver, calls, _) = counting_resolver(ResolverConfig { cache_ttl: 0, ..ResolverConfig::default() }, None);
+
+        assert!(resolver.resolve(DID).await.is_ok());
+        assert!(resolver.resolve(DID).await.is_ok());
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[actix_rt::test]
+    async fn test_resolve_negative_cached() {
+        let (resolver, calls, _) = counting_resolver(ResolverConfig::default(), None);
+
+        assert!(resolver.resolve("did:key:zInvalid").await.is_err());
+        assert!(resolver.resolve("did:key:zInvalid").await.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[actix_rt::test]
+    async fn test_resolve_outage_not_cached() {
+        let (resolver, calls, offline) = counting_resolver(ResolverConfig::default(), None);
+
+        offline.store(true, Ordering::SeqCst);
+
+        assert!(resolver.resolve(DID).await.is_err());
+
+        offline.store(false, Ordering::SeqCst);
+
+        assert!(resolver.resolve(DID).await.is_ok());
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[actix_rt::test]
+    async fn test_resolve_coalesced() {
+        let (resolver, calls, _) = counting_resolver(ResolverConfig::default(), None);
+
+        let (a, b, c) = tokio::join!(resolver.resolve(DID), resolver.resolve(DID), resolver.resolve(DID));
+
+        assert!(a.is_ok() && b.is_ok() && c.is_ok());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[actix_rt::test]
+    async fn test_resolve_cancelled() {
+        let (resolver, calls, _) = counting_resolver(ResolverConfig::default(), None);
+
+        assert!(tokio::time::timeout(std::time::Duration::from_millis(1), resolver.resolve(DID)).await.is_err());
+        assert!(resolver.inflight.lock().unwrap().is_empty());
+
+        assert!(resolver.resolve(DID).await.is_ok());
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[actix_rt::test]
+    async fn test_resolve_offline_from_snapshot() {
+        let path = std::env::temp_dir().join(format!("nodex-resolver-{}.json", std::process::id()));
+        let config = ResolverConfig { cache_ttl: 0, ..ResolverConfig::default() };
+
+        let (resolver, _, _) = counting_resolver(config.clone(), Some(path.clone()));
+        assert!(resolver.resolve(DID).await.is_ok());
+
+        // NOTE: the snapshot is written in the background
+        for _ in 0..100 {
+            if path.exists() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+
+        // NOTE: a restarted agent during a resolver outage
+        let (resolver, calls, offline) = counting_resolver(config, Some(path.clone()));
+        offline.store(true, Ordering::SeqCst);
+
+        let result = match resolver.resolve(DID).await {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert_eq!(result.did_document.id, DID);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[actix_rt::test]
+    async fn test_resolve_offline_beyond_max_stale() {
+        let (resolver, _, offline) = counting_resolver(ResolverConfig { cache_ttl: 0, max_stale: 0, ..ResolverConfig::default() }, None);
+
+        assert!(resolver.resolve(DID).await.is_ok());
+
+        offline.store(true, Ordering::SeqCst);
+
+        assert!(resolver.resolve(DID).await.is_err());
+    }
+}
@@ -0,0 +1,321 @@
+use serde::{Serialize, Deserialize};
+use serde_json::{Value, json};
+use reqwest::Url;
+use crate::nodex::{errors::NodeXError, schema::did_document::{Service, ServiceEndpointValue}, sidetree::payload::DIDResolutionResponse};
+use super::DidResolver;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum DocumentVersion {
+    Id(String),
+    Time(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DidUrl {
+    pub did: String,
+    pub path: Option<String>,
+    pub query: Option<String>,
+    pub fragment: Option<String>,
+}
+
+impl DidUrl {
+    pub fn parse(did_url: &str) -> Result<Self, NodeXError> {
+        let (rest, fragment) = match did_url.split_once('#') {
+            Some((rest, fragment)) => (rest, Some(fragment.to_string())),
+            None => (did_url, None),
+        };
+        let (rest, query) = match rest.split_once('?') {
+            Some((rest, query)) => (rest, Some(query.to_string())),
+            None => (rest, None),
+        };
+        let (did, path) = match rest.find('/') {
+            Some(i) => (&rest[..i], Some(rest[i..].to_string())),
+            None => (rest, None),
+        };
+
+        let mut parts = did.splitn(3, ':');
+
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some("did"), Some(method), Some(id)) if !method.is_empty() && !id.is_empty() => {},
+            _ => return Err(NodeXError{})
+        }
+
+        Ok(DidUrl {
+            did: did.to_string(),
+            path,
+            query,
+            fragment,
+        })
+    }
+
+    fn param(&self, name: &str) -> Option<String> {
+        match &self.query {
+            Some(v) => qstring::QString::from(v.as_str()).get(name).map(|v| v.to_string()),
+            None => None,
+        }
+    }
+
+    pub fn version(&self) -> Option<DocumentVersion> {
+        match (self.param("versionId"), self.param("versionTime")) {
+            (Some(v), _) => Some(DocumentVersion::Id(v)),
+            (None, Some(v)) => Some(DocumentVersion::Time(v)),
+            (None, None) => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DereferencingResult {
+    #[serde(rename = "@context")]
+    pub context: String,
+
+    #[serde(rename = "dereferencingMetadata")]
+    pub dereferencing_metadata: Value,
+
+    #[serde(rename = "contentStream")]
+    pub content_stream: Value,
+
+    #[serde(rename = "contentMetadata")]
+    pub content_metadata: Value,
+}
+
+pub struct DidUrlDereferencer {}
+
+impl DidUrlDereferencer {
+    fn result(content_type: &str, content_stream: Value, content_metadata: Value) -> DereferencingResult {
+        DereferencingResult {
+            context: "https://w3id.org/did-resolution/v1".to_string(),
+            dereferencing_metadata: json!({ "contentType": content_type }),
+            content_stream,
+            content_metadata,
+        }
+    }
+
+    // NOTE: a DIDCommMessaging endpoint is an object with its URL in "uri"
+    fn endpoint_url(service: &Service) -> Option<String> {
+        match &service.service_endpoint {
+            ServiceEndpointValue::Uri(v) => Some(v.clone()),
+            ServiceEndpointValue::Map(v) => v.get("uri").and_then(|v| v.as_str()).map(|v| v.to_string()),
+            ServiceEndpointValue::Set(v) => v.iter().find_map(|v| match v {
+                Value::String(v) => Some(v.clone()),
+                v => v.get("uri").and_then(|v| v.as_str()).map(|v| v.to_string()),
+            }),
+        }
+    }
+
+    pub fn select(did_url: &DidUrl, response: &DIDResolutionResponse) -> Result<DereferencingResult, NodeXError> {
+        let document = &response.did_document;
+
+        if did_url.path.is_some() {
+            return Err(NodeXError{})
+        }
+
+        if let Some(service_id) = did_url.param("service") {
+            let service = match document.service.iter().flatten().find(|v| document.absolute_id(&v.id) == document.absolute_id(&format!("#{}", service_id))) {
+                Some(v) => v,
+                None => return Err(NodeXError{})
+            };
+
+            let endpoint = match Self::endpoint_url(service) {
+                Some(v) => v,
+                None => return Err(NodeXError{})
+            };
+
+            let mut url = match did_url.param("relativeRef") {
+                Some(v) => {
+                    match Url::parse(&endpoint).and_then(|base| base.join(&v)) {
+                        Ok(v) => v.to_string(),
+                        Err(_) => return Err(NodeXError{})
+                    }
+                },
+                None => endpoint,
+            };
+
+            // NOTE: the fragment applies to the selected endpoint
+            if let Some(fragment) = &did_url.fragment {
+                if !url.contains('#') {
+                    url = format!("{}#{}", url, fragment);
+                }
+            }
+
+            return Ok(Self::result("text/uri-list", json!(url), json!({})))
+        }
+
+        let metadata = match serde_json::to_value(&response.method_metadata) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        match &did_url.fragment {
+            Some(fragment) => {
+                let id = format!("#{}", fragment);
+
+                if let Some(method) = document.verification_methods().into_iter().find(|v| document.absolute_id(&v.id) == document.absolute_id(&id)) {
+                    return match serde_json::to_value(&method) {
+                        Ok(v) => Ok(Self::result("application/did+ld+json", v, metadata)),
+                        Err(_) => Err(NodeXError{})
+                    }
+                }
+
+                match document.service.iter().flatten().find(|v| document.absolute_id(&v.id) == document.absolute_id(&id)) {
+                    Some(service) => {
+                        match serde_json::to_value(service) {
+                            Ok(v) => Ok(Self::result("application/did+ld+json", v, metadata)),
+                            Err(_) => Err(NodeXError{})
+                        }
+                    },
+                    None => Err(NodeXError{})
+                }
+            },
+            None => {
+                match serde_json::to_value(document) {
+                    Ok(v) => Ok(Self::result("application/did+ld+json", v, metadata)),
+                    Err(_) => Err(NodeXError{})
+                }
+            },
+        }
+    }
+
+    // NOTE: Ok(None) when the DID URL does not identify a resource, Err when the DID could not be resolved
+    pub async fn dereference(resolver: &dyn DidResolver, did_url: &str) -> Result<Option<DereferencingResult>, NodeXError> {
+        let did_url = match DidUrl::parse(did_url) {
+            Ok(v) => v,
+            Err(_) => return Ok(None)
+        };
+
+        let response = match did_url.version() {
+            Some(version) => resolver.resolve_version(&did_url.did, &version).await.map(Some),
+            None => resolver.find(&did_url.did).await,
+        };
+
+        match response {
+            Ok(Some(v)) => Ok(Self::select(&did_url, &v).ok()),
+            Ok(None) => Ok(None),
+            Err(_) => Err(NodeXError{})
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use rstest::*;
+    use crate::nodex::{schema::did_document::DIDDocument, sidetree::payload::MethodMetadata};
+
+    fn response() -> DIDResolutionResponse {
+        let document = json!({
+            "id": "did:example:123",
+            "verificationMethod": [{
+                "id": "#signingKey",
+                "type": "EcdsaSecp256k1VerificationKey2019",
+                "controller": "did:example:123",
+                "publicKeyJwk": {
+                    "kty": "EC",
+                    "crv": "secp256k1",
+                    "x": "Z4Y3NNOxv0J6tCgqOBFnHnaZhJF6LdulT7z8A-2D5_8",
+                    "y": "i5a2NtJoUKXkLm6q8nOEu9WOkso1Ag6FTUT6k_LMnGk",
+                },
+            }],
+            "service": [{
+                "id": "#files",
+                "type": "LinkedDomains",
+                "serviceEndpoint": "https://example.com/files/",
+            }, {
+                "id": "did:example:123#didcomm",
+                "type": "DIDCommMessaging",
+                "serviceEndpoint": { "uri": "https://example.com/didcomm" },
+            }],
+        });
+
+        DIDResolutionResponse {
+            context: "https://w3id.org/did-resolution/v1".to_string(),
+            did_document: serde_json::from_value::<DIDDocument>(document).unwrap(),
+            method_metadata: MethodMetadata {
+                published: true,
+                recovery_commitment: None,
+                update_commitment: None,
+                deactivated: None,
+            },
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let result = match DidUrl::parse("did:example:123/path?service=files&relativeRef=%2Fa%3Fb#frag") {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert_eq!(result.did, "did:example:123");
+        assert_eq!(result.path, Some("/path".to_string()));
+        assert_eq!(result.param("relativeRef"), Some("/a?b".to_string()));
+        assert_eq!(result.fragment, Some("frag".to_string()));
+        assert_eq!(DidUrl::parse("did:example:123?versionTime=2023-01-01T00:00:00Z").unwrap().version(), Some(DocumentVersion::Time("2023-01-01T00:00:00Z".to_string())));
+    }
+
+    #[rstest]
+    #[case("did:example")]
+    #[case("did::123")]
+    #[case("https://example.com#key")]
+    fn test_parse_with_invalid_url(#[case] did_url: &str) {
+        assert!(DidUrl::parse(did_url).is_err());
+    }
+
+    #[rstest]
+    #[case("did:example:123?service=files&relativeRef=report.pdf", json!("https://example.com/files/report.pdf"))]
+    #[case("did:example:123?service=files&relativeRef=%2Fother#page", json!("https://example.com/other#page"))]
+    #[case("did:example:123?service=didcomm", json!("https://example.com/didcomm"))]
+    #[case("did:example:123#files", json!("https://example.com/files/"))]
+    fn test_select_service(#[case] did_url: &str, #[case] expected: Value) {
+        let result = match DidUrlDereferencer::select(&DidUrl::parse(did_url).unwrap(), &response()) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        match result.content_stream.get("serviceEndpoint") {
+            Some(v) => assert_eq!(v, &expected),
+            None => assert_eq!(result.content_stream, expected),
+        }
+    }
+
+    #[test]
+    fn test_select_verification_method() {
+        let result = match DidUrlDereferencer::select(&DidUrl::parse("did:example:123#signingKey").unwrap(), &response()) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert_eq!(result.content_stream["type"], "EcdsaSecp256k1VerificationKey2019");
+        assert_eq!(result.content_metadata["published"], true);
+    }
+
+    #[rstest]
+    #[case("did:example:123#unknown")]
+    #[case("did:example:123?service=unknown")]
+    #[case("did:example:123/path")]
+    fn test_select_not_found(#[case] did_url: &str) {
+        assert!(DidUrlDereferencer::select(&DidUrl::parse(did_url).unwrap(), &response()).is_err());
+    }
+
+    struct OfflineResolver {}
+
+    #[async_trait::async_trait]
+    impl DidResolver for OfflineResolver {
+        async fn resolve(&self, _did: &str) -> Result<DIDResolutionResponse, NodeXError> {
+            Err(NodeXError{})
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_dereference() {
+        let resolver = crate::nodex::resolver::key::KeyDidResolver {};
+        let did = "did:key:zQ3shokFTS3brHcDQrn82RUDfCZESWL1ZdCEJwekUDPQiYBme";
+
+        assert!(matches!(DidUrlDereferencer::dereference(&resolver, &format!("{}#zQ3shokFTS3brHcDQrn82RUDfCZESWL1ZdCEJwekUDPQiYBme", did)).await, Ok(Some(_))));
+        assert!(matches!(DidUrlDereferencer::dereference(&resolver, &format!("{}/path/to/resource", did)).await, Ok(None)));
+        assert!(matches!(DidUrlDereferencer::dereference(&resolver, "did:key:zInvalid").await, Ok(None)));
+
+        // NOTE: a resolver failure is not reported as not found
+        assert!(DidUrlDereferencer::dereference(&OfflineResolver {}, did).await.is_err());
+    }
+}
@@ -0,0 +1,205 @@
+use async_trait::async_trait;
+use crate::nodex::{errors::NodeXError, keyring::{p256::P256, secp256k1::{Secp256k1, KeyPairSecp256K1}}, runtime::base64_url::{Base64Url, PaddingType}, schema::did_document::{DIDDocument, OneOrMany, VerificationMethod, VerificationMethodRef}, sidetree::payload::{DIDResolutionResponse, MethodMetadata}};
+use serde_json::json;
+use super::DidResolver;
+
+// NOTE: multicodec prefixes (unsigned varint)
+const SECP256K1_PUB_PREFIX: [u8; 2] = [0xe7, 0x01];
+const ED25519_PUB_PREFIX: [u8; 2] = [0xed, 0x01];
+const P256_PUB_PREFIX: [u8; 2] = [0x80, 0x24];
+
+pub struct KeyDidResolver {}
+
+impl KeyDidResolver {
+    fn public_key_jwk(identifier: &str) -> Result<(String, KeyPairSecp256K1), NodeXError> {
+        // NOTE: multibase base58btc
+        let encoded = match identifier.strip_prefix('z') {
+            Some(v) => v,
+            None => return Err(NodeXError{})
+        };
+
+        let decoded = match bs58::decode(encoded).into_vec() {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        if decoded.len() < 2 {
+            return Err(NodeXError{})
+        }
+
+        let (prefix, public) = decoded.split_at(2);
+
+        if prefix == SECP256K1_PUB_PREFIX {
+            let context = match Secp256k1::from_public_key(public) {
+                Ok(v) => v,
+                Err(_) => return Err(NodeXError{})
+            };
+
+            return match context.to_jwk(false) {
+                Ok(v) => Ok(("EcdsaSecp256k1VerificationKey2019".to_string(), v)),
+                Err(_) => Err(NodeXError{})
+            }
+        }
+
+        if prefix == ED25519_PUB_PREFIX {
+            if public.len() != 32 {
+                return Err(NodeXError{})
+            }
+
+            return Ok(("JsonWebKey2020".to_string(), KeyPairSecp256K1 {
+                kty: "OKP".to_string(),
+                crv: "Ed25519".to_string(),
+                x  : Base64Url::encode(public, &PaddingType::NoPadding),
+                y  : "".to_string(),
+                d  : None,
+                kid: None,
+            }))
+        }
+
+        if prefix == P256_PUB_PREFIX {
+            let context = match P256::from_public_key(public) {
+                Ok(v) => v,
+                Err(_) => return Err(NodeXError{})
+            };
+
+            return match context.to_jwk(false) {
+                Ok(v) => Ok(("EcdsaSecp256r1VerificationKey2019".to_string(), v)),
+                Err(_) => Err(NodeXError{})
+            }
+        }
+
+        Err(NodeXError{})
+    }
+
+    pub fn resolve_document(did: &str) -> Result<DIDResolutionResponse, NodeXError> {
+        let identifier = match did.strip_prefix("did:key:") {
+            Some(v) => v,
+            None => return Err(NodeXError{})
+        };
+
+        let (r#type, jwk) = match Self::public_key_jwk(identifier) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        let key_id = format!("{}#{}", did, identifier);
+        let is_ecdh = jwk.kty == "EC";
+
+        Ok(DIDResolutionResponse {
+            context: "https://w3id.org/did-resolution/v1".to_string(),
+            did_document: DIDDocument {
+                context: Some(OneOrMany::Many(vec![ json!("https://www.w3.org/ns/did/v1") ])),
+                verification_method: Some(vec![ VerificationMethod::new(&key_id, did, &r#type, &jwk) ]),
+                authentication: Some(vec![ VerificationMethodRef::Reference(key_id.clone()) ]),
+                assertion_method: Some(vec![ VerificationMethodRef::Reference(key_id.clone()) ]),
+                // NOTE: secp256k1 and P-256 keys are also used for ECDH
+                key_agreement: if is_ecdh { Some(vec![ VerificationMethodRef::Reference(key_id.clone()) ]) } else { None },
+                capability_invocation: Some(vec![ VerificationMethodRef::Reference(key_id.clone()) ]),
+                capability_delegation: Some(vec![ VerificationMethodRef::Reference(key_id) ]),
+                ..DIDDocument::new(did)
+            },
+            method_metadata: MethodMetadata {
+                published: true,
+                recovery_commitment: None,
+                update_commitment: None,
+                deactivated: None,
+            },
+        })
+    }
+}
+
+#[async_trait]
+impl DidResolver for KeyDidResolver {
+    async fn resolve(&self, did: &str) -> Result<DIDResolutionResponse, NodeXError> {
+        Self::resolve_document(did)
+    }
+
+    // NOTE: the document is derived from the DID, a DID which cannot be decoded does not exist
+    async fn find(&self, did: &str) -> Result<Option<DIDResolutionResponse>, NodeXError> {
+        Ok(Self::resolve_document(did).ok())
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use rstest::*;
+
+    #[test]
+    fn test_resolve_secp256k1() {
+        let did = "did:key:zQ3shokFTS3brHcDQrn82RUDfCZESWL1ZdCEJwekUDPQiYBme";
+
+        let result = match KeyDidResolver::resolve_document(did) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        let public_keys = result.did_document.verification_methods();
+
+        assert_eq!(public_keys.len(), 1);
+        assert_eq!(public_keys[0].id, "did:key:zQ3shokFTS3brHcDQrn82RUDfCZESWL1ZdCEJwekUDPQiYBme#zQ3shokFTS3brHcDQrn82RUDfCZESWL1ZdCEJwekUDPQiYBme");
+        assert_eq!(public_keys[0].r#type, "EcdsaSecp256k1VerificationKey2019");
+        let jwk = match public_keys[0].jwk() {
+            Some(v) => v,
+            None => panic!()
+        };
+
+        assert_eq!(jwk.crv, "secp256k1");
+        assert!(Secp256k1::from_jwk(&jwk).is_ok());
+    }
+
+    #[test]
+    fn test_resolve_ed25519() {
+        let did = "did:key:z6MkhaXgBZDvotDkL5257faiztiGiC2QtKLGpbnnEGta2doK";
+
+        let result = match KeyDidResolver::resolve_document(did) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        let public_keys = result.did_document.verification_methods();
+
+        assert_eq!(public_keys.len(), 1);
+        assert_eq!(public_keys[0].r#type, "JsonWebKey2020");
+        let jwk = match public_keys[0].jwk() {
+            Some(v) => v,
+            None => panic!()
+        };
+
+        assert_eq!(jwk.kty, "OKP");
+        assert_eq!(jwk.crv, "Ed25519");
+        assert_eq!(jwk.x, "Lm_M42cB3HkUiODQsXRcweM6TByfzEHGO9ND274JcOY");
+    }
+
+    #[test]
+    fn test_resolve_p256() {
+        let did = "did:key:zDnaerDaTF5BXEavCrfRZEk316dpbLsfPDZ3WJ5hRTPFU2169";
+
+        let result = match KeyDidResolver::resolve_document(did) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        let public_keys = result.did_document.verification_methods();
+
+        assert_eq!(public_keys.len(), 1);
+        assert_eq!(public_keys[0].r#type, "EcdsaSecp256r1VerificationKey2019");
+        let jwk = match public_keys[0].jwk() {
+            Some(v) => v,
+            None => panic!()
+        };
+
+        assert_eq!(jwk.crv, "P-256");
+        assert!(P256::from_jwk(&jwk).is_ok());
+        assert!(result.did_document.key_agreement.is_some());
+    }
+
+    #[rstest]
+    #[case("did:key:Q3shokFTS3brHcDQrn82RUDfCZESWL1ZdCEJwekUDPQiYBme")]
+    #[case("did:key:z")]
+    #[case("did:key:z0OIl")]
+    #[case("did:web:example.com")]
+    fn test_resolve_with_invalid_did(#[case] did: &str) {
+        assert!(KeyDidResolver::resolve_document(did).is_err());
+    }
+}
@@ -0,0 +1,250 @@
+use std::collections::HashMap;
+use async_trait::async_trait;
+use crate::nodex::{errors::NodeXError, resolver::dereferencer::DocumentVersion, schema::did_document::{VerificationMethod, VerificationRelationship}, sidetree::payload::DIDResolutionResponse};
+
+pub mod nodex;
+pub mod key;
+pub mod web;
+pub mod cache;
+pub mod dereferencer;
+
+#[async_trait]
+pub trait DidResolver: Send + Sync {
+    async fn resolve(&self, did: &str) -> Result<DIDResolutionResponse, NodeXError>;
+
+    // NOTE: Ok(None) when the method reports that the DID does not exist, Err when it could not be asked
+    async fn find(&self, did: &str) -> Result<Option<DIDResolutionResponse>, NodeXError> {
+        match self.resolve(did).await {
+            Ok(v) => Ok(Some(v)),
+            Err(_) => Err(NodeXError{})
+        }
+    }
+
+    // NOTE: historical documents are only available from methods which keep the operation history
+    async fn resolve_version(&self, _did: &str, _version: &DocumentVersion) -> Result<DIDResolutionResponse, NodeXError> {
+        Err(NodeXError{})
+    }
+
+    // NOTE: a bare DID is only accepted when it has exactly one method for the relationship
+    async fn resolve_verification_method(&self, did_url: &str, relationship: VerificationRelationship) -> Result<VerificationMethod, NodeXError> {
+        self.resolve_verification_method_version(did_url, relationship, None).await
+    }
+
+    // NOTE: the method of the document published at the given version (a key rotated since), the DID must still be active
+    async fn resolve_verification_method_version(&self, did_url: &str, relationship: VerificationRelationship, version: Option<&DocumentVersion>) -> Result<VerificationMethod, NodeXError> {
+        let (did, fragment) = match did_url.split_once('#') {
+            Some((did, fragment)) => (did, Some(fragment)),
+            None => (did_url, None),
+        };
+
+        let response = match self.resolve(did).await {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        // NOTE: a deactivated DID has no valid keys
+        if response.method_metadata.is_deactivated() {
+            return Err(NodeXError{})
+        }
+
+        let document = match version {
+            Some(v) => {
+                match self.resolve_version(did, v).await {
+                    Ok(v) => v.did_document,
+                    Err(_) => return Err(NodeXError{})
+                }
+            },
+            None => response.did_document,
+        };
+
+        match fragment {
+            Some(v) => {
+                match document.find_verification_method(&format!("#{}", v), relationship) {
+                    Some(v) => Ok(v),
+                    None => Err(NodeXError{})
+                }
+            },
+            None => {
+                let mut candidates = document.verification_methods_for(relationship);
+
+                if candidates.len() != 1 {
+                    return Err(NodeXError{})
+                }
+
+                Ok(candidates.remove(0))
+            },
+        }
+    }
+}
+
+pub struct DidResolverRegistry {
+    drivers: HashMap<String, Box<dyn DidResolver>>,
+}
+
+impl Default for DidResolverRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DidResolverRegistry {
+    pub fn new() -> Self {
+        let mut registry = DidResolverRegistry { drivers: HashMap::new() };
+
+        registry.register("nodex", Box::new(nodex::NodexDidResolver::new()));
+        registry.register("key", Box::new(key::KeyDidResolver {}));
+        registry.register("web", Box::new(web::WebDidResolver::new()));
+
+        registry
+    }
+
+    pub fn register(&mut self, method: &str, driver: Box<dyn DidResolver>) {
+        self.drivers.insert(method.to_string(), driver);
+    }
+
+    pub fn method(did: &str) -> Option<&str> {
+        let mut parts = did.splitn(3, ':');
+
+        if parts.next()? != "did" {
+            return None
+        }
+
+        let method = parts.next()?;
+
+        match parts.next() {
+            Some(v) if !v.is_empty() && !method.is_empty() => Some(method),
+            _ => None,
+        }
+    }
+
+    fn driver(&self, did: &str) -> Result<&dyn DidResolver, NodeXError> {
+        let method = match Self::method(did) {
+            Some(v) => v,
+            None => return Err(NodeXError{})
+        };
+
+        match self.drivers.get(method) {
+            Some(v) => Ok(v.as_ref()),
+            None => {
+                log::error!("unsupported DID method: {}", method);
+                Err(NodeXError{})
+            }
+        }
+    }
+
+    pub async fn resolve(&self, did: &str) -> Result<DIDResolutionResponse, NodeXError> {
+        match self.driver(did) {
+            Ok(v) => v.resolve(did).await,
+            Err(_) => Err(NodeXError{})
+        }
+    }
+}
+
+#[async_trait]
+impl DidResolver for DidResolverRegistry {
+    async fn resolve(&self, did: &str) -> Result<DIDResolutionResponse, NodeXError> {
+        DidResolverRegistry::resolve(self, did).await
+    }
+
+    // NOTE: a DID of an unsupported method can never be resolved
+    async fn find(&self, did: &str) -> Result<Option<DIDResolutionResponse>, NodeXError> {
+        match self.driver(did) {
+            Ok(v) => v.find(did).await,
+            Err(_) => Ok(None)
+        }
+    }
+
+    async fn resolve_version(&self, did: &str, version: &DocumentVersion) -> Result<DIDResolutionResponse, NodeXError> {
+        match self.driver(did) {
+            Ok(v) => v.resolve_version(did, version).await,
+            Err(_) => Err(NodeXError{})
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case("did:nodex:test:EiBprXreMiba4loyl3psXm0RsECdtlCiQIjM8G9BtdQplA", Some("nodex"))]
+    #[case("did:key:zQ3shokFTS3brHcDQrn82RUDfCZESWL1ZdCEJwekUDPQiYBme", Some("key"))]
+    #[case("did:web:example.com", Some("web"))]
+    #[case("did:web:", None)]
+    #[case("did:web", None)]
+    #[case("urn:web:example.com", None)]
+    fn test_method(#[case] did: &str, #[case] expected: Option<&str>) {
+        assert_eq!(DidResolverRegistry::method(did), expected);
+    }
+
+    #[actix_rt::test]
+    async fn test_resolve_with_unsupported_method() {
+        let registry = DidResolverRegistry::new();
+
+        assert!(registry.resolve("did:example:123456789abcdefghi").await.is_err());
+        assert!(matches!(registry.find("did:example:123456789abcdefghi").await, Ok(None)));
+    }
+
+    #[actix_rt::test]
+    async fn test_resolve_with_key() {
+        let registry = DidResolverRegistry::new();
+
+        let result = match registry.resolve("did:key:zQ3shokFTS3brHcDQrn82RUDfCZESWL1ZdCEJwekUDPQiYBme").await {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert_eq!(result.did_document.id, "did:key:zQ3shokFTS3brHcDQrn82RUDfCZESWL1ZdCEJwekUDPQiYBme");
+    }
+
+    #[actix_rt::test]
+    async fn test_resolve_verification_method() {
+        let registry = DidResolverRegistry::new();
+        let did = "did:key:zQ3shokFTS3brHcDQrn82RUDfCZESWL1ZdCEJwekUDPQiYBme";
+
+        let result = match registry.resolve_verification_method(&format!("{}#zQ3shokFTS3brHcDQrn82RUDfCZESWL1ZdCEJwekUDPQiYBme", did), VerificationRelationship::AssertionMethod).await {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert_eq!(result.r#type, "EcdsaSecp256k1VerificationKey2019");
+        assert!(registry.resolve_verification_method(did, VerificationRelationship::Authentication).await.is_ok());
+        assert!(registry.resolve_verification_method(&format!("{}#other", did), VerificationRelationship::Authentication).await.is_err());
+    }
+
+    // NOTE: the current document is deactivated, the historical one is not
+    struct DeactivatedResolver {
+        deactivated: bool,
+    }
+
+    #[async_trait]
+    impl DidResolver for DeactivatedResolver {
+        async fn resolve(&self, did: &str) -> Result<DIDResolutionResponse, NodeXError> {
+            match key::KeyDidResolver::resolve_document(did) {
+                Ok(mut v) => {
+                    v.method_metadata.deactivated = Some(self.deactivated);
+                    Ok(v)
+                },
+                Err(_) => Err(NodeXError{})
+            }
+        }
+
+        async fn resolve_version(&self, did: &str, _version: &DocumentVersion) -> Result<DIDResolutionResponse, NodeXError> {
+            key::KeyDidResolver::resolve_document(did)
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_resolve_verification_method_version() {
+        let did_url = "did:key:zQ3shokFTS3brHcDQrn82RUDfCZESWL1ZdCEJwekUDPQiYBme#zQ3shokFTS3brHcDQrn82RUDfCZESWL1ZdCEJwekUDPQiYBme";
+        let version = DocumentVersion::Time("2023-01-01T00:00:00Z".to_string());
+
+        let active = DeactivatedResolver { deactivated: false };
+        assert!(active.resolve_verification_method_version(did_url, VerificationRelationship::AssertionMethod, Some(&version)).await.is_ok());
+
+        let deactivated = DeactivatedResolver { deactivated: true };
+        assert!(deactivated.resolve_verification_method(did_url, VerificationRelationship::AssertionMethod).await.is_err());
+        assert!(deactivated.resolve_verification_method_version(did_url, VerificationRelationship::AssertionMethod, Some(&version)).await.is_err());
+    }
+}
@@ -0,0 +1,157 @@
+use async_trait::async_trait;
+use reqwest::StatusCode;
+use serde_json::Value;
+use crate::nodex::{errors::NodeXError, sidetree::{payload::{OperationPayload, DIDResolutionResponse, DIDOperationsResponse}, resolver::{LongFormResolver, ResolutionVerifier}}, utils::http_client::{HttpClient, HttpClientConfig}};
+use super::{DidResolver, dereferencer::DocumentVersion};
+
+pub const DEFAULT_BASE_URL: &str = "https://did.nodecross.io";
+
+pub struct NodexDidResolver {
+    http_client: HttpClient,
+}
+
+impl Default for NodexDidResolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NodexDidResolver {
+    pub fn new() -> Self {
+        let client_config: HttpClientConfig = HttpClientConfig {
+            base_url: DEFAULT_BASE_URL.to_string(),
+        };
+
+        let client = match HttpClient::new(&client_config) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        NodexDidResolver { http_client: client }
+    }
+
+    // NOTE: the operations of the DID in the order the node anchored them
+    async fn find_operations(&self, did: &str) -> Result<Vec<Value>, NodeXError> {
+        let query = qstring::QString::new(vec![ ("did", did) ]);
+
+        let res = match self.http_client.get(&(format!("/api/v1/operations?{}", query))).await {
+            Ok(v) if v.status().is_success() => v,
+            _ => return Err(NodeXError{})
+        };
+
+        match res.json::<DIDOperationsResponse>().await {
+            Ok(v) => Ok(v.operations),
+            Err(_) => Err(NodeXError{})
+        }
+    }
+
+    // NOTE: Ok(None) when the node does not know the DID
+    async fn find_identifier(&self, did: &str, query: &str) -> Result<Option<DIDResolutionResponse>, NodeXError> {
+        let res = match self.http_client.get(&(format!("/api/v1/identifiers/{}{}", &did, query))).await {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        if res.status() == StatusCode::NOT_FOUND {
+            return Ok(None)
+        }
+
+        if !res.status().is_success() {
+            log::error!("unable to resolve {}: {}", &did, res.status());
+            return Err(NodeXError{})
+        }
+
+        match res.json::<DIDResolutionResponse>().await {
+            Ok(v) => Ok(Some(v)),
+            Err(_) => Err(NodeXError{})
+        }
+    }
+
+    async fn find_published_identifier_with_query(&self, did: &str, query: &str) -> Result<Option<DIDResolutionResponse>, NodeXError> {
+        match self.find_identifier(did, query).await {
+            Ok(Some(v)) => self.verify(did, query, v).await.map(Some),
+            Ok(None) => Ok(None),
+            Err(_) => Err(NodeXError{})
+        }
+    }
+
+    async fn verify(&self, did: &str, query: &str, json: DIDResolutionResponse) -> Result<DIDResolutionResponse, NodeXError> {
+        // NOTE: an unpublished DID has no operations on the node, and a node may not serve them at all. The create
+        //       operation data is checked anyway, the history is replayed only when the node returns it
+        let short_form = match OperationPayload::did_short_form(did) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        let operations = if json.method_metadata.published {
+            match self.find_operations(&short_form).await {
+                Ok(v) => Some(v),
+                Err(_) => {
+                    log::warn!("the operations of {} are not available, the history is not replayed", &did);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        let verified = if query.is_empty() {
+            ResolutionVerifier::verify(did, &json, operations.as_deref())
+        } else {
+            ResolutionVerifier::verify_version(did, &json, operations.as_deref())
+        };
+
+        match verified {
+            Ok(_) => Ok(json),
+            Err(_) => {
+                log::error!("resolution response for {} does not match the DID", &did);
+                Err(NodeXError{})
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl DidResolver for NodexDidResolver {
+    async fn resolve(&self, did: &str) -> Result<DIDResolutionResponse, NodeXError> {
+        match self.find(did).await {
+            Ok(Some(v)) => Ok(v),
+            _ => Err(NodeXError{})
+        }
+    }
+
+    async fn find(&self, did: &str) -> Result<Option<DIDResolutionResponse>, NodeXError> {
+        if OperationPayload::is_long_form(did) {
+            let short_form = match OperationPayload::did_short_form(did) {
+                Ok(v) => v,
+                Err(_) => return Err(NodeXError{})
+            };
+
+            // NOTE: prefer the published document, fall back to the initial state. The long form is verified, it carries
+            //       the create operation data
+            return match self.find_identifier(&short_form, "").await {
+                Ok(Some(v)) if v.method_metadata.published => self.verify(did, "", v).await.map(Some),
+                _ => LongFormResolver::resolve(did).map(Some),
+            }
+        }
+
+        self.find_published_identifier_with_query(did, "").await
+    }
+
+    async fn resolve_version(&self, did: &str, version: &DocumentVersion) -> Result<DIDResolutionResponse, NodeXError> {
+        let short_form = match OperationPayload::did_short_form(did) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        let query = match version {
+            DocumentVersion::Id(v) => qstring::QString::new(vec![ ("versionId", v.as_str()) ]),
+            DocumentVersion::Time(v) => qstring::QString::new(vec![ ("versionTime", v.as_str()) ]),
+        };
+
+        match self.find_published_identifier_with_query(&short_form, &format!("?{}", query)).await {
+            Ok(Some(v)) => Ok(v),
+            _ => Err(NodeXError{})
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use async_trait::async_trait;
+use reqwest::StatusCode;
+use serde_json::Value;
+use crate::nodex::{errors::NodeXError, schema::did_document::DIDDocument, sidetree::payload::{DIDResolutionResponse, MethodMetadata}};
+use super::DidResolver;
+
+pub struct WebDidResolver {
+    instance: reqwest::Client,
+}
+
+impl Default for WebDidResolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WebDidResolver {
+    pub fn new() -> Self {
+        WebDidResolver { instance: reqwest::Client::new() }
+    }
+
+    pub fn document_url(did: &str) -> Result<String, NodeXError> {
+        let identifier = match did.strip_prefix("did:web:") {
+            Some(v) => v,
+            None => return Err(NodeXError{})
+        };
+
+        let segments: Vec<String> = identifier.split(':').map(|v| v.replace("%3A", ":").replace("%3a", ":")).collect();
+
+        if segments.iter().any(|v| v.is_empty() || v.contains('/') || v == "." || v == "..") {
+            return Err(NodeXError{})
+        }
+
+        // NOTE: user info in the host would send the request to another host
+        if segments[0].contains('@') {
+            return Err(NodeXError{})
+        }
+
+        if segments.len() == 1 {
+            Ok(format!("https://{}/.well-known/did.json", &segments[0]))
+        } else {
+            Ok(format!("https://{}/did.json", segments.join("/")))
+        }
+    }
+
+    pub fn to_document(did: &str, value: &Value) -> Result<DIDDocument, NodeXError> {
+        let document = match serde_json::from_value::<DIDDocument>(value.clone()) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        if document.id != did {
+            return Err(NodeXError{})
+        }
+
+        Ok(document)
+    }
+}
+
+#[async_trait]
+impl DidResolver for WebDidResolver {
+    async fn resolve(&self, did: &str) -> Result<DIDResolutionResponse, NodeXError> {
+        match self.find(did).await {
+            Ok(Some(v)) => Ok(v),
+            _ => Err(NodeXError{})
+        }
+    }
+
+    async fn find(&self, did: &str) -> Result<Option<DIDResolutionResponse>, NodeXError> {
+        let url = match Self::document_url(did) {
+            Ok(v) => v,
+            Err(_) => return Ok(None)
+        };
+
+        let res = match self.instance.get(&url).send().await {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        if res.status() == StatusCode::NOT_FOUND || res.status() == StatusCode::GONE {
+            return Ok(None)
+        }
+
+        if !res.status().is_success() {
+            log::error!("unable to fetch {}: {}", &url, res.status());
+            return Err(NodeXError{})
+        }
+
+        let json = match res.json::<Value>().await {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        let did_document = match Self::to_document(did, &json) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        Ok(Some(DIDResolutionResponse {
+            context: "https://w3id.org/did-resolution/v1".to_string(),
+            did_document,
+            method_metadata: MethodMetadata {
+                published: true,
+                recovery_commitment: None,
+                update_commitment: None,
+                deactivated: None,
+            },
+        }))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use rstest::*;
+    use serde_json::json;
+
+    #[rstest]
+    #[case("did:web:w3c-ccg.github.io", "https://w3c-ccg.github.io/.well-known/did.json")]
+    #[case("did:web:w3c-ccg.github.io:user:alice", "https://w3c-ccg.github.io/user/alice/did.json")]
+    #[case("did:web:example.com%3A3000:user:alice", "https://example.com:3000/user/alice/did.json")]
+    fn test_document_url(#[case] did: &str, #[case] expected: &str) {
+        let result = match WebDidResolver::document_url(did) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert_eq!(result, expected);
+    }
+
+    #[rstest]
+    #[case("did:web:")]
+    #[case("did:web:example.com::alice")]
+    #[case("did:web:example.com:..:alice")]
+    #[case("did:web:example.com:user:.")]
+    #[case("did:web:..")]
+    #[case("did:web:alice@example.com")]
+    #[case("did:web:example.com%3A3000@attacker.example:alice")]
+    #[case("did:key:z6MkhaXgBZDvotDkL5257faiztiGiC2QtKLGpbnnEGta2doK")]
+    fn test_document_url_with_invalid_did(#[case] did: &str) {
+        assert!(WebDidResolver::document_url(did).is_err());
+    }
+
+    #[test]
+    fn test_to_document() {
+        let did = "did:web:example.com";
+        let value = json!({
+            "@context": [ "https://www.w3.org/ns/did/v1" ],
+            "id": did,
+            "verificationMethod": [{
+                "id": "#key-1",
+                "type": "JsonWebKey2020",
+                "controller": did,
+                "publicKeyJwk": {
+                    "kty": "EC",
+                    "crv": "secp256k1",
+                    "x": "Z4Y3NNOxv0J6tCgqOBFnHnaZhJF6LdulT7z8A-2D5_8",
+                    "y": "i5a2NtJoUKXkLm6q8nOEu9WOkso1Ag6FTUT6k_LMnGk",
+                },
+            }, {
+                "id": "#key-2",
+                "type": "Ed25519VerificationKey2018",
+                "controller": did,
+                "publicKeyBase58": "H3C2AVvLMv6gmMNam3uVAjZpfkcJCwDwnZn6z3wXmqPV",
+            }],
+            "authentication": [ "#key-1" ],
+        });
+
+        let result = match WebDidResolver::to_document(did, &value) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        let public_keys = result.verification_methods();
+
+        assert_eq!(public_keys.len(), 2);
+        assert_eq!(result.absolute_id(&public_keys[0].id), "did:web:example.com#key-1");
+        assert!(public_keys[0].jwk().is_some());
+        assert!(public_keys[1].jwk().is_none());
+        assert_eq!(serde_json::to_value(&result).unwrap(), value);
+    }
+
+    #[test]
+    fn test_to_document_with_other_id() {
+        let value = json!({ "id": "did:web:example.org" });
+
+        assert!(WebDidResolver::to_document("did:web:example.com", &value).is_err());
+    }
+}
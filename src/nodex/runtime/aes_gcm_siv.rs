@@ -6,7 +6,6 @@ use aes_gcm_siv::aead::{Aead, NewAead};
 pub struct AesGcmSiv {}
 
 impl AesGcmSiv {
-    #[allow(dead_code)]
     pub fn encrypt(_key: &[u8], _nonce: &[u8], _plain_text: &[u8]) -> Result<Vec<u8>, NodeXError> {
         let key = Key::from_slice(_key);
         let nonce = Nonce::from_slice(_nonce);
@@ -19,7 +18,6 @@ impl AesGcmSiv {
         }
     }
 
-    #[allow(dead_code)]
     pub fn decrypt(_key: &[u8], _nonce: &[u8], _cipher_text: &[u8]) -> Result<Vec<u8>, NodeXError> {
         let key = Key::from_slice(_key);
         let nonce = Nonce::from_slice(_nonce);
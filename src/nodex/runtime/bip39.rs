@@ -30,7 +30,10 @@ impl BIP39 {
 
         let seed = match trng.read(&(bits / 8)) {
             Ok(v) => v,
-            Err(_) => return Err(NodeXError{})
+            Err(e) => {
+                log::error!("unable to read entropy: {:?}", e);
+                return Err(NodeXError{})
+            }
         };
 
         match Mnemonic::from_entropy_in(Language::English, &seed) {
@@ -0,0 +1,74 @@
+use std::convert::TryFrom;
+use ed25519_dalek::{ExpandedSecretKey, PublicKey, SecretKey, Signature};
+
+use crate::nodex::errors::NodeXError;
+
+pub struct Ed25519 {}
+
+impl Ed25519 {
+    pub fn generate_public_key(private_key: &[u8]) -> Result<Vec<u8>, NodeXError> {
+        let secret_key = match SecretKey::from_bytes(private_key) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        Ok(PublicKey::from(&secret_key).to_bytes().to_vec())
+    }
+
+    pub fn eddsa_sign(message: &[u8], private_key: &[u8]) -> Result<Vec<u8>, NodeXError> {
+        let secret_key = match SecretKey::from_bytes(private_key) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+        let public_key = PublicKey::from(&secret_key);
+
+        let signature = ExpandedSecretKey::from(&secret_key).sign(message, &public_key);
+
+        Ok(signature.to_bytes().to_vec())
+    }
+
+    pub fn eddsa_verify(signature: &[u8], message: &[u8], public_key: &[u8]) -> Result<bool, NodeXError> {
+        let verify_key = match PublicKey::from_bytes(public_key) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        let wrapped_signature = match Signature::try_from(signature) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        match verify_key.verify_strict(message, &wrapped_signature) {
+            Ok(()) => Ok(true),
+            Err(_) => Ok(false)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // NOTE: RFC 8032, 7.1 (TEST 2)
+    #[test]
+    fn test() {
+        let private_key = hex::decode("4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb").unwrap();
+        let message = hex::decode("72").unwrap();
+
+        let public_key = match Ed25519::generate_public_key(&private_key) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert_eq!(hex::encode(&public_key), "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c");
+
+        let signature = match Ed25519::eddsa_sign(&message, &private_key) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert_eq!(hex::encode(&signature), "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00");
+        assert!(Ed25519::eddsa_verify(&signature, &message, &public_key).unwrap());
+        assert!(!Ed25519::eddsa_verify(&signature, b"other", &public_key).unwrap());
+    }
+}
@@ -11,11 +11,9 @@ type _HmacSha512 = Hmac<Sha512>;
 //     fn verify(secret: &[u8], message: &[u8], digest: &[u8]) -> Result<bool, NodeXError>;
 // }
 
-#[allow(dead_code)]
 pub struct HmacSha256 {}
 
 impl HmacSha256 {
-    #[allow(dead_code)]
     pub fn digest(secret: &[u8], message: &[u8]) -> Result<Vec<u8>, NodeXError> {
         let mut mac = match _HmacSha256::new_from_slice(secret) {
             Ok(v) => v,
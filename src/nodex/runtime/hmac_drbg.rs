@@ -0,0 +1,113 @@
+use crate::nodex::errors::NodeXError;
+
+use super::hmac::HmacSha256;
+
+// NOTE: HMAC_DRBG with SHA-256 (NIST SP 800-90A, 10.1.2)
+pub struct HmacDrbg {
+    key: Vec<u8>,
+    value: Vec<u8>,
+    reseed_counter: u64,
+}
+
+impl HmacDrbg {
+    const OUTLEN: usize = 32;
+    const RESEED_INTERVAL: u64 = 1 << 48;
+    const MAX_BYTES_PER_REQUEST: usize = 1 << 16;
+
+    pub fn new(entropy: &[u8], nonce: &[u8], personalization: &[u8]) -> Result<Self, NodeXError> {
+        let mut drbg = HmacDrbg {
+            key: vec![0x00; Self::OUTLEN],
+            value: vec![0x01; Self::OUTLEN],
+            reseed_counter: 1,
+        };
+
+        match drbg.update(&[ entropy, nonce, personalization ].concat()) {
+            Ok(_) => Ok(drbg),
+            Err(_) => Err(NodeXError{})
+        }
+    }
+
+    fn update(&mut self, provided: &[u8]) -> Result<(), NodeXError> {
+        self.key = HmacSha256::digest(&self.key, &[ &self.value[..], &[ 0x00 ], provided ].concat())?;
+        self.value = HmacSha256::digest(&self.key, &self.value)?;
+
+        if provided.is_empty() {
+            return Ok(())
+        }
+
+        self.key = HmacSha256::digest(&self.key, &[ &self.value[..], &[ 0x01 ], provided ].concat())?;
+        self.value = HmacSha256::digest(&self.key, &self.value)?;
+
+        Ok(())
+    }
+
+    pub fn reseed(&mut self, entropy: &[u8], additional: &[u8]) -> Result<(), NodeXError> {
+        self.update(&[ entropy, additional ].concat())?;
+        self.reseed_counter = 1;
+
+        Ok(())
+    }
+
+    pub fn generate(&mut self, size: usize, additional: &[u8]) -> Result<Vec<u8>, NodeXError> {
+        if Self::RESEED_INTERVAL < self.reseed_counter || Self::MAX_BYTES_PER_REQUEST < size {
+            return Err(NodeXError{})
+        }
+
+        if !additional.is_empty() {
+            self.update(additional)?;
+        }
+
+        let mut output = Vec::with_capacity(size + Self::OUTLEN);
+
+        while output.len() < size {
+            self.value = HmacSha256::digest(&self.key, &self.value)?;
+            output.extend_from_slice(&self.value);
+        }
+
+        output.truncate(size);
+
+        self.update(additional)?;
+        self.reseed_counter += 1;
+
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_generate() {
+        // NOTE: NIST CAVP HMAC_DRBG SHA-256, no prediction resistance, COUNT = 0
+        let (entropy, nonce) = match (hex::decode("ca851911349384bffe89de1cbdc46e6831e44d34a4fb935ee285dd14b71a7488"), hex::decode("659ba96c601dc69fc902940805ec0ca8")) {
+            (Ok(entropy), Ok(nonce)) => (entropy, nonce),
+            _ => panic!()
+        };
+
+        let mut drbg = match HmacDrbg::new(&entropy, &nonce, &[]) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        let result = match drbg.generate(128, &[]).and_then(|_| drbg.generate(128, &[])) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert_eq!(hex::encode(result), "e528e9abf2dece54d47c7e75e5fe302149f817ea9fb4bee6f4199697d04d5b89d54fbb978a15b5c443c9ec21036d2460b6f73ebad0dc2aba6e624abf07745bc107694bb7547bb0995f70de25d6b29e2d3011bb19d27676c07162c8b5ccde0668961df86803482cb37ed6d5c0bb8d50cf1f50d476aa0458bdaba806f48be9dcb8");
+    }
+
+    #[test]
+    pub fn test_reseed() {
+        let (mut drbg, mut other) = match (HmacDrbg::new(&[ 0x01; 32 ], &[ 0x02; 16 ], &[]), HmacDrbg::new(&[ 0x01; 32 ], &[ 0x02; 16 ], &[])) {
+            (Ok(drbg), Ok(other)) => (drbg, other),
+            _ => panic!()
+        };
+
+        assert!(other.reseed(&[ 0x03; 32 ], &[]).is_ok());
+
+        assert_eq!(drbg.generate(33, &[]).ok().map(|v| v.len()), Some(33));
+        assert_ne!(drbg.generate(32, &[]).ok(), other.generate(32, &[]).ok());
+    }
+}
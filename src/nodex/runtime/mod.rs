@@ -7,4 +7,8 @@ pub mod hmac;
 pub mod multihash;
 pub mod random;
 pub mod jcs;
-pub mod sha2;
\ No newline at end of file
+pub mod sha2;
+pub mod scrypt;
+pub mod hmac_drbg;
+pub mod ed25519;
+pub mod p256;
\ No newline at end of file
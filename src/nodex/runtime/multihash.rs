@@ -60,6 +60,19 @@ impl Multihash {
         Ok(Multihash::hash_then_encode(&hashed))
     }
 
+    pub fn canonicalize_then_hash_then_encode(message: &[u8]) -> Result<String, NodeXError> {
+        let plain = match String::from_utf8(message.to_vec()) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+        let canonicalized = match super::jcs::Jcs::canonicalize(&plain) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        Ok(Multihash::hash_then_encode(canonicalized.as_bytes()))
+    }
+
     #[allow(dead_code)]
     pub fn decode(encoded: &[u8]) -> Result<DecodedContainer, NodeXError> {
         // check for: [ code, size, digest... ]
@@ -126,6 +139,16 @@ mod tests {
         assert_eq!(result, String::from("EiAEX1W46vVid7IjJyFY5ibjmyrgepTjW0rYrw-wo4xLCw"));
     }
 
+    #[test]
+    fn test_canonicalize_then_hash_then_encode() {
+        let result = match Multihash::canonicalize_then_hash_then_encode(message().as_bytes()) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert_eq!(result, String::from("EiBfRiXU9h7bUngHRV9I-L4njnHoSqlNIxEf-rO2MJOnEw"));
+    }
+
     #[test]
     fn test_hash_then_encode() {
         let result = Multihash::hash_then_encode(message().as_bytes());
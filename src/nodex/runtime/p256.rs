@@ -0,0 +1,157 @@
+use hmac::digest::generic_array::GenericArray;
+use p256::{
+    ecdsa::{SigningKey, VerifyingKey, Signature, signature::{Signer, Verifier}},
+    PublicKey, elliptic_curve::sec1::ToEncodedPoint,
+    SecretKey
+};
+use p256::elliptic_curve::ecdh::diffie_hellman;
+
+use crate::nodex::errors::NodeXError;
+
+use super::sha2::SHA256;
+
+pub struct P256 {}
+
+impl P256 {
+    pub fn ecdh(private_key: &[u8], public_key: &[u8]) -> Result<Vec<u8>, NodeXError> {
+        let sk = match SecretKey::from_be_bytes(private_key) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+        let pk = match PublicKey::from_sec1_bytes(public_key) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+
+        Ok(diffie_hellman(
+            sk.to_nonzero_scalar(),
+            pk.as_affine()
+        ).as_bytes().to_vec())
+    }
+
+    // NOTE: the uncompressed point (65 bytes)
+    pub fn generate_public_key(private_key: &[u8]) -> Result<Vec<u8>, NodeXError> {
+        let secret_key = match SecretKey::from_be_bytes(private_key) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        Ok(secret_key.public_key().to_encoded_point(false).as_bytes().to_vec())
+    }
+
+    pub fn convert_public_key(public_key: &[u8], compress: bool) -> Result<Vec<u8>, NodeXError> {
+        let public_key = match PublicKey::from_sec1_bytes(public_key) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        Ok(public_key.to_encoded_point(compress).as_bytes().to_vec())
+    }
+
+    pub fn ecdsa_sign(message: &[u8], private_key: &[u8]) -> Result<Vec<u8>, NodeXError> {
+        let signing_key = match SigningKey::from_bytes(private_key) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        let signature: Signature = match signing_key.try_sign(message) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        Ok(signature.as_ref().to_vec())
+    }
+
+    pub fn ecdsa_verify(signature: &[u8], message: &[u8], public_key: &[u8]) -> Result<bool, NodeXError> {
+        let verify_key = match VerifyingKey::from_sec1_bytes(public_key) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        if signature.len() != 64 {
+            return Err(NodeXError{})
+        }
+
+        let r = GenericArray::from_slice(&signature[0..32]);
+        let s = GenericArray::from_slice(&signature[32..]);
+
+        let wrapped_signature = match Signature::from_scalars(*r, *s) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        match verify_key.verify(message, &wrapped_signature) {
+            Ok(()) => Ok(true),
+            Err(_) => Ok(false)
+        }
+    }
+
+    // NOTE: the Concat KDF of JWA (RFC 7518, 4.6.2) with SHA-256, the key of ECDH-ES in Direct Key Agreement mode
+    pub fn concat_kdf(shared_secret: &[u8], algorithm_id: &str, apu: &[u8], apv: &[u8], key_length: usize) -> Result<Vec<u8>, NodeXError> {
+        if key_length == 0 || 32 < key_length {
+            return Err(NodeXError{})
+        }
+
+        let with_length = |v: &[u8]| [ &(v.len() as u32).to_be_bytes()[..], v ].concat();
+
+        let digest = SHA256::digest(&[
+            &1u32.to_be_bytes()[..],
+            shared_secret,
+            &with_length(algorithm_id.as_bytes()),
+            &with_length(apu),
+            &with_length(apv),
+            &((key_length * 8) as u32).to_be_bytes(),
+        ].concat());
+
+        Ok(digest[..key_length].to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nodex::runtime::base64_url::{Base64Url, PaddingType};
+
+    fn decode(v: &str) -> Vec<u8> {
+        Base64Url::decode_as_bytes(v, &PaddingType::NoPadding).unwrap()
+    }
+
+    #[test]
+    fn test_ecdh() {
+        // NOTE: RFC 7518, Appendix C
+        let ephemeral = decode("0_NxaRPUMQoAJt50Gz8YiTr8gRTwyEaCumd-MToTmIo");
+        let recipient = decode("VEmDZpDXXK8p8N0Cndsxs924q6nS1RXFASRl6BfUqdw");
+
+        let ephemeral_public = P256::generate_public_key(&ephemeral).unwrap();
+        let recipient_public = P256::generate_public_key(&recipient).unwrap();
+
+        assert_eq!(ephemeral_public[1..33].to_vec(), decode("gI0GAILBdu7T53akrFmMyGcsF3n5dO7MmwNBHKW5SV0"));
+        assert_eq!(recipient_public[33..].to_vec(), decode("e8lnCO-AlStT-NJVX-crhB7QRYhiix03illJOVAOyck"));
+
+        let shared_1 = P256::ecdh(&ephemeral, &recipient_public).unwrap();
+        let shared_2 = P256::ecdh(&recipient, &P256::convert_public_key(&ephemeral_public, true).unwrap()).unwrap();
+
+        assert_eq!(shared_1, vec![
+            158, 86, 217, 29, 129, 113, 53, 211, 114, 131, 66, 131, 191, 132,
+            38, 156, 251, 49, 110, 163, 218, 128, 106, 72, 246, 218, 167, 121,
+            140, 254, 144, 196,
+        ]);
+        assert_eq!(shared_1, shared_2);
+
+        let key = P256::concat_kdf(&shared_1, "A128GCM", b"Alice", b"Bob", 16).unwrap();
+
+        assert_eq!(Base64Url::encode(&key, &PaddingType::NoPadding), "VqqN6vgjbSBcIijNcacQGg");
+    }
+
+    #[test]
+    fn test_ecdsa() {
+        let private_key = decode("VEmDZpDXXK8p8N0Cndsxs924q6nS1RXFASRl6BfUqdw");
+        let public_key = P256::generate_public_key(&private_key).unwrap();
+
+        let signature = P256::ecdsa_sign(b"0123456789abcdef", &private_key).unwrap();
+
+        assert_eq!(signature.len(), 64);
+        assert!(P256::ecdsa_verify(&signature, b"0123456789abcdef", &public_key).unwrap());
+        assert!(!P256::ecdsa_verify(&signature, b"0123456789abcdeg", &public_key).unwrap());
+    }
+}
@@ -0,0 +1,44 @@
+use crate::nodex::errors::NodeXError;
+
+pub struct Scrypt {}
+
+impl Scrypt {
+    const R: u32 = 8;
+    const P: u32 = 1;
+
+    pub fn derive_key(passphrase: &[u8], salt: &[u8], log_n: u8, size: usize) -> Result<Vec<u8>, NodeXError> {
+        let params = match scrypt::Params::new(log_n, Self::R, Self::P, size) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        let mut key = vec![0u8; size];
+
+        match scrypt::scrypt(passphrase, salt, &params, &mut key) {
+            Ok(_) => Ok(key),
+            Err(_) => Err(NodeXError{})
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    pub fn it_should_success_derive_key() {
+        let result = match Scrypt::derive_key(b"password", b"NaCl", 10, 32) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert_eq!(result.len(), 32);
+        assert_eq!(Scrypt::derive_key(b"password", b"NaCl", 10, 32).unwrap(), result);
+        assert_ne!(Scrypt::derive_key(b"password", b"KCl", 10, 32).unwrap(), result);
+    }
+
+    #[test]
+    pub fn it_should_fail_derive_key_with_invalid_params() {
+        assert!(Scrypt::derive_key(b"password", b"NaCl", 10, 0).is_err());
+    }
+}
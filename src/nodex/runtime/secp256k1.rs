@@ -86,6 +86,23 @@ impl Secp256k1 {
             Err(_) => Ok(false)
         }
     }
+
+    // NOTE: signatures made outside k256 may have a high S, which k256 rejects
+    pub fn normalize_signature(signature: &[u8]) -> Result<Vec<u8>, NodeXError> {
+        if signature.len() != 64 {
+            return Err(NodeXError{})
+        }
+
+        let r = GenericArray::from_slice(&signature[0..32]);
+        let s = GenericArray::from_slice(&signature[32..]);
+
+        let wrapped_signature = match Signature::from_scalars(*r, *s) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        Ok(wrapped_signature.normalize_s().unwrap_or(wrapped_signature).as_ref().to_vec())
+    }
 }
 
 #[cfg(test)]
@@ -241,4 +258,23 @@ mod tests {
 
         assert!(result_2);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_normalize_signature() {
+        let message = String::from(&message()).as_bytes().to_vec();
+
+        let signature = match Secp256k1::ecdsa_sign(&message, &private_key()) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        // NOTE: n - s
+        let order = ibig::UBig::from_str_radix("fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141", 16).unwrap();
+        let s = ibig::UBig::from_be_bytes(&signature[32..]);
+        let high = [ &signature[..32], &(order - s).to_be_bytes()[..] ].concat();
+
+        assert_eq!(Secp256k1::normalize_signature(&high).unwrap(), signature);
+        assert_eq!(Secp256k1::normalize_signature(&signature).unwrap(), signature);
+        assert!(Secp256k1::normalize_signature(&signature[1..]).is_err());
+    }
+}
@@ -1,10 +1,8 @@
 use sha2::{Sha256, Digest};
 
-#[allow(dead_code)]
 pub struct SHA256 {}
 
 impl SHA256 {
-    #[allow(dead_code)]
     pub fn digest(message: &[u8]) -> Vec<u8> {
         let mut hasher = Sha256::new();
 
@@ -0,0 +1,474 @@
+use std::collections::BTreeMap;
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+
+use crate::nodex::keyring::{ed25519::Ed25519, secp256k1::KeyPairSecp256K1, signing_key::SigningKey};
+
+// NOTE: DID Core properties which accept either a single value or a set
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum OneOrMany<T> {
+    One(T),
+    Many(Vec<T>),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct VerificationMethod {
+    #[serde(rename = "id")]
+    pub id: String,
+
+    #[serde(rename = "controller")]
+    pub controller: String,
+
+    #[serde(rename = "type")]
+    pub r#type: String,
+
+    #[serde(rename = "publicKeyJwk", skip_serializing_if = "Option::is_none")]
+    pub public_key_jwk: Option<Value>,
+
+    #[serde(rename = "publicKeyMultibase", skip_serializing_if = "Option::is_none")]
+    pub public_key_multibase: Option<String>,
+
+    #[serde(rename = "publicKeyBase58", skip_serializing_if = "Option::is_none")]
+    pub public_key_base58: Option<String>,
+
+    #[serde(flatten)]
+    pub additional: BTreeMap<String, Value>,
+}
+
+impl VerificationMethod {
+    pub fn new(id: &str, controller: &str, r#type: &str, jwk: &KeyPairSecp256K1) -> Self {
+        VerificationMethod {
+            id: id.to_string(),
+            controller: controller.to_string(),
+            r#type: r#type.to_string(),
+            public_key_jwk: serde_json::to_value(jwk).ok(),
+            public_key_multibase: None,
+            public_key_base58: None,
+            additional: BTreeMap::new(),
+        }
+    }
+
+    // NOTE: None when the key is not a JWK of a supported curve
+    pub fn jwk(&self) -> Option<KeyPairSecp256K1> {
+        match &self.public_key_jwk {
+            Some(v) => serde_json::from_value::<KeyPairSecp256K1>(v.clone()).ok(),
+            None => None,
+        }
+    }
+
+    // NOTE: the key verifying signatures of the method, either a JWK or an Ed25519 key of the Ed25519 suites
+    pub fn signing_key(&self) -> Option<SigningKey> {
+        if let Some(jwk) = self.jwk() {
+            return SigningKey::from_jwk(&jwk).ok()
+        }
+
+        let key = match (self.r#type.as_str(), &self.public_key_multibase, &self.public_key_base58) {
+            ("Ed25519VerificationKey2020", Some(v), _) => Ed25519::from_multibase(v).ok(),
+            ("Ed25519VerificationKey2018", _, Some(v)) => bs58::decode(v).into_vec().ok().and_then(|v| Ed25519::from_public_key(&v).ok()),
+            _ => None,
+        };
+
+        key.map(SigningKey::Ed25519)
+    }
+
+    // NOTE: legacy Sidetree documents list the purposes on the key instead of in the relationships
+    pub fn sidetree_relationships(&self) -> Vec<VerificationRelationship> {
+        match self.additional.get("purpose").and_then(|v| v.as_array()) {
+            Some(v) => v.iter().filter_map(|v| v.as_str()).filter_map(VerificationRelationship::from_sidetree_purpose).collect(),
+            None => vec![],
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum VerificationMethodRef {
+    Reference(String),
+    Embedded(VerificationMethod),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum VerificationRelationship {
+    Authentication,
+    AssertionMethod,
+    KeyAgreement,
+    CapabilityInvocation,
+    CapabilityDelegation,
+}
+
+impl VerificationRelationship {
+    pub fn from_proof_purpose(purpose: &str) -> Option<Self> {
+        match purpose {
+            "authentication" => Some(VerificationRelationship::Authentication),
+            "assertionMethod" => Some(VerificationRelationship::AssertionMethod),
+            "keyAgreement" => Some(VerificationRelationship::KeyAgreement),
+            "capabilityInvocation" => Some(VerificationRelationship::CapabilityInvocation),
+            "capabilityDelegation" => Some(VerificationRelationship::CapabilityDelegation),
+            _ => None,
+        }
+    }
+
+    // NOTE: the "general" purpose is not bound to any relationship
+    pub fn from_sidetree_purpose(purpose: &str) -> Option<Self> {
+        match purpose {
+            "auth" => Some(VerificationRelationship::Authentication),
+            v => Self::from_proof_purpose(v),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum ServiceEndpointValue {
+    Uri(String),
+    Map(BTreeMap<String, Value>),
+    Set(Vec<Value>),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Service {
+    #[serde(rename = "id")]
+    pub id: String,
+
+    #[serde(rename = "type")]
+    pub r#type: OneOrMany<String>,
+
+    #[serde(rename = "serviceEndpoint")]
+    pub service_endpoint: ServiceEndpointValue,
+
+    #[serde(flatten)]
+    pub additional: BTreeMap<String, Value>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct DIDDocument {
+    #[serde(rename = "@context", skip_serializing_if = "Option::is_none")]
+    pub context: Option<OneOrMany<Value>>,
+
+    #[serde(rename = "id")]
+    pub id: String,
+
+    #[serde(rename = "controller", skip_serializing_if = "Option::is_none")]
+    pub controller: Option<OneOrMany<String>>,
+
+    #[serde(rename = "alsoKnownAs", skip_serializing_if = "Option::is_none")]
+    pub also_known_as: Option<Vec<String>>,
+
+    #[serde(rename = "verificationMethod", skip_serializing_if = "Option::is_none")]
+    pub verification_method: Option<Vec<VerificationMethod>>,
+
+    // NOTE: Sidetree nodes still publish keys under the pre DID Core name
+    #[serde(rename = "publicKey", skip_serializing_if = "Option::is_none")]
+    pub public_key: Option<Vec<VerificationMethod>>,
+
+    #[serde(rename = "authentication", skip_serializing_if = "Option::is_none")]
+    pub authentication: Option<Vec<VerificationMethodRef>>,
+
+    #[serde(rename = "assertionMethod", skip_serializing_if = "Option::is_none")]
+    pub assertion_method: Option<Vec<VerificationMethodRef>>,
+
+    #[serde(rename = "keyAgreement", skip_serializing_if = "Option::is_none")]
+    pub key_agreement: Option<Vec<VerificationMethodRef>>,
+
+    #[serde(rename = "capabilityInvocation", skip_serializing_if = "Option::is_none")]
+    pub capability_invocation: Option<Vec<VerificationMethodRef>>,
+
+    #[serde(rename = "capabilityDelegation", skip_serializing_if = "Option::is_none")]
+    pub capability_delegation: Option<Vec<VerificationMethodRef>>,
+
+    #[serde(rename = "service", skip_serializing_if = "Option::is_none")]
+    pub service: Option<Vec<Service>>,
+
+    #[serde(flatten)]
+    pub additional: BTreeMap<String, Value>,
+}
+
+impl DIDDocument {
+    pub fn new(id: &str) -> Self {
+        DIDDocument {
+            context: None,
+            id: id.to_string(),
+            controller: None,
+            also_known_as: None,
+            verification_method: None,
+            public_key: None,
+            authentication: None,
+            assertion_method: None,
+            key_agreement: None,
+            capability_invocation: None,
+            capability_delegation: None,
+            service: None,
+            additional: BTreeMap::new(),
+        }
+    }
+
+    // NOTE: relative references ("#key-1") are relative to the document id
+    pub fn absolute_id(&self, id: &str) -> String {
+        if id.starts_with('#') {
+            format!("{}{}", self.id, id)
+        } else {
+            id.to_string()
+        }
+    }
+
+    // NOTE: every method in the document, including the ones embedded in verification relationships
+    pub fn verification_methods(&self) -> Vec<VerificationMethod> {
+        let embedded = [
+            &self.authentication,
+            &self.assertion_method,
+            &self.key_agreement,
+            &self.capability_invocation,
+            &self.capability_delegation,
+        ];
+
+        let mut methods: Vec<VerificationMethod> = vec![];
+
+        let candidates = self.verification_method.iter().flatten()
+            .chain(self.public_key.iter().flatten())
+            .chain(embedded.iter().filter_map(|v| v.as_ref()).flatten().filter_map(|v| match v {
+                VerificationMethodRef::Embedded(v) => Some(v),
+                VerificationMethodRef::Reference(_) => None,
+            }));
+
+        for method in candidates {
+            if !methods.iter().any(|v| self.absolute_id(&v.id) == self.absolute_id(&method.id)) {
+                methods.push(method.clone());
+            }
+        }
+
+        methods
+    }
+
+    fn relationship(&self, relationship: VerificationRelationship) -> &Option<Vec<VerificationMethodRef>> {
+        match relationship {
+            VerificationRelationship::Authentication => &self.authentication,
+            VerificationRelationship::AssertionMethod => &self.assertion_method,
+            VerificationRelationship::KeyAgreement => &self.key_agreement,
+            VerificationRelationship::CapabilityInvocation => &self.capability_invocation,
+            VerificationRelationship::CapabilityDelegation => &self.capability_delegation,
+        }
+    }
+
+    pub fn verification_methods_for(&self, relationship: VerificationRelationship) -> Vec<VerificationMethod> {
+        let refs = match self.relationship(relationship) {
+            Some(v) => v,
+            None => {
+                return self.public_key.iter().flatten()
+                    .filter(|v| v.sidetree_relationships().contains(&relationship))
+                    .cloned()
+                    .collect()
+            },
+        };
+
+        let methods = self.verification_methods();
+
+        refs.iter().filter_map(|item| match item {
+            VerificationMethodRef::Reference(id) => {
+                methods.iter().find(|v| self.absolute_id(&v.id) == self.absolute_id(id)).cloned()
+            },
+            VerificationMethodRef::Embedded(v) => Some(v.clone()),
+        }).collect()
+    }
+
+    pub fn find_verification_method(&self, id: &str, relationship: VerificationRelationship) -> Option<VerificationMethod> {
+        self.verification_methods_for(relationship).into_iter().find(|v| self.absolute_id(&v.id) == self.absolute_id(id))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::nodex::keyring::signing_key::KeyType;
+    use serde_json::json;
+
+    fn document() -> Value {
+        json!({
+            "@context": [
+                "https://www.w3.org/ns/did/v1",
+                { "@base": "did:example:123" },
+            ],
+            "id": "did:example:123",
+            "controller": "did:example:456",
+            "alsoKnownAs": [ "https://example.com/alice" ],
+            "verificationMethod": [{
+                "id": "#key-1",
+                "type": "JsonWebKey2020",
+                "controller": "did:example:123",
+                "publicKeyJwk": {
+                    "kty": "EC",
+                    "crv": "secp256k1",
+                    "x": "Z4Y3NNOxv0J6tCgqOBFnHnaZhJF6LdulT7z8A-2D5_8",
+                    "y": "i5a2NtJoUKXkLm6q8nOEu9WOkso1Ag6FTUT6k_LMnGk",
+                    "use": "sig",
+                },
+            }],
+            "authentication": [
+                "#key-1",
+                {
+                    "id": "#key-2",
+                    "type": "Ed25519VerificationKey2020",
+                    "controller": "did:example:123",
+                    "publicKeyMultibase": "z6MkmM42vxfqZQsv4ehtTjFFxQ4sQKS2w6WR7emozFAn5cxu",
+                },
+            ],
+            "assertionMethod": [ "#key-1" ],
+            "keyAgreement": [{
+                "id": "#key-3",
+                "type": "X25519KeyAgreementKey2019",
+                "controller": "did:example:123",
+                "publicKeyBase58": "JhNWeSVLMYccCk7iopQW4guaSJTojqpMEELgSLhKwRr",
+            }],
+            "capabilityInvocation": [ "#key-1" ],
+            "service": [{
+                "id": "#didcomm",
+                "type": "DIDCommMessaging",
+                "serviceEndpoint": {
+                    "uri": "https://example.com/didcomm",
+                    "accept": [ "didcomm/v2" ],
+                    "routingKeys": [],
+                },
+            }, {
+                "id": "#domain",
+                "type": [ "LinkedDomains" ],
+                "serviceEndpoint": "https://example.com",
+            }],
+            "created": "2023-01-01T00:00:00Z",
+        })
+    }
+
+    #[test]
+    pub fn test_round_trip() {
+        let value = document();
+
+        let result = match serde_json::from_value::<DIDDocument>(value.clone()) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert_eq!(serde_json::to_value(&result).unwrap(), value);
+    }
+
+    #[test]
+    pub fn test_verification_methods() {
+        let result = match serde_json::from_value::<DIDDocument>(document()) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        let ids: Vec<String> = result.verification_methods().iter().map(|v| result.absolute_id(&v.id)).collect();
+
+        assert_eq!(ids, vec![
+            "did:example:123#key-1".to_string(),
+            "did:example:123#key-2".to_string(),
+            "did:example:123#key-3".to_string(),
+        ]);
+
+        let jwk = match result.verification_methods()[0].jwk() {
+            Some(v) => v,
+            None => panic!()
+        };
+
+        assert_eq!(jwk.crv, "secp256k1");
+        assert!(result.verification_methods()[1].jwk().is_none());
+    }
+
+    #[test]
+    pub fn test_signing_key() {
+        let result = match serde_json::from_value::<DIDDocument>(document()) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        let methods = result.verification_methods();
+
+        assert_eq!(methods[0].signing_key().map(|v| v.get_type()), Some(KeyType::Secp256k1));
+        assert_eq!(methods[1].signing_key().map(|v| v.get_type()), Some(KeyType::Ed25519));
+        assert!(methods[2].signing_key().is_none());
+    }
+
+    #[test]
+    pub fn test_find_verification_method() {
+        let result = match serde_json::from_value::<DIDDocument>(document()) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert!(result.find_verification_method("did:example:123#key-1", VerificationRelationship::AssertionMethod).is_some());
+        assert!(result.find_verification_method("#key-2", VerificationRelationship::Authentication).is_some());
+        assert!(result.find_verification_method("#key-2", VerificationRelationship::AssertionMethod).is_none());
+        assert!(result.find_verification_method("#key-3", VerificationRelationship::Authentication).is_none());
+        assert!(result.find_verification_method("#key-3", VerificationRelationship::KeyAgreement).is_some());
+        assert!(result.find_verification_method("#key-4", VerificationRelationship::Authentication).is_none());
+        assert_eq!(result.verification_methods_for(VerificationRelationship::CapabilityDelegation).len(), 0);
+    }
+
+    #[test]
+    pub fn test_sidetree_document() {
+        let value = json!({
+            "id": "did:nodex:test:EiBprXreMiba4loyl3psXm0RsECdtlCiQIjM8G9BtdQplA",
+            "publicKey": [{
+                "id": "#signingKey",
+                "controller": "",
+                "type": "EcdsaSecp256k1VerificationKey2019",
+                "publicKeyJwk": {
+                    "kty": "EC",
+                    "crv": "secp256k1",
+                    "x": "Z4Y3NNOxv0J6tCgqOBFnHnaZhJF6LdulT7z8A-2D5_8",
+                    "y": "i5a2NtJoUKXkLm6q8nOEu9WOkso1Ag6FTUT6k_LMnGk",
+                },
+            }],
+            "authentication": [ "#signingKey" ],
+        });
+
+        let result = match serde_json::from_value::<DIDDocument>(value.clone()) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert_eq!(result.verification_methods().len(), 1);
+        assert!(result.find_verification_method("#signingKey", VerificationRelationship::Authentication).is_some());
+        assert!(result.find_verification_method("#signingKey", VerificationRelationship::AssertionMethod).is_none());
+        assert_eq!(serde_json::to_value(&result).unwrap(), value);
+    }
+
+    #[test]
+    pub fn test_sidetree_document_with_purposes() {
+        let value = json!({
+            "id": "did:nodex:test:EiBprXreMiba4loyl3psXm0RsECdtlCiQIjM8G9BtdQplA",
+            "publicKey": [{
+                "id": "#signingKey",
+                "controller": "",
+                "type": "EcdsaSecp256k1VerificationKey2019",
+                "publicKeyJwk": {
+                    "kty": "EC",
+                    "crv": "secp256k1",
+                    "x": "Z4Y3NNOxv0J6tCgqOBFnHnaZhJF6LdulT7z8A-2D5_8",
+                    "y": "i5a2NtJoUKXkLm6q8nOEu9WOkso1Ag6FTUT6k_LMnGk",
+                },
+                "purpose": [ "auth", "general" ],
+            }, {
+                "id": "#encryptionKey",
+                "controller": "",
+                "type": "X25519KeyAgreementKey2019",
+                "publicKeyJwk": {
+                    "kty": "OKP",
+                    "crv": "X25519",
+                    "x": "Z4Y3NNOxv0J6tCgqOBFnHnaZhJF6LdulT7z8A-2D5_8",
+                },
+                "purpose": [ "keyAgreement" ],
+            }],
+        });
+
+        let result = match serde_json::from_value::<DIDDocument>(value) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert!(result.find_verification_method("#signingKey", VerificationRelationship::Authentication).is_some());
+        assert!(result.find_verification_method("#signingKey", VerificationRelationship::AssertionMethod).is_none());
+        assert!(result.find_verification_method("#encryptionKey", VerificationRelationship::KeyAgreement).is_some());
+        assert!(result.find_verification_method("#encryptionKey", VerificationRelationship::Authentication).is_none());
+        assert_eq!(result.verification_methods_for(VerificationRelationship::AssertionMethod).len(), 0);
+    }
+}
@@ -1 +1,2 @@
-pub mod general;
\ No newline at end of file
+pub mod general;
+pub mod did_document;
\ No newline at end of file
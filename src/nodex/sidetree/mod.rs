@@ -1 +1,3 @@
-pub mod payload;
\ No newline at end of file
+pub mod payload;
+pub mod resolver;
+pub mod queue;
\ No newline at end of file
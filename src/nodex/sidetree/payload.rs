@@ -1,14 +1,17 @@
 use serde::{Serialize, Deserialize};
-use serde_json::json;
+use std::collections::BTreeMap;
+use serde_json::{json, Value};
 
-use crate::nodex::{keyring::secp256k1::KeyPairSecp256K1, errors::NodeXError};
+use crate::nodex::{keyring::{secp256k1::{KeyPairSecp256K1, Secp256k1}, signing_key::SigningKey}, errors::NodeXError, schema::did_document::{DIDDocument, OneOrMany, Service, ServiceEndpointValue}};
+use crate::nodex::cipher::jws::Jws;
 use crate::nodex::runtime::multihash::Multihash;
+use crate::nodex::runtime::jcs::Jcs;
 use crate::nodex::runtime::base64_url::Base64Url;
 use crate::nodex::runtime::base64_url::PaddingType;
 
 pub struct OperationPayload {}
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ServiceEndpoint {
     #[serde(rename = "id")]
     pub id: String,
@@ -17,56 +20,42 @@ pub struct ServiceEndpoint {
     pub r#type: String,
 
     #[serde(rename = "serviceEndpoint")]
-    pub service_endpoint: String,
+    pub service_endpoint: ServiceEndpointValue,
 
-    #[serde(rename = "description")]
+    #[serde(rename = "description", skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
 }
-  
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct DidPublicKey {
-    #[serde(rename = "id")]
-    pub id: String,
-
-    #[serde(rename = "controller")]
-    pub controller: String,
-
-    #[serde(rename = "type")]
-    pub r#type: String,
-
-    #[serde(rename = "publicKeyJwk")]
-    pub public_key_jwk: KeyPairSecp256K1,
-}
-  
-#[derive(Debug, Serialize, Deserialize)]
-struct Authentication {
-    #[serde(rename = "type")]
-    r#type: String,
-
-    #[serde(rename = "publicKey")]
-    public_key: String,
-}
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct DIDDocument {
-    // TODO: impl parser for mixed type
-    // #[serde(rename = "@context")]
-    // context: String,
+impl ServiceEndpoint {
+    // NOTE: Sidetree limits ids to 50 base64url characters and types to 30 characters
+    pub fn is_valid(&self) -> bool {
+        let id = !self.id.is_empty() && self.id.len() <= 50 && self.id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        let r#type = !self.r#type.is_empty() && self.r#type.len() <= 30;
+        let endpoint = match &self.service_endpoint {
+            ServiceEndpointValue::Uri(v) => !v.is_empty(),
+            ServiceEndpointValue::Map(v) => !v.is_empty(),
+            ServiceEndpointValue::Set(v) => !v.is_empty(),
+        };
 
-    #[serde(rename = "id")]
-    pub id: String,
+        id && r#type && endpoint
+    }
 
-    #[serde(rename = "publicKey")]
-    pub public_key: Option<Vec<DidPublicKey>>,
+    pub fn to_service(&self) -> Service {
+        let mut additional = BTreeMap::new();
 
-    #[serde(rename = "service")]
-    pub service: Option<Vec<ServiceEndpoint>>,
+        if let Some(v) = &self.description {
+            additional.insert("description".to_string(), json!(v));
+        }
 
-    // TODO: impl parser for mixed type
-    #[serde(rename = "authentication")]
-    pub authentication: Option<Vec<String>>,
+        Service {
+            id: format!("#{}", self.id),
+            r#type: OneOrMany::One(self.r#type.clone()),
+            service_endpoint: self.service_endpoint.clone(),
+            additional,
+        }
+    }
 }
-
+  
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct PublicKeyPayload {
     #[serde(rename = "id")]
@@ -83,71 +72,75 @@ pub struct PublicKeyPayload {
 }
 
 // ACTION: add-public-keys
-#[allow(dead_code)]
-struct DIDAddPublicKeysPayload {
-    id     : String,
-    r#type : String,
-    jwk    : KeyPairSecp256K1,
-    purpose: Vec<String>,
-}
-
-#[allow(dead_code)]
-struct DIDAddPublicKeysAction {
-    action     : String, //'add-public-keys',
-    public_keys: Vec<DIDAddPublicKeysPayload>
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DIDAddPublicKeysAction {
+    #[serde(rename = "public_keys")]
+    pub public_keys: Vec<PublicKeyPayload>,
 }
 
 // ACTION: remove-public-keys
-#[allow(dead_code)]
-struct DIDRemovePublicKeysAction {
-    action: String, // 'remove-public-keys',
-    ids   : Vec<String>,
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DIDRemovePublicKeysAction {
+    #[serde(rename = "ids")]
+    pub ids: Vec<String>,
 }
 
 // ACTION: add-services
-#[allow(dead_code)]
-struct DIDAddServicesPayload {
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DIDAddServicesAction {
+    #[serde(rename = "services")]
+    pub services: Vec<ServiceEndpoint>,
 }
 
-#[allow(dead_code)]
-struct DIDAddServicesAction {
-    action  : String, // 'add-services',
-    services: Vec<DIDAddServicesPayload>,
+// ACTION: remove-services
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DIDRemoveServicesAction {
+    #[serde(rename = "ids")]
+    pub ids: Vec<String>,
 }
 
-// ACTION: remove-services
-#[allow(dead_code)]
-struct DIDRemoveServicesAction {
-    action: String, // 'remove-services',
-    ids   : Vec<String>,
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "action")]
+pub enum DIDPatchAction {
+    #[serde(rename = "add-public-keys")]
+    AddPublicKeys(DIDAddPublicKeysAction),
+
+    #[serde(rename = "remove-public-keys")]
+    RemovePublicKeys(DIDRemovePublicKeysAction),
+
+    #[serde(rename = "add-services")]
+    AddServices(DIDAddServicesAction),
+
+    #[serde(rename = "remove-services")]
+    RemoveServices(DIDRemoveServicesAction),
 }
 
 // ACTION: replace
 #[derive(Debug, Serialize, Deserialize)]
-struct DIDReplacePayload {
+pub struct DIDReplacePayload {
     #[serde(rename = "public_keys")] 
-    public_keys: Vec<PublicKeyPayload>,
+    pub public_keys: Vec<PublicKeyPayload>,
 
     #[serde(rename = "service_endpoints")] 
-    service_endpoints: Vec<String>,
+    pub service_endpoints: Vec<ServiceEndpoint>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-struct DIDReplaceAction {
-    action  : String, // 'replace',
-    document: DIDReplacePayload,
+pub struct DIDReplaceAction {
+    pub action  : String, // 'replace',
+    pub document: DIDReplacePayload,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
-struct DIDReplaceDeltaObject {
-    patches: Vec<DIDReplaceAction>,
-    update_commitment: String,
+pub struct DIDReplaceDeltaObject {
+    pub patches: Vec<DIDReplaceAction>,
+    pub update_commitment: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-struct DIDReplaceSuffixObject {
-    delta_hash: String,
-    recovery_commitment: String,
+pub struct DIDReplaceSuffixObject {
+    pub delta_hash: String,
+    pub recovery_commitment: String,
 }
 
 // ACTION: ietf-json-patch
@@ -162,7 +155,7 @@ struct DIDResolutionRequest {
     did: String
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct MethodMetadata {
     #[serde(rename = "published")]
     pub published: bool,
@@ -172,9 +165,18 @@ pub struct MethodMetadata {
 
     #[serde(rename = "updateCommitment")]
     pub update_commitment: Option<String>,
+
+    #[serde(rename = "deactivated", skip_serializing_if = "Option::is_none")]
+    pub deactivated: Option<bool>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl MethodMetadata {
+    pub fn is_deactivated(&self) -> bool {
+        self.deactivated.unwrap_or(false)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct DIDResolutionResponse {
     #[serde(rename = "@context")]
     pub context: String,
@@ -204,14 +206,31 @@ pub struct DIDCreateRequest {
     pub commitment_keys: CommitmentKeys,
 
     #[serde(rename = "serviceEndpoints")] 
-    pub service_endpoints: Vec<String>,
+    pub service_endpoints: Vec<ServiceEndpoint>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DIDCreatePayload {
+    pub r#type: String, // 'create',
+    pub delta: String,
+    pub suffix_data: String,
 }
 
+// NOTE: the create operation data of a long-form DID, encoded as it is sent to the node
 #[derive(Debug, Serialize, Deserialize)]
-struct DIDCreatePayload {
-    r#type: String, // 'create',
-    delta: String,
-    suffix_data: String,
+pub struct DIDLongFormState {
+    pub delta: String,
+    pub suffix_data: String,
+}
+
+// NOTE: the initial state carried by a long-form DID (Sidetree), the operation data is embedded as JSON objects
+#[derive(Debug, Serialize, Deserialize)]
+struct DIDLongFormObject {
+    #[serde(rename = "suffixData")]
+    suffix_data: Value,
+
+    #[serde(rename = "delta")]
+    delta: Value,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -226,38 +245,217 @@ pub struct DIDCreateResponse {
     pub method_metadata: MethodMetadata,
 }
 
-#[allow(dead_code)]
-struct DIDUpdateRequest {
-    // NOT IMPLEMENTED
+#[derive(Clone)]
+pub struct DIDUpdateRequest {
+    pub did_suffix: String,
+    pub patches: Vec<DIDPatchAction>,
+    pub update_key: Secp256k1,
+    pub next_update_key: KeyPairSecp256K1,
 }
 
-#[allow(dead_code)]
-struct DIDUpdateResponse {
-    // NOT IMPLEMENTED
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DIDUpdateDeltaObject {
+    pub patches: Vec<DIDPatchAction>,
+    pub update_commitment: String,
 }
 
-#[allow(dead_code)]
-struct DIDRecoverRequest {
-    // NOT IMPLEMENTED
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DIDUpdateSignedDataObject {
+    pub update_key: KeyPairSecp256K1,
+    pub delta_hash: String,
 }
 
-#[allow(dead_code)]
-struct DIDRecoverResponse {
-    // NOT IMPLEMENTED
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DIDUpdatePayload {
+    pub r#type: String, // 'update',
+    pub did_suffix: String,
+    pub reveal_value: String,
+    pub delta: String,
+    pub signed_data: String,
 }
 
-#[allow(dead_code)]
-struct DIDDeactivateRequest {
-    // NOT IMPLEMENTED
+#[derive(Clone)]
+pub struct DIDRecoverRequest {
+    pub did_suffix: String,
+    pub recovery_key: Secp256k1,
+    pub public_keys: Vec<PublicKeyPayload>,
+    pub commitment_keys: CommitmentKeys,
+    pub service_endpoints: Vec<ServiceEndpoint>,
 }
 
-#[allow(dead_code)]
-struct DIDDeactivateResponse {
-    // NOT IMPLEMENTED
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DIDRecoverSignedDataObject {
+    pub recovery_key: KeyPairSecp256K1,
+    pub delta_hash: String,
+    pub recovery_commitment: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DIDRecoverPayload {
+    pub r#type: String, // 'recover',
+    pub did_suffix: String,
+    pub reveal_value: String,
+    pub delta: String,
+    pub signed_data: String,
+}
+
+#[derive(Clone)]
+pub struct DIDDeactivateRequest {
+    pub did_suffix: String,
+    pub recovery_key: Secp256k1,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DIDDeactivateSignedDataObject {
+    pub did_suffix: String,
+    pub recovery_key: KeyPairSecp256K1,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DIDDeactivatePayload {
+    pub r#type: String, // 'deactivate',
+    pub did_suffix: String,
+    pub reveal_value: String,
+    pub signed_data: String,
+}
+
+// NOTE: an operation as it was sent to the node, in the order the node anchored it
+#[derive(Debug)]
+pub enum DIDOperation {
+    Create(DIDCreatePayload),
+    Update(DIDUpdatePayload),
+    Recover(DIDRecoverPayload),
+    Deactivate(DIDDeactivatePayload),
+}
+
+impl DIDOperation {
+    pub fn from_value(value: &Value) -> Option<Self> {
+        match value.get("type").and_then(|v| v.as_str()) {
+            Some("create") => serde_json::from_value::<DIDCreatePayload>(value.clone()).ok().map(DIDOperation::Create),
+            Some("update") => serde_json::from_value::<DIDUpdatePayload>(value.clone()).ok().map(DIDOperation::Update),
+            Some("recover") => serde_json::from_value::<DIDRecoverPayload>(value.clone()).ok().map(DIDOperation::Recover),
+            Some("deactivate") => serde_json::from_value::<DIDDeactivatePayload>(value.clone()).ok().map(DIDOperation::Deactivate),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DIDOperationsResponse {
+    #[serde(rename = "operations")]
+    pub operations: Vec<Value>,
 }
 
 impl OperationPayload {
     pub fn did_create_payload(params: &DIDCreateRequest) -> Result<String, NodeXError> {
+        let state = match Self::did_create_state(params) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        let payload: DIDCreatePayload = DIDCreatePayload {
+            r#type: "create".to_string(),
+            delta: state.delta,
+            suffix_data: state.suffix_data,
+        };
+
+        Ok(json!(payload).to_string())
+    }
+
+    pub fn did_long_form(prefix: &str, params: &DIDCreateRequest) -> Result<String, NodeXError> {
+        let state = match Self::did_create_state(params) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        let suffix = match Self::did_unique_suffix(&state.suffix_data) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        let long_form = match (Self::decode_object(&state.suffix_data), Self::decode_object(&state.delta)) {
+            (Some(suffix_data), Some(delta)) => DIDLongFormObject { suffix_data, delta },
+            _ => return Err(NodeXError{})
+        };
+
+        let canonicalized = match Jcs::canonicalize(&json!(long_form).to_string()) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+        let encoded_state = Base64Url::encode(canonicalized.as_bytes(), &PaddingType::NoPadding);
+
+        Ok(format!("{}:{}:{}", prefix, suffix, encoded_state))
+    }
+
+    fn decode_object(encoded: &str) -> Option<Value> {
+        let decoded = Base64Url::decode_as_string(encoded, &PaddingType::NoPadding).ok()?;
+
+        match serde_json::from_str::<Value>(&decoded) {
+            Ok(v) if v.is_object() => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn did_long_form_state(did: &str) -> Option<DIDLongFormState> {
+        let encoded_state = did.rsplit(':').next()?;
+        let decoded = Base64Url::decode_as_string(encoded_state, &PaddingType::NoPadding).ok()?;
+
+        // NOTE: the objects are encoded again as they were hashed, serde_json writes the keys in order without spaces
+        if let Ok(v) = serde_json::from_str::<DIDLongFormObject>(&decoded) {
+            if !v.suffix_data.is_object() || !v.delta.is_object() {
+                return None
+            }
+
+            return Some(DIDLongFormState {
+                delta: Base64Url::encode(v.delta.to_string().as_bytes(), &PaddingType::NoPadding),
+                suffix_data: Base64Url::encode(v.suffix_data.to_string().as_bytes(), &PaddingType::NoPadding),
+            })
+        }
+
+        // NOTE: long-form DIDs saved before the Sidetree encoding carry the encoded operation data
+        serde_json::from_str::<DIDLongFormState>(&decoded).ok()
+    }
+
+    pub fn is_long_form(did: &str) -> bool {
+        Self::did_long_form_state(did).is_some()
+    }
+
+    pub fn did_short_form(did: &str) -> Result<String, NodeXError> {
+        if !Self::is_long_form(did) {
+            return Ok(did.to_string())
+        }
+
+        match did.rsplit_once(':') {
+            Some((v, _)) => Ok(v.to_string()),
+            None => Err(NodeXError{})
+        }
+    }
+
+    pub fn did_long_form_payload(did: &str) -> Result<String, NodeXError> {
+        let state = match Self::did_long_form_state(did) {
+            Some(v) => v,
+            None => return Err(NodeXError{})
+        };
+
+        let payload: DIDCreatePayload = DIDCreatePayload {
+            r#type: "create".to_string(),
+            delta: state.delta,
+            suffix_data: state.suffix_data,
+        };
+
+        Ok(json!(payload).to_string())
+    }
+
+    pub fn did_unique_suffix(encoded_suffix_data: &str) -> Result<String, NodeXError> {
+        let suffix_data = match Base64Url::decode_as_string(encoded_suffix_data, &PaddingType::NoPadding) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        Multihash::canonicalize_then_hash_then_encode(suffix_data.as_bytes())
+    }
+
+    fn did_create_state(params: &DIDCreateRequest) -> Result<DIDLongFormState, NodeXError> {
         let update = json!(&params.commitment_keys.update);
         let update_commitment = match Multihash::canonicalize_then_double_hash_then_encode(update.to_string().as_bytes()) {
             Ok(v) => v,
@@ -299,14 +497,170 @@ impl OperationPayload {
         let encoded_delta = Base64Url::encode(delta_bytes, &PaddingType::NoPadding);
         let encoded_suffix = Base64Url::encode(suffix_bytes, &PaddingType::NoPadding);
 
-        let payload: DIDCreatePayload = DIDCreatePayload {
-            r#type: "create".to_string(),
+        Ok(DIDLongFormState {
             delta: encoded_delta,
             suffix_data: encoded_suffix,
+        })
+    }
+
+    pub fn did_update_payload(params: &DIDUpdateRequest) -> Result<String, NodeXError> {
+        let update_key = match params.update_key.to_jwk(false) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+        let reveal_value = match Multihash::canonicalize_then_hash_then_encode(json!(&update_key).to_string().as_bytes()) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        let next_update = json!(&params.next_update_key);
+        let update_commitment = match Multihash::canonicalize_then_double_hash_then_encode(next_update.to_string().as_bytes()) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        let delta = json!(DIDUpdateDeltaObject {
+            patches: params.patches.clone(),
+            update_commitment,
+        }).to_string();
+
+        let delta_bytes = delta.as_bytes();
+        let delta_hash = Base64Url::encode(
+            &Multihash::hash(delta_bytes), &PaddingType::NoPadding
+        );
+
+        // NOTE: sign the delta hash with the key being revealed
+        let signed_data = match Jws::encode_compact(&json!(DIDUpdateSignedDataObject {
+            update_key,
+            delta_hash,
+        }), &SigningKey::Secp256k1(params.update_key.clone())) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        let encoded_delta = Base64Url::encode(delta_bytes, &PaddingType::NoPadding);
+
+        let payload: DIDUpdatePayload = DIDUpdatePayload {
+            r#type: "update".to_string(),
+            did_suffix: params.did_suffix.clone(),
+            reveal_value,
+            delta: encoded_delta,
+            signed_data,
         };
 
         Ok(json!(payload).to_string())
     }
+
+    pub fn did_recover_payload(params: &DIDRecoverRequest) -> Result<String, NodeXError> {
+        let recovery_key = match params.recovery_key.to_jwk(false) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+        let reveal_value = match Multihash::canonicalize_then_hash_then_encode(json!(&recovery_key).to_string().as_bytes()) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        let update = json!(&params.commitment_keys.update);
+        let update_commitment = match Multihash::canonicalize_then_double_hash_then_encode(update.to_string().as_bytes()) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        let recovery = json!(&params.commitment_keys.recovery);
+        let recovery_commitment = match Multihash::canonicalize_then_double_hash_then_encode(recovery.to_string().as_bytes()) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        let document: DIDReplacePayload = DIDReplacePayload {
+            public_keys: params.public_keys.clone(),
+            service_endpoints: params.service_endpoints.clone(),
+        };
+        let patch: DIDReplaceAction = DIDReplaceAction {
+            action  : "replace".to_string(),
+            document,
+        };
+
+        let delta = json!(DIDReplaceDeltaObject {
+            patches: vec![ patch ],
+            update_commitment,
+        }).to_string();
+
+        let delta_bytes = delta.as_bytes();
+        let delta_hash = Base64Url::encode(
+            &Multihash::hash(delta_bytes), &PaddingType::NoPadding
+        );
+
+        // NOTE: sign the delta hash and the next recovery commitment with the key being revealed
+        let signed_data = match Jws::encode_compact(&json!(DIDRecoverSignedDataObject {
+            recovery_key,
+            delta_hash,
+            recovery_commitment,
+        }), &SigningKey::Secp256k1(params.recovery_key.clone())) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        let encoded_delta = Base64Url::encode(delta_bytes, &PaddingType::NoPadding);
+
+        let payload: DIDRecoverPayload = DIDRecoverPayload {
+            r#type: "recover".to_string(),
+            did_suffix: params.did_suffix.clone(),
+            reveal_value,
+            delta: encoded_delta,
+            signed_data,
+        };
+
+        Ok(json!(payload).to_string())
+    }
+
+    pub fn did_deactivate_payload(params: &DIDDeactivateRequest) -> Result<String, NodeXError> {
+        let recovery_key = match params.recovery_key.to_jwk(false) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+        let reveal_value = match Multihash::canonicalize_then_hash_then_encode(json!(&recovery_key).to_string().as_bytes()) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        let signed_data = match Jws::encode_compact(&json!(DIDDeactivateSignedDataObject {
+            did_suffix: params.did_suffix.clone(),
+            recovery_key,
+        }), &SigningKey::Secp256k1(params.recovery_key.clone())) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        let payload: DIDDeactivatePayload = DIDDeactivatePayload {
+            r#type: "deactivate".to_string(),
+            did_suffix: params.did_suffix.clone(),
+            reveal_value,
+            signed_data,
+        };
+
+        Ok(json!(payload).to_string())
+    }
+
+    pub fn commitment(key: &KeyPairSecp256K1) -> Result<String, NodeXError> {
+        match Multihash::canonicalize_then_double_hash_then_encode(json!(key).to_string().as_bytes()) {
+            Ok(v) => Ok(v),
+            Err(_) => Err(NodeXError{})
+        }
+    }
+
+    pub fn did_suffix(did: &str) -> Result<String, NodeXError> {
+        let did = match Self::did_short_form(did) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        match did.rsplit(':').next() {
+            Some(v) if !v.is_empty() => Ok(v.to_string()),
+            _ => Err(NodeXError{})
+        }
+    }
 }
 
 #[cfg(test)]
@@ -349,4 +703,255 @@ pub mod tests {
 
         println!("{}", json!(&result));
     }
+    #[test]
+    pub fn test_did_update_payload() {
+        let keyring = match keyring::mnemonic::MnemonicKeyring::create_keyring() {
+            Ok(v) => v,
+            Err(_) => panic!(),
+        };
+
+        let public = match keyring.get_encrypt_key_pair().to_public_key("encryptionKey", &["keyAgreement"]) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+        let next_update_key = match keyring.get_recovery_key_pair().to_jwk(false) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        let result = match OperationPayload::did_update_payload(&DIDUpdateRequest {
+            did_suffix: "EiCW6eklabBIrkTMHFpBln7574xmZlbMakWSCNtBWcunDg".to_string(),
+            patches: vec![
+                DIDPatchAction::AddPublicKeys(DIDAddPublicKeysAction {
+                    public_keys: vec![ public ],
+                }),
+                DIDPatchAction::RemoveServices(DIDRemoveServicesAction {
+                    ids: vec![ "messaging".to_string() ],
+                }),
+            ],
+            update_key: keyring.get_update_key_pair(),
+            next_update_key,
+        }) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        let payload = match serde_json::from_str::<DIDUpdatePayload>(&result) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert_eq!(payload.r#type, "update");
+        assert_eq!(payload.did_suffix, "EiCW6eklabBIrkTMHFpBln7574xmZlbMakWSCNtBWcunDg");
+        assert_eq!(payload.signed_data.split('.').count(), 3);
+
+        let delta = match Base64Url::decode_as_string(&payload.delta, &PaddingType::NoPadding) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+        let delta = match serde_json::from_str::<serde_json::Value>(&delta) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert_eq!(delta["patches"][0]["action"], "add-public-keys");
+        assert_eq!(delta["patches"][1]["action"], "remove-services");
+    }
+
+    #[test]
+    pub fn test_did_recover_payload() {
+        let keyring = match keyring::mnemonic::MnemonicKeyring::create_keyring() {
+            Ok(v) => v,
+            Err(_) => panic!(),
+        };
+        let next = match keyring::mnemonic::MnemonicKeyring::create_keyring() {
+            Ok(v) => v,
+            Err(_) => panic!(),
+        };
+
+        let public = match next.get_sign_key_pair().to_public_key("signingKey", &["auth", "general"]) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+        let update = match next.get_update_key_pair().to_jwk(false) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+        let recovery = match next.get_recovery_key_pair().to_jwk(false) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        let result = match OperationPayload::did_recover_payload(&DIDRecoverRequest {
+            did_suffix: "EiCW6eklabBIrkTMHFpBln7574xmZlbMakWSCNtBWcunDg".to_string(),
+            recovery_key: keyring.get_recovery_key_pair(),
+            public_keys: vec![ public ],
+            commitment_keys: CommitmentKeys {
+                recovery,
+                update,
+            },
+            service_endpoints: vec![],
+        }) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        let payload = match serde_json::from_str::<DIDRecoverPayload>(&result) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert_eq!(payload.r#type, "recover");
+        assert_eq!(payload.did_suffix, "EiCW6eklabBIrkTMHFpBln7574xmZlbMakWSCNtBWcunDg");
+
+        let signed_data: Vec<&str> = payload.signed_data.split('.').collect();
+
+        assert_eq!(signed_data.len(), 3);
+
+        let signed_data = match Base64Url::decode_as_string(signed_data[1], &PaddingType::NoPadding) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+        let signed_data = match serde_json::from_str::<DIDRecoverSignedDataObject>(&signed_data) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert_eq!(signed_data.delta_hash, Base64Url::encode(
+            &Multihash::hash(&match Base64Url::decode_as_bytes(&payload.delta, &PaddingType::NoPadding) {
+                Ok(v) => v,
+                Err(_) => panic!()
+            }), &PaddingType::NoPadding
+        ));
+    }
+
+    #[test]
+    pub fn test_did_deactivate_payload() {
+        let keyring = match keyring::mnemonic::MnemonicKeyring::create_keyring() {
+            Ok(v) => v,
+            Err(_) => panic!(),
+        };
+
+        let result = match OperationPayload::did_deactivate_payload(&DIDDeactivateRequest {
+            did_suffix: "EiCW6eklabBIrkTMHFpBln7574xmZlbMakWSCNtBWcunDg".to_string(),
+            recovery_key: keyring.get_recovery_key_pair(),
+        }) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        let payload = match serde_json::from_str::<DIDDeactivatePayload>(&result) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        let recovery_key = match keyring.get_recovery_key_pair().to_jwk(false) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+        let reveal_value = match Multihash::canonicalize_then_hash_then_encode(json!(&recovery_key).to_string().as_bytes()) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert_eq!(payload.r#type, "deactivate");
+        assert_eq!(payload.did_suffix, "EiCW6eklabBIrkTMHFpBln7574xmZlbMakWSCNtBWcunDg");
+        assert_eq!(payload.reveal_value, reveal_value);
+        assert_eq!(payload.signed_data.split('.').count(), 3);
+    }
+
+    #[test]
+    pub fn test_did_long_form() {
+        let keyring = match keyring::mnemonic::MnemonicKeyring::create_keyring() {
+            Ok(v) => v,
+            Err(_) => panic!(),
+        };
+
+        let public = match keyring.get_sign_key_pair().to_public_key("signingKey", &["auth", "general"]) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+        let update = match keyring.get_update_key_pair().to_jwk(false) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+        let recovery = match keyring.get_recovery_key_pair().to_jwk(false) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        let request = DIDCreateRequest {
+            public_keys: vec![ public ],
+            commitment_keys: CommitmentKeys {
+                recovery,
+                update,
+            },
+            service_endpoints: vec![],
+        };
+
+        let did = match OperationPayload::did_long_form("did:nodex:test", &request) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+        let short_form = match OperationPayload::did_short_form(&did) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+        let suffix = match OperationPayload::did_suffix(&did) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert!(OperationPayload::is_long_form(&did));
+        assert!(!OperationPayload::is_long_form(&short_form));
+        assert_eq!(short_form, format!("did:nodex:test:{}", suffix));
+        assert_eq!(did.split(':').count(), 5);
+
+        let payload = match OperationPayload::did_long_form_payload(&did) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+        let expected = match OperationPayload::did_create_payload(&request) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert_eq!(payload, expected);
+
+        let encoded_state = match did.rsplit(':').next() {
+            Some(v) => v,
+            None => panic!()
+        };
+        let state = match Base64Url::decode_as_string(encoded_state, &PaddingType::NoPadding).map(|v| serde_json::from_str::<Value>(&v)) {
+            Ok(Ok(v)) => v,
+            _ => panic!()
+        };
+
+        assert!(state["suffixData"]["recovery_commitment"].is_string());
+        assert!(state["delta"]["patches"].is_array());
+    }
+
+    #[test]
+    pub fn test_did_long_form_legacy() {
+        let state = json!({ "delta": "e30", "suffix_data": "e30" }).to_string();
+        let did = format!("did:nodex:test:suffix:{}", Base64Url::encode(state.as_bytes(), &PaddingType::NoPadding));
+
+        let result = match OperationPayload::did_long_form_state(&did) {
+            Some(v) => v,
+            None => panic!()
+        };
+
+        assert_eq!(result.delta, "e30");
+        assert_eq!(result.suffix_data, "e30");
+    }
+
+    #[test]
+    pub fn test_did_suffix() {
+        let result = match OperationPayload::did_suffix("did:nodex:test:EiCW6eklabBIrkTMHFpBln7574xmZlbMakWSCNtBWcunDg") {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert_eq!(result, "EiCW6eklabBIrkTMHFpBln7574xmZlbMakWSCNtBWcunDg");
+    }
 }
\ No newline at end of file
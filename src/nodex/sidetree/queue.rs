@@ -0,0 +1,405 @@
+use std::{fs, path::PathBuf, sync::{Arc, Mutex, OnceLock}};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use super::payload::MethodMetadata;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum OperationType {
+    #[serde(rename = "create")]
+    Create,
+
+    #[serde(rename = "update")]
+    Update,
+
+    #[serde(rename = "recover")]
+    Recover,
+
+    #[serde(rename = "deactivate")]
+    Deactivate,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum OperationStatus {
+    // NOTE: waiting to be (re)submitted to the node
+    #[serde(rename = "pending")]
+    Pending,
+
+    // NOTE: accepted by the node, waiting for the resolver to report it
+    #[serde(rename = "submitted")]
+    Submitted,
+
+    #[serde(rename = "published")]
+    Published,
+
+    // NOTE: rejected by the node, it is never retried
+    #[serde(rename = "failed")]
+    Failed,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct QueuedOperation {
+    #[serde(rename = "id")]
+    pub id: String,
+
+    #[serde(rename = "did")]
+    pub did: String,
+
+    #[serde(rename = "type")]
+    pub r#type: OperationType,
+
+    #[serde(rename = "payload")]
+    pub payload: String,
+
+    // NOTE: the update commitment the document has once an update or a recovery is published
+    #[serde(rename = "updateCommitment", skip_serializing_if = "Option::is_none")]
+    pub update_commitment: Option<String>,
+
+    #[serde(rename = "status")]
+    pub status: OperationStatus,
+
+    #[serde(rename = "attempts")]
+    pub attempts: u32,
+
+    #[serde(rename = "lastError", skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+
+    #[serde(rename = "nextAttemptAt")]
+    pub next_attempt_at: i64,
+
+    #[serde(rename = "createdAt")]
+    pub created_at: i64,
+
+    #[serde(rename = "updatedAt")]
+    pub updated_at: i64,
+}
+
+impl QueuedOperation {
+    pub fn is_finished(&self) -> bool {
+        self.status == OperationStatus::Published || self.status == OperationStatus::Failed
+    }
+
+    pub fn is_published(&self, metadata: &MethodMetadata) -> bool {
+        match self.r#type {
+            OperationType::Create => metadata.published,
+            OperationType::Update | OperationType::Recover => metadata.published && metadata.update_commitment == self.update_commitment,
+            OperationType::Deactivate => metadata.is_deactivated(),
+        }
+    }
+}
+
+pub struct OperationQueue {
+    path: Option<PathBuf>,
+    operations: Mutex<Vec<QueuedOperation>>,
+    processing: tokio::sync::Mutex<()>,
+}
+
+pub fn operation_queue() -> Arc<OperationQueue> {
+    static SINGLETON: OnceLock<Arc<OperationQueue>> = OnceLock::new();
+
+    SINGLETON.get_or_init(|| {
+        let path = dirs::home_dir().map(|v| v.join(".nodex").join("run").join("operations.json"));
+
+        Arc::new(OperationQueue::new(path))
+    }).clone()
+}
+
+impl OperationQueue {
+    const BACKOFF_BASE: i64 = 5;
+    const BACKOFF_MAX: i64 = 600;
+    const MAX_FINISHED: usize = 100;
+
+    pub fn new(path: Option<PathBuf>) -> Self {
+        let operations = match &path {
+            Some(path) => Self::read(path),
+            None => vec![],
+        };
+
+        OperationQueue {
+            path,
+            operations: Mutex::new(operations),
+            processing: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    fn read(path: &PathBuf) -> Vec<QueuedOperation> {
+        let content = match fs::read_to_string(path) {
+            Ok(v) => v,
+            Err(_) => return vec![],
+        };
+
+        match serde_json::from_str::<Vec<QueuedOperation>>(&content) {
+            Ok(v) => v,
+            Err(_) => {
+                log::warn!("ignoring broken operation queue: {:?}", path);
+                vec![]
+            }
+        }
+    }
+
+    fn write(&self, operations: &mut Vec<QueuedOperation>) {
+        // NOTE: keep the most recent finished operations for the status API only
+        let finished = operations.iter().filter(|v| v.is_finished()).count();
+
+        if finished > Self::MAX_FINISHED {
+            let mut excess = finished - Self::MAX_FINISHED;
+
+            operations.retain(|v| {
+                if excess > 0 && v.is_finished() {
+                    excess -= 1;
+                    return false
+                }
+                true
+            });
+        }
+
+        let path = match &self.path {
+            Some(v) => v,
+            None => return,
+        };
+
+        let content = match serde_json::to_string(operations) {
+            Ok(v) => v,
+            Err(_) => return,
+        };
+
+        if let Some(dir) = path.parent() {
+            if fs::create_dir_all(dir).is_err() {
+                return
+            }
+        }
+
+        let tmp = path.with_extension("json.tmp");
+
+        if fs::write(&tmp, content).is_err() || fs::rename(&tmp, path).is_err() {
+            log::warn!("unable to write operation queue: {:?}", path);
+        }
+    }
+
+    pub fn backoff(attempts: u32) -> i64 {
+        let exponent = attempts.saturating_sub(1).min(16);
+
+        (Self::BACKOFF_BASE << exponent).min(Self::BACKOFF_MAX)
+    }
+
+    // NOTE: the caller holds the guard while operations are submitted, so an operation is never sent twice at once
+    pub async fn lock(&self) -> tokio::sync::MutexGuard<'_, ()> {
+        self.processing.lock().await
+    }
+
+    pub fn enqueue(&self, did: &str, r#type: OperationType, payload: &str, update_commitment: Option<String>) -> QueuedOperation {
+        let now = Utc::now().timestamp();
+
+        let operation = QueuedOperation {
+            id: cuid::cuid2(),
+            did: did.to_string(),
+            r#type,
+            payload: payload.to_string(),
+            update_commitment,
+            status: OperationStatus::Pending,
+            attempts: 0,
+            last_error: None,
+            next_attempt_at: now,
+            created_at: now,
+            updated_at: now,
+        };
+
+        let mut operations = self.operations.lock().unwrap();
+
+        operations.push(operation.clone());
+        self.write(&mut operations);
+
+        operation
+    }
+
+    pub fn find(&self, id: &str) -> Option<QueuedOperation> {
+        self.operations.lock().unwrap().iter().find(|v| v.id == id).cloned()
+    }
+
+    pub fn list(&self) -> Vec<QueuedOperation> {
+        self.operations.lock().unwrap().clone()
+    }
+
+    pub fn has_unfinished(&self, did: &str) -> bool {
+        self.operations.lock().unwrap().iter().any(|v| v.did == did && !v.is_finished())
+    }
+
+    // NOTE: operations of a DID depend on each other, so only the oldest unfinished one of each DID is processed
+    pub fn due(&self, now: i64) -> Vec<QueuedOperation> {
+        let operations = self.operations.lock().unwrap();
+        let mut dids: Vec<&str> = vec![];
+        let mut due = vec![];
+
+        for operation in operations.iter().filter(|v| !v.is_finished()) {
+            if dids.contains(&operation.did.as_str()) {
+                continue;
+            }
+
+            dids.push(&operation.did);
+
+            if operation.next_attempt_at <= now {
+                due.push(operation.clone());
+            }
+        }
+
+        due
+    }
+
+    fn update(&self, id: &str, status: OperationStatus, last_error: Option<String>) {
+        let now = Utc::now().timestamp();
+        let mut operations = self.operations.lock().unwrap();
+
+        if let Some(operation) = operations.iter_mut().find(|v| v.id == id) {
+            // NOTE: attempts count the tries of the current status
+            if operation.status != status {
+                operation.attempts = 0;
+            }
+
+            operation.attempts += 1;
+            operation.next_attempt_at = now + Self::backoff(operation.attempts);
+            operation.status = status;
+            operation.updated_at = now;
+
+            if last_error.is_some() {
+                operation.last_error = last_error;
+            }
+        }
+
+        self.write(&mut operations);
+    }
+
+    pub fn retry(&self, id: &str, error: &str) {
+        self.update(id, OperationStatus::Pending, Some(error.to_string()))
+    }
+
+    pub fn submitted(&self, id: &str) {
+        self.update(id, OperationStatus::Submitted, None)
+    }
+
+    pub fn published(&self, id: &str) {
+        self.update(id, OperationStatus::Published, None)
+    }
+
+    // NOTE: the later operations of the DID are built on the commitment of the failed one, so they fail too
+    pub fn failed(&self, id: &str, error: &str) -> Vec<QueuedOperation> {
+        self.update(id, OperationStatus::Failed, Some(error.to_string()));
+
+        let now = Utc::now().timestamp();
+        let mut operations = self.operations.lock().unwrap();
+
+        let (index, did) = match operations.iter().position(|v| v.id == id) {
+            Some(v) => (v, operations[v].did.clone()),
+            None => return vec![],
+        };
+
+        let mut blocked = vec![];
+
+        for operation in operations.iter_mut().skip(index + 1).filter(|v| v.did == did && !v.is_finished()) {
+            operation.status = OperationStatus::Failed;
+            operation.last_error = Some(format!("blocked by the failed operation {}", id));
+            operation.updated_at = now;
+
+            blocked.push(operation.clone());
+        }
+
+        self.write(&mut operations);
+
+        blocked
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case(1, 5)]
+    #[case(2, 10)]
+    #[case(5, 80)]
+    #[case(8, 600)]
+    #[case(100, 600)]
+    fn test_backoff(#[case] attempts: u32, #[case] expected: i64) {
+        assert_eq!(OperationQueue::backoff(attempts), expected);
+    }
+
+    #[test]
+    fn test_due() {
+        let queue = OperationQueue::new(None);
+        let now = Utc::now().timestamp();
+
+        let create = queue.enqueue("did:nodex:test:a", OperationType::Create, "{}", None);
+        let update = queue.enqueue("did:nodex:test:a", OperationType::Update, "{}", Some("commitment".to_string()));
+        let other = queue.enqueue("did:nodex:test:b", OperationType::Deactivate, "{}", None);
+
+        let due: Vec<String> = queue.due(now).into_iter().map(|v| v.id).collect();
+        assert_eq!(due, vec![ create.id.clone(), other.id.clone() ]);
+
+        // NOTE: a failed attempt is retried later
+        queue.retry(&other.id, "unreachable");
+        let due: Vec<String> = queue.due(now).into_iter().map(|v| v.id).collect();
+        assert_eq!(due, vec![ create.id.clone() ]);
+        assert_eq!(queue.find(&other.id).unwrap().last_error, Some("unreachable".to_string()));
+
+        // NOTE: the update follows once the create is published
+        queue.submitted(&create.id);
+        assert!(queue.due(now).is_empty());
+        queue.published(&create.id);
+        let due: Vec<String> = queue.due(now).into_iter().map(|v| v.id).collect();
+        assert_eq!(due, vec![ update.id ]);
+    }
+
+    #[test]
+    fn test_failed_blocks_dependents() {
+        let queue = OperationQueue::new(None);
+        let now = Utc::now().timestamp();
+
+        let update = queue.enqueue("did:nodex:test:a", OperationType::Update, "{}", Some("a".to_string()));
+        let next = queue.enqueue("did:nodex:test:a", OperationType::Update, "{}", Some("b".to_string()));
+        let other = queue.enqueue("did:nodex:test:b", OperationType::Update, "{}", Some("c".to_string()));
+
+        let blocked: Vec<String> = queue.failed(&update.id, "400 Bad Request").into_iter().map(|v| v.id).collect();
+
+        assert_eq!(blocked, vec![ next.id.clone() ]);
+        assert_eq!(queue.find(&next.id).unwrap().status, OperationStatus::Failed);
+
+        let due: Vec<String> = queue.due(now).into_iter().map(|v| v.id).collect();
+        assert_eq!(due, vec![ other.id ]);
+    }
+
+    #[test]
+    fn test_is_published() {
+        let queue = OperationQueue::new(None);
+        let operation = queue.enqueue("did:nodex:test:a", OperationType::Update, "{}", Some("next".to_string()));
+
+        let mut metadata = MethodMetadata {
+            published: true,
+            recovery_commitment: None,
+            update_commitment: Some("current".to_string()),
+            deactivated: None,
+        };
+        assert!(!operation.is_published(&metadata));
+
+        metadata.update_commitment = Some("next".to_string());
+        assert!(operation.is_published(&metadata));
+    }
+
+    #[test]
+    fn test_persistence() {
+        let path = std::env::temp_dir().join(format!("nodex-operations-{}.json", std::process::id()));
+
+        let queue = OperationQueue::new(Some(path.clone()));
+        let operation = queue.enqueue("did:nodex:test:a", OperationType::Create, "{}", None);
+        queue.submitted(&operation.id);
+
+        let restored = OperationQueue::new(Some(path.clone()));
+        let _ = fs::remove_file(&path);
+
+        match restored.find(&operation.id) {
+            Some(v) => {
+                assert_eq!(v.status, OperationStatus::Submitted);
+                assert_eq!(v.attempts, 1);
+            },
+            None => panic!()
+        }
+    }
+}
@@ -0,0 +1,831 @@
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+
+use crate::nodex::errors::NodeXError;
+use crate::nodex::cipher::jws::Jws;
+use crate::nodex::keyring::{secp256k1::KeyPairSecp256K1, signing_key::SigningKey};
+use crate::nodex::runtime::multihash::Multihash;
+use crate::nodex::runtime::base64_url::{Base64Url, PaddingType};
+
+use crate::nodex::schema::did_document::{DIDDocument, Service, VerificationMethod, VerificationMethodRef};
+use super::payload::{
+    OperationPayload, DIDResolutionResponse, MethodMetadata, DIDReplaceDeltaObject, DIDReplaceSuffixObject, DIDOperation, DIDPatchAction,
+    DIDCreatePayload, DIDUpdatePayload, DIDRecoverPayload, DIDDeactivatePayload, DIDUpdateDeltaObject,
+    DIDUpdateSignedDataObject, DIDRecoverSignedDataObject, DIDDeactivateSignedDataObject, PublicKeyPayload, ServiceEndpoint,
+};
+
+// NOTE: the state of a DID after its operations are applied in order (Sidetree)
+#[derive(Clone, Debug)]
+struct DIDState {
+    public_keys: Vec<PublicKeyPayload>,
+    services: Vec<ServiceEndpoint>,
+    update_commitment: Option<String>,
+    recovery_commitment: Option<String>,
+    deactivated: bool,
+}
+
+impl DIDState {
+    // NOTE: the delta must be the one the operation committed to
+    fn decode_delta<T: DeserializeOwned>(encoded: &str, delta_hash: &str) -> Option<T> {
+        let bytes = Base64Url::decode_as_bytes(encoded, &PaddingType::NoPadding).ok()?;
+
+        if Base64Url::encode(&Multihash::hash(&bytes), &PaddingType::NoPadding) != delta_hash {
+            return None
+        }
+
+        serde_json::from_slice::<T>(&bytes).ok()
+    }
+
+    // NOTE: the signed data names the key which signed it, the signature is checked by reveal
+    fn signed_data<T: DeserializeOwned>(jws: &str) -> Option<T> {
+        let payload = jws.split('.').nth(1)?;
+        let decoded = Base64Url::decode_as_string(payload, &PaddingType::NoPadding).ok()?;
+
+        serde_json::from_str::<T>(&decoded).ok()
+    }
+
+    // NOTE: the revealed key must match the commitment of the previous operation and must have signed this one
+    fn reveal(key: &KeyPairSecp256K1, reveal_value: &str, commitment: &Option<String>, signed_data: &str) -> bool {
+        let key_json = json!(key).to_string();
+
+        let revealed = Multihash::canonicalize_then_hash_then_encode(key_json.as_bytes()).ok();
+        let committed = Multihash::canonicalize_then_double_hash_then_encode(key_json.as_bytes()).ok();
+
+        if revealed.as_deref() != Some(reveal_value) || committed.is_none() || &committed != commitment {
+            return false
+        }
+
+        match SigningKey::from_jwk(key) {
+            Ok(v) => Jws::decode_compact(signed_data, &v).is_ok(),
+            Err(_) => false,
+        }
+    }
+
+    fn replace(&mut self, delta: &DIDReplaceDeltaObject) -> bool {
+        self.public_keys = vec![];
+        self.services = vec![];
+
+        for patch in delta.patches.iter() {
+            if patch.action != "replace" {
+                return false
+            }
+
+            self.public_keys.extend(patch.document.public_keys.iter().cloned());
+            self.services.extend(patch.document.service_endpoints.iter().cloned());
+        }
+
+        true
+    }
+
+    fn create(suffix: &str, operation: &DIDCreatePayload) -> Option<Self> {
+        // NOTE: the suffix must be derived from the suffix data
+        match OperationPayload::did_unique_suffix(&operation.suffix_data) {
+            Ok(v) if v == suffix => {},
+            _ => return None,
+        };
+
+        let suffix_data = Base64Url::decode_as_string(&operation.suffix_data, &PaddingType::NoPadding).ok()
+            .and_then(|v| serde_json::from_str::<DIDReplaceSuffixObject>(&v).ok())?;
+        let delta = Self::decode_delta::<DIDReplaceDeltaObject>(&operation.delta, &suffix_data.delta_hash)?;
+
+        let mut state = DIDState {
+            public_keys: vec![],
+            services: vec![],
+            update_commitment: Some(delta.update_commitment.clone()),
+            recovery_commitment: Some(suffix_data.recovery_commitment),
+            deactivated: false,
+        };
+
+        if !state.replace(&delta) {
+            return None
+        }
+
+        Some(state)
+    }
+
+    fn update(&self, suffix: &str, operation: &DIDUpdatePayload) -> Option<Self> {
+        if self.deactivated || operation.did_suffix != suffix {
+            return None
+        }
+
+        let signed_data = Self::signed_data::<DIDUpdateSignedDataObject>(&operation.signed_data)?;
+
+        if !Self::reveal(&signed_data.update_key, &operation.reveal_value, &self.update_commitment, &operation.signed_data) {
+            return None
+        }
+
+        let delta = Self::decode_delta::<DIDUpdateDeltaObject>(&operation.delta, &signed_data.delta_hash)?;
+
+        let mut state = self.clone();
+
+        for patch in delta.patches.iter() {
+            match patch {
+                DIDPatchAction::AddPublicKeys(v) => {
+                    for key in v.public_keys.iter() {
+                        state.public_keys.retain(|item| item.id != key.id);
+                        state.public_keys.push(key.clone());
+                    }
+                },
+                DIDPatchAction::RemovePublicKeys(v) => state.public_keys.retain(|item| !v.ids.contains(&item.id)),
+                DIDPatchAction::AddServices(v) => {
+                    for service in v.services.iter() {
+                        state.services.retain(|item| item.id != service.id);
+                        state.services.push(service.clone());
+                    }
+                },
+                DIDPatchAction::RemoveServices(v) => state.services.retain(|item| !v.ids.contains(&item.id)),
+            }
+        }
+
+        state.update_commitment = Some(delta.update_commitment);
+
+        Some(state)
+    }
+
+    fn recover(&self, suffix: &str, operation: &DIDRecoverPayload) -> Option<Self> {
+        if self.deactivated || operation.did_suffix != suffix {
+            return None
+        }
+
+        let signed_data = Self::signed_data::<DIDRecoverSignedDataObject>(&operation.signed_data)?;
+
+        if !Self::reveal(&signed_data.recovery_key, &operation.reveal_value, &self.recovery_commitment, &operation.signed_data) {
+            return None
+        }
+
+        let delta = Self::decode_delta::<DIDReplaceDeltaObject>(&operation.delta, &signed_data.delta_hash)?;
+
+        let mut state = self.clone();
+
+        if !state.replace(&delta) {
+            return None
+        }
+
+        state.update_commitment = Some(delta.update_commitment);
+        state.recovery_commitment = Some(signed_data.recovery_commitment);
+
+        Some(state)
+    }
+
+    fn deactivate(&self, suffix: &str, operation: &DIDDeactivatePayload) -> Option<Self> {
+        if self.deactivated || operation.did_suffix != suffix {
+            return None
+        }
+
+        let signed_data = Self::signed_data::<DIDDeactivateSignedDataObject>(&operation.signed_data)?;
+
+        if signed_data.did_suffix != suffix || !Self::reveal(&signed_data.recovery_key, &operation.reveal_value, &self.recovery_commitment, &operation.signed_data) {
+            return None
+        }
+
+        Some(DIDState {
+            public_keys: vec![],
+            services: vec![],
+            update_commitment: None,
+            recovery_commitment: None,
+            deactivated: true,
+        })
+    }
+
+    // NOTE: every state the DID has been in, invalid operations are ignored as the node ignores them
+    fn replay(suffix: &str, operations: &[DIDOperation]) -> Vec<Self> {
+        let mut states: Vec<Self> = vec![];
+
+        for operation in operations.iter() {
+            let next = match (states.last(), operation) {
+                (None, DIDOperation::Create(v)) => Self::create(suffix, v),
+                (Some(state), DIDOperation::Update(v)) => state.update(suffix, v),
+                (Some(state), DIDOperation::Recover(v)) => state.recover(suffix, v),
+                (Some(state), DIDOperation::Deactivate(v)) => state.deactivate(suffix, v),
+                _ => None,
+            };
+
+            if let Some(v) = next {
+                states.push(v);
+            }
+        }
+
+        states
+    }
+
+    fn to_resolution_response(&self, did: &str, published: bool) -> DIDResolutionResponse {
+        let mut public_key: Vec<VerificationMethod> = vec![];
+        let mut authentication: Vec<VerificationMethodRef> = vec![];
+        let mut assertion_method: Vec<VerificationMethodRef> = vec![];
+        let mut key_agreement: Vec<VerificationMethodRef> = vec![];
+
+        for key in self.public_keys.iter() {
+            let id = format!("#{}", key.id);
+
+            if key.purpose.iter().any(|v| v == "auth") {
+                authentication.push(VerificationMethodRef::Reference(id.clone()));
+            }
+            if key.purpose.iter().any(|v| v == "assertionMethod") {
+                assertion_method.push(VerificationMethodRef::Reference(id.clone()));
+            }
+            if key.purpose.iter().any(|v| v == "keyAgreement") {
+                key_agreement.push(VerificationMethodRef::Reference(id.clone()));
+            }
+
+            public_key.push(VerificationMethod::new(&id, did, &key.r#type, &key.jwk));
+        }
+
+        let service: Vec<Service> = self.services.iter().map(|v| v.to_service()).collect();
+
+        DIDResolutionResponse {
+            context: "https://w3id.org/did-resolution/v1".to_string(),
+            did_document: DIDDocument {
+                public_key: Some(public_key),
+                authentication: Some(authentication),
+                assertion_method: if assertion_method.is_empty() { None } else { Some(assertion_method) },
+                key_agreement: if key_agreement.is_empty() { None } else { Some(key_agreement) },
+                service: if service.is_empty() { None } else { Some(service) },
+                ..DIDDocument::new(did)
+            },
+            method_metadata: MethodMetadata {
+                published,
+                recovery_commitment: self.recovery_commitment.clone(),
+                update_commitment: self.update_commitment.clone(),
+                deactivated: if self.deactivated { Some(true) } else { None },
+            },
+        }
+    }
+}
+
+pub struct LongFormResolver {}
+
+impl LongFormResolver {
+    // NOTE: resolves the initial state of a long-form DID without contacting a node
+    pub fn resolve(did: &str) -> Result<DIDResolutionResponse, NodeXError> {
+        let state = match OperationPayload::did_long_form_state(did) {
+            Some(v) => v,
+            None => return Err(NodeXError{})
+        };
+        let suffix = match OperationPayload::did_suffix(did) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        let operation = DIDCreatePayload {
+            r#type: "create".to_string(),
+            delta: state.delta,
+            suffix_data: state.suffix_data,
+        };
+
+        match DIDState::create(&suffix, &operation) {
+            Some(v) => Ok(v.to_resolution_response(did, false)),
+            None => Err(NodeXError{})
+        }
+    }
+}
+
+pub struct ResolutionVerifier {}
+
+impl ResolutionVerifier {
+    fn fragment(id: &str) -> String {
+        match id.rsplit_once('#') {
+            Some((_, v)) => v.to_string(),
+            None => id.to_string(),
+        }
+    }
+
+    fn public_key_fingerprints(document: &DIDDocument) -> Vec<String> {
+        let mut fingerprints: Vec<String> = document.verification_methods().iter().map(|key| {
+            format!("{}|{}|{}", Self::fragment(&key.id), key.r#type, key.public_key_jwk.clone().map(|v| v.to_string()).unwrap_or_default())
+        }).collect();
+
+        fingerprints.sort();
+        fingerprints
+    }
+
+    fn reference_fingerprints(refs: &Option<Vec<VerificationMethodRef>>) -> Vec<String> {
+        refs.iter().flatten().map(|item| match item {
+            VerificationMethodRef::Reference(id) => Self::fragment(id),
+            VerificationMethodRef::Embedded(v) => Self::fragment(&v.id),
+        }).collect()
+    }
+
+    fn service_fingerprints(document: &DIDDocument) -> Vec<String> {
+        document.service.iter().flatten().map(|service| {
+            format!("{}|{}|{}", Self::fragment(&service.id), json!(service.r#type), json!(service.service_endpoint))
+        }).collect()
+    }
+
+    fn is_subset(claimed: &[String], expected: &[String]) -> bool {
+        claimed.iter().all(|v| expected.contains(v))
+    }
+
+    // NOTE: the response may not claim keys, relationships or services the replayed state does not have
+    fn matches(response: &DIDResolutionResponse, expected: &DIDResolutionResponse) -> bool {
+        if response.method_metadata.is_deactivated() != expected.method_metadata.is_deactivated() {
+            return false
+        }
+        if expected.method_metadata.is_deactivated() {
+            return true
+        }
+
+        let (claimed, document) = (&response.did_document, &expected.did_document);
+
+        response.method_metadata.update_commitment == expected.method_metadata.update_commitment &&
+        response.method_metadata.recovery_commitment == expected.method_metadata.recovery_commitment &&
+        Self::public_key_fingerprints(claimed) == Self::public_key_fingerprints(document) &&
+        Self::is_subset(&Self::reference_fingerprints(&claimed.authentication), &Self::reference_fingerprints(&document.authentication)) &&
+        Self::is_subset(&Self::reference_fingerprints(&claimed.assertion_method), &Self::reference_fingerprints(&document.assertion_method)) &&
+        Self::is_subset(&Self::reference_fingerprints(&claimed.key_agreement), &Self::reference_fingerprints(&document.key_agreement)) &&
+        Self::is_subset(&Self::service_fingerprints(claimed), &Self::service_fingerprints(document))
+    }
+
+    fn is_same_did(did: &str, response: &DIDResolutionResponse) -> bool {
+        match (OperationPayload::did_short_form(did), OperationPayload::did_short_form(&response.did_document.id)) {
+            (Ok(u), Ok(v)) => u == v,
+            _ => false,
+        }
+    }
+
+    // NOTE: the initial state is recomputed from the create operation data a long-form DID carries, the suffix must be
+    //       derived from suffix_data and the delta must be the one it committed to. A short-form DID carries none
+    fn verify_create_operation(did: &str, response: &DIDResolutionResponse) -> Result<(), NodeXError> {
+        if !Self::is_same_did(did, response) {
+            return Err(NodeXError{})
+        }
+
+        let state = match OperationPayload::did_long_form_state(did) {
+            Some(v) => v,
+            None => return Ok(()),
+        };
+
+        let suffix = match OperationPayload::did_suffix(did) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        let initial = match DIDState::create(&suffix, &DIDCreatePayload {
+            r#type: "create".to_string(),
+            delta: state.delta,
+            suffix_data: state.suffix_data,
+        }) {
+            Some(v) => v.to_resolution_response(did, response.method_metadata.published),
+            None => return Err(NodeXError{})
+        };
+
+        let is_initial_state =
+            response.method_metadata.update_commitment == initial.method_metadata.update_commitment &&
+            response.method_metadata.recovery_commitment == initial.method_metadata.recovery_commitment;
+
+        // NOTE: an unpublished DID can only be in its initial state
+        if !response.method_metadata.published && !is_initial_state {
+            return Err(NodeXError{})
+        }
+
+        // NOTE: later states depend on the operation history and cannot be derived from the create operation
+        if !is_initial_state {
+            log::warn!("{} has been updated since creation, skipping key check", &did);
+            return Ok(())
+        }
+
+        if Self::matches(response, &initial) {
+            Ok(())
+        } else {
+            Err(NodeXError{})
+        }
+    }
+
+    // NOTE: replays the operations of the DID the node returned
+    fn replay(did: &str, response: &DIDResolutionResponse, operations: &[Value]) -> Result<Vec<DIDResolutionResponse>, NodeXError> {
+        let suffix = match OperationPayload::did_suffix(did) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        let operations: Vec<DIDOperation> = operations.iter().filter_map(DIDOperation::from_value).collect();
+        let states = DIDState::replay(&suffix, &operations);
+
+        // NOTE: without a valid create operation there is nothing to check the response against
+        if states.is_empty() {
+            return Err(NodeXError{})
+        }
+
+        // NOTE: an unpublished DID can only be in its initial state
+        let published = response.method_metadata.published;
+        let states = if published { &states[..] } else { &states[..1] };
+
+        Ok(states.iter().map(|v| v.to_resolution_response(did, published)).collect())
+    }
+
+    // NOTE: rejects responses which contradict the create operation data, and the operation history of the DID when
+    //       the node returns it (None when it does not serve the operations)
+    pub fn verify(did: &str, response: &DIDResolutionResponse, operations: Option<&[Value]>) -> Result<(), NodeXError> {
+        if Self::verify_create_operation(did, response).is_err() {
+            return Err(NodeXError{})
+        }
+
+        let operations = match operations {
+            Some(v) if !v.is_empty() => v,
+            _ => return Ok(()),
+        };
+
+        let states = match Self::replay(did, response, operations) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        match states.last() {
+            Some(v) if Self::matches(response, v) => Ok(()),
+            _ => Err(NodeXError{})
+        }
+    }
+
+    // NOTE: a historical document must match one of the states the DID has been in
+    pub fn verify_version(did: &str, response: &DIDResolutionResponse, operations: Option<&[Value]>) -> Result<(), NodeXError> {
+        if Self::verify_create_operation(did, response).is_err() {
+            return Err(NodeXError{})
+        }
+
+        let operations = match operations {
+            Some(v) if !v.is_empty() => v,
+            _ => return Ok(()),
+        };
+
+        let states = match Self::replay(did, response, operations) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{})
+        };
+
+        if states.iter().any(|v| Self::matches(response, v)) {
+            Ok(())
+        } else {
+            Err(NodeXError{})
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::nodex::keyring::{self, mnemonic::MnemonicKeyring, secp256k1::Secp256k1};
+    use crate::nodex::sidetree::payload::{DIDCreateRequest, DIDUpdateRequest, DIDAddPublicKeysAction, CommitmentKeys, ServiceEndpoint};
+    use crate::nodex::schema::did_document::{ServiceEndpointValue, VerificationRelationship};
+
+    use super::*;
+
+    fn create_request() -> DIDCreateRequest {
+        let keyring = match keyring::mnemonic::MnemonicKeyring::create_keyring() {
+            Ok(v) => v,
+            Err(_) => panic!(),
+        };
+
+        create_request_with(&keyring)
+    }
+
+    fn create_request_with(keyring: &MnemonicKeyring) -> DIDCreateRequest {
+        let public = match keyring.get_sign_key_pair().to_public_key("signingKey", &["auth", "general", "assertionMethod"]) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+        let update = match keyring.get_update_key_pair().to_jwk(false) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+        let recovery = match keyring.get_recovery_key_pair().to_jwk(false) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        DIDCreateRequest {
+            public_keys: vec![ public ],
+            commitment_keys: CommitmentKeys {
+                recovery,
+                update,
+            },
+            service_endpoints: vec![],
+        }
+    }
+
+    #[test]
+    pub fn test_resolve() {
+        let request = create_request();
+
+        let did = match OperationPayload::did_long_form("did:nodex:test", &request) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        let result = match LongFormResolver::resolve(&did) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert_eq!(result.did_document.id, did);
+        assert!(!result.method_metadata.published);
+        assert!(result.method_metadata.update_commitment.is_some());
+        assert!(result.method_metadata.recovery_commitment.is_some());
+
+        let public_keys = match result.did_document.public_key {
+            Some(v) => v,
+            None => panic!()
+        };
+
+        assert_eq!(public_keys.len(), 1);
+        assert_eq!(public_keys[0].id, "#signingKey");
+        let jwk = match public_keys[0].jwk() {
+            Some(v) => v,
+            None => panic!()
+        };
+
+        assert_eq!(jwk.x, request.public_keys[0].jwk.x);
+        assert_eq!(result.did_document.authentication, Some(vec![ VerificationMethodRef::Reference("#signingKey".to_string()) ]));
+        assert_eq!(result.did_document.assertion_method, Some(vec![ VerificationMethodRef::Reference("#signingKey".to_string()) ]));
+    }
+
+    #[test]
+    pub fn test_resolve_with_services() {
+        let mut request = create_request();
+
+        request.service_endpoints = vec![ ServiceEndpoint {
+            id: "didcomm".to_string(),
+            r#type: "DIDCommMessaging".to_string(),
+            service_endpoint: ServiceEndpointValue::Uri("https://example.com/didcomm".to_string()),
+            description: None,
+        } ];
+
+        let did = match OperationPayload::did_long_form("did:nodex:test", &request) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        let result = match LongFormResolver::resolve(&did) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        let service = match result.did_document.service {
+            Some(v) => v,
+            None => panic!()
+        };
+
+        assert_eq!(service.len(), 1);
+        assert_eq!(service[0].id, "#didcomm");
+        assert_eq!(service[0].service_endpoint, ServiceEndpointValue::Uri("https://example.com/didcomm".to_string()));
+    }
+
+    #[test]
+    pub fn test_resolve_with_key_agreement() {
+        let keyring = match keyring::mnemonic::MnemonicKeyring::create_keyring() {
+            Ok(v) => v,
+            Err(_) => panic!(),
+        };
+
+        let mut request = create_request();
+
+        request.public_keys.push(match keyring.get_encrypt_key_pair().to_public_key("encryptionKey", &["keyAgreement"]) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        });
+
+        let did = match OperationPayload::did_long_form("did:nodex:test", &request) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        let result = match LongFormResolver::resolve(&did) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        let document = result.did_document;
+
+        assert_eq!(document.key_agreement, Some(vec![ VerificationMethodRef::Reference("#encryptionKey".to_string()) ]));
+        assert_eq!(document.authentication, Some(vec![ VerificationMethodRef::Reference("#signingKey".to_string()) ]));
+
+        let key_agreement = document.verification_methods_for(VerificationRelationship::KeyAgreement);
+
+        assert_eq!(key_agreement.len(), 1);
+        assert_eq!(key_agreement[0].jwk().map(|v| v.crv), Some("X25519".to_string()));
+    }
+
+    #[test]
+    pub fn test_resolve_with_tampered_suffix() {
+        let did = match OperationPayload::did_long_form("did:nodex:test", &create_request()) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+        let other = match OperationPayload::did_long_form("did:nodex:test", &create_request()) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        let (_, state) = match other.rsplit_once(':') {
+            Some(v) => v,
+            None => panic!()
+        };
+        let (prefix, _) = match did.rsplit_once(':') {
+            Some(v) => v,
+            None => panic!()
+        };
+
+        assert!(LongFormResolver::resolve(&format!("{}:{}", prefix, state)).is_err());
+    }
+
+    #[test]
+    pub fn test_resolve_short_form() {
+        assert!(LongFormResolver::resolve("did:nodex:test:EiCW6eklabBIrkTMHFpBln7574xmZlbMakWSCNtBWcunDg").is_err());
+    }
+
+    #[test]
+    pub fn test_verify() {
+        let did = match OperationPayload::did_long_form("did:nodex:test", &create_request()) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        let mut response = match LongFormResolver::resolve(&did) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert!(ResolutionVerifier::verify(&did, &response, None).is_ok());
+
+        // NOTE: published under the short form
+        response.did_document.id = match OperationPayload::did_short_form(&did) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+        response.method_metadata.published = true;
+
+        assert!(ResolutionVerifier::verify(&did, &response, None).is_ok());
+    }
+
+    #[test]
+    pub fn test_verify_with_substituted_key() {
+        let did = match OperationPayload::did_long_form("did:nodex:test", &create_request()) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+        let other = match OperationPayload::did_long_form("did:nodex:test", &create_request()) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        let mut response = match LongFormResolver::resolve(&did) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+        let substituted = match LongFormResolver::resolve(&other) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        response.did_document.public_key = substituted.did_document.public_key;
+
+        assert!(ResolutionVerifier::verify(&did, &response, None).is_err());
+    }
+
+    #[test]
+    pub fn test_verify_with_other_did() {
+        let did = match OperationPayload::did_long_form("did:nodex:test", &create_request()) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+        let other = match OperationPayload::did_long_form("did:nodex:test", &create_request()) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        let response = match LongFormResolver::resolve(&other) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert!(ResolutionVerifier::verify(&did, &response, None).is_err());
+        assert!(ResolutionVerifier::verify("did:nodex:test:EiCW6eklabBIrkTMHFpBln7574xmZlbMakWSCNtBWcunDg", &response, None).is_err());
+    }
+
+    // NOTE: an update adding the key, and the commitment to the next update key
+    fn update_operation(did: &str, update_key: &Secp256k1, public: &PublicKeyPayload) -> (Value, String) {
+        let next = match keyring::mnemonic::MnemonicKeyring::create_keyring() {
+            Ok(v) => match v.get_update_key_pair().to_jwk(false) {
+                Ok(v) => v,
+                Err(_) => panic!()
+            },
+            Err(_) => panic!(),
+        };
+        let did_suffix = match OperationPayload::did_suffix(did) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        let payload = match OperationPayload::did_update_payload(&DIDUpdateRequest {
+            did_suffix,
+            patches: vec![ DIDPatchAction::AddPublicKeys(DIDAddPublicKeysAction { public_keys: vec![ public.clone() ] }) ],
+            update_key: update_key.clone(),
+            next_update_key: next.clone(),
+        }) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+        let commitment = match OperationPayload::commitment(&next) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        match serde_json::from_str::<Value>(&payload) {
+            Ok(v) => (v, commitment),
+            Err(_) => panic!()
+        }
+    }
+
+    #[test]
+    pub fn test_verify_with_operations() {
+        let keyring = match keyring::mnemonic::MnemonicKeyring::create_keyring() {
+            Ok(v) => v,
+            Err(_) => panic!(),
+        };
+        let request = create_request_with(&keyring);
+
+        let long_form = match OperationPayload::did_long_form("did:nodex:test", &request) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+        let did = match OperationPayload::did_short_form(&long_form) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+        let create = match OperationPayload::did_create_payload(&request) {
+            Ok(v) => match serde_json::from_str::<Value>(&v) {
+                Ok(v) => v,
+                Err(_) => panic!()
+            },
+            Err(_) => panic!()
+        };
+
+        let public = match keyring.get_encrypt_key_pair().to_public_key("encryptionKey", &["keyAgreement"]) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+        let (update, commitment) = update_operation(&did, &keyring.get_update_key_pair(), &public);
+
+        let mut initial = match LongFormResolver::resolve(&long_form) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+        initial.did_document.id = did.clone();
+        initial.method_metadata.published = true;
+
+        let mut updated = initial.clone();
+        updated.method_metadata.update_commitment = Some(commitment);
+        updated.did_document.public_key = updated.did_document.public_key.map(|mut v| {
+            v.push(VerificationMethod::new("#encryptionKey", &did, &public.r#type, &public.jwk));
+            v
+        });
+        updated.did_document.key_agreement = Some(vec![ VerificationMethodRef::Reference("#encryptionKey".to_string()) ]);
+
+        assert!(ResolutionVerifier::verify(&did, &updated, Some(&[ create.clone(), update.clone() ])).is_ok());
+        assert!(ResolutionVerifier::verify(&did, &initial, Some(&[ create.clone(), update.clone() ])).is_err());
+        assert!(ResolutionVerifier::verify_version(&did, &initial, Some(&[ create.clone(), update.clone() ])).is_ok());
+
+        // NOTE: the updated document cannot be verified without the update operation
+        assert!(ResolutionVerifier::verify(&did, &updated, Some(std::slice::from_ref(&create))).is_err());
+
+        // NOTE: a node which does not serve the operations leaves the create operation data of the long form to check
+        assert!(ResolutionVerifier::verify(&did, &updated, None).is_ok());
+        assert!(ResolutionVerifier::verify(&long_form, &updated, None).is_ok());
+        assert!(ResolutionVerifier::verify(&long_form, &initial, None).is_ok());
+
+        let mut substituted = initial.clone();
+        substituted.did_document.public_key = updated.did_document.public_key.clone();
+        assert!(ResolutionVerifier::verify(&long_form, &substituted, None).is_err());
+
+        // NOTE: an update revealing a key which was not committed to is ignored
+        let other = match keyring::mnemonic::MnemonicKeyring::create_keyring() {
+            Ok(v) => v,
+            Err(_) => panic!(),
+        };
+        let (tampered, _) = update_operation(&did, &other.get_update_key_pair(), &public);
+
+        assert!(ResolutionVerifier::verify(&did, &updated, Some(&[ create.clone(), tampered.clone() ])).is_err());
+        assert!(ResolutionVerifier::verify(&did, &initial, Some(&[ create, tampered ])).is_ok());
+    }
+
+    #[test]
+    pub fn test_verify_short_form_without_operations() {
+        let did = match OperationPayload::did_long_form("did:nodex:test", &create_request()) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        let mut response = match LongFormResolver::resolve(&did) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+        response.did_document.id = match OperationPayload::did_short_form(&did) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+        response.method_metadata.published = true;
+
+        // NOTE: nothing to check a short-form DID against, the response is accepted
+        assert!(ResolutionVerifier::verify(&response.did_document.id.clone(), &response, None).is_ok());
+
+        // NOTE: an empty history does not verify anything either
+        assert!(ResolutionVerifier::verify(&response.did_document.id.clone(), &response, Some(&[])).is_ok());
+    }
+}
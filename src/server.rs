@@ -1,15 +1,43 @@
-use actix_web::{ middleware, HttpServer, App, web, dev::Server };
+use actix_web::{ middleware, HttpServer, App, web, dev::{Server, Service}, http::header::HeaderMap };
 use tokio::sync::Mutex as TokioMutex;
 use tokio::sync::mpsc::Sender;
 use std::path::PathBuf;
 
+use crate::config::{with_profile, is_valid_profile_name, DEFAULT_PROFILE};
 use crate::controllers;
+use crate::app_config;
 use crate::handlers::Command;
 
 pub struct Context {
     pub sender: TokioMutex<Sender<Command>>
 }
 
+const PROFILE_HEADER: &str = "x-nodex-profile";
+
+// NOTE: the profile is picked by "/profiles/{ profile }/internal/..." or by the "X-Nodex-Profile" header
+fn request_profile(path: &str, headers: &HeaderMap) -> Option<String> {
+    if let Some(v) = path.strip_prefix("/profiles/") {
+        return v.split('/').next().map(|v| v.to_string())
+    }
+
+    headers.get(PROFILE_HEADER).map(|v| v.to_str().unwrap_or_default().to_string())
+}
+
+fn internal_routes(cfg: &mut web::ServiceConfig) {
+    cfg
+        .route("/verifiable-credentials", web::post().to(controllers::internal::did_generate_vc::handler))
+        .route("/verifiable-credentials/verify", web::post().to(controllers::internal::did_verify_vc::handler))
+        .route("/verifiable-presentations", web::post().to(controllers::internal::did_generate_vp::handler))
+        .route("/verifiable-presentations/verify", web::post().to(controllers::internal::did_verify_vp::handler))
+
+        .route("/didcomm/plaintext-messages", web::post().to(controllers::internal::didcomm_generate_plaintext::handler))
+        .route("/didcomm/plaintext-messages/verify", web::post().to(controllers::internal::didcomm_verify_plaintext::handler))
+        .route("/didcomm/signed-messages", web::post().to(controllers::internal::didcomm_generate_signed::handler))
+        .route("/didcomm/signed-messages/verify", web::post().to(controllers::internal::didcomm_verify_signed::handler))
+        .route("/didcomm/encrypted-messages", web::post().to(controllers::internal::didcomm_generate_encrypted::handler))
+        .route("/didcomm/encrypted-messages/verify", web::post().to(controllers::internal::didcomm_verify_encrypted::handler));
+}
+
 pub fn new_server(sock_path: &PathBuf, sender: Sender<Command>) -> Server {
     let context = web::Data::new(Context {
         sender: TokioMutex::new(sender),
@@ -21,24 +49,47 @@ pub fn new_server(sock_path: &PathBuf, sender: Sender<Command>) -> Server {
             .wrap(middleware::Compress::default())
             .wrap(middleware::Logger::default())
             .app_data(context.clone())
+            .wrap_fn(|req, srv| {
+                let profile = request_profile(req.path(), req.headers());
+
+                // NOTE: only the profiles configured in config.json are served, a request never creates one
+                let res = match &profile {
+                    Some(v) if !is_valid_profile_name(v) => None,
+                    Some(v) if !app_config().inner.lock().unwrap().has_profile(v) => None,
+                    _ => Some(srv.call(req)),
+                };
+
+                async move {
+                    match res {
+                        Some(res) => with_profile(&profile.unwrap_or_else(|| DEFAULT_PROFILE.to_string()), res).await,
+                        None => Err(actix_web::error::ErrorBadRequest("invalid profile")),
+                    }
+                }
+            })
 
             // NOTE: Public Routes
             .route("/identifiers", web::post().to(controllers::public::nodex_create_identifier::handler))
+            .route("/identifiers/services", web::get().to(controllers::public::nodex_find_services::handler))
+            .route("/identifiers/services", web::post().to(controllers::public::nodex_add_services::handler))
+            .route("/identifiers/services/{id}", web::put().to(controllers::public::nodex_update_service::handler))
+            .route("/identifiers/services/{id}", web::delete().to(controllers::public::nodex_remove_service::handler))
             .route("/identifiers/{did}", web::get().to(controllers::public::nodex_find_identifier::handler))
+            .route("/identifiers/{did}", web::patch().to(controllers::public::nodex_update_identifier::handler))
+            .route("/identifiers/{did}", web::delete().to(controllers::public::nodex_deactivate_identifier::handler))
+            .route("/identifiers/{did_url:.*}/dereference", web::get().to(controllers::public::nodex_dereference_identifier::handler))
+            .route("/identifiers/{did}/recover", web::post().to(controllers::public::nodex_recover_identifier::handler))
+            .route("/operations", web::get().to(controllers::public::nodex_find_operations::handler))
+            .route("/operations/{id}", web::get().to(controllers::public::nodex_find_operation::handler))
             .route("/transfer", web::post().to(controllers::public::nodex_transfer::handler))
 
             // NOTE: Internal (Private) Routes
-            .route("/internal/verifiable-credentials", web::post().to(controllers::internal::did_generate_vc::handler))
-            .route("/internal/verifiable-credentials/verify", web::post().to(controllers::internal::did_verify_vc::handler))
-            .route("/internal/verifiable-presentations", web::post().to(controllers::internal::did_generate_vp::handler))
-            .route("/internal/verifiable-presentations/verify", web::post().to(controllers::internal::did_verify_vp::handler))
-
-            .route("/internal/didcomm/plaintext-messages", web::post().to(controllers::internal::didcomm_generate_plaintext::handler))
-            .route("/internal/didcomm/plaintext-messages/verify", web::post().to(controllers::internal::didcomm_verify_plaintext::handler))
-            .route("/internal/didcomm/signed-messages", web::post().to(controllers::internal::didcomm_generate_signed::handler))
-            .route("/internal/didcomm/signed-messages/verify", web::post().to(controllers::internal::didcomm_verify_signed::handler))
-            .route("/internal/didcomm/encrypted-messages", web::post().to(controllers::internal::didcomm_generate_encrypted::handler))
-            .route("/internal/didcomm/encrypted-messages/verify", web::post().to(controllers::internal::didcomm_verify_encrypted::handler))
+            .service(web::scope("/internal").configure(internal_routes))
+            .service(web::scope("/profiles/{profile}/internal").configure(internal_routes))
+
+            // NOTE: Admin Routes
+            .route("/admin/keys/rotate", web::post().to(controllers::admin::keys_rotate::handler))
+            .route("/admin/identifiers/restore", web::post().to(controllers::admin::identifiers_restore::handler))
+            .route("/admin/extensions", web::get().to(controllers::admin::extensions::handler))
     })
     .bind_uds(&sock_path)
     .unwrap()
@@ -1,6 +1,6 @@
 use chrono::Utc;
 use serde_json::{Value, json};
-use crate::{nodex::{errors::NodeXError, keyring::{self}, schema::general::{GeneralVcDataModel, Issuer, CredentialSubject}, cipher::credential_signer::{CredentialSigner, CredentialSignerSuite}}};
+use crate::nodex::{errors::NodeXError, resolver::{DidResolver, cache::did_resolver, dereferencer::DocumentVersion}, keyring::{self}, schema::{general::{GeneralVcDataModel, Issuer, CredentialSubject}, did_document::VerificationRelationship}, cipher::credential_signer::{CredentialSigner, CredentialSignerSuite}};
 
 pub struct DIDVCService {
 }
@@ -36,7 +36,7 @@ impl DIDVCService {
 
         let signed = match CredentialSigner::sign(&model, &CredentialSignerSuite {
             did: Some(did),
-            key_id: Some("signingKey".to_string()),
+            key_id: Some(keyring.get_sign_key_id()),
             context: keyring.get_sign_key_pair(),
         }) {
             Ok(v) => v,
@@ -47,47 +47,60 @@ impl DIDVCService {
     }
 
     pub async fn verify(message: &Value) -> Result<Value, NodeXError> {
-        let service = crate::services::nodex::NodeX::new();
+        let resolver = did_resolver();
 
         let model = match serde_json::from_value::<GeneralVcDataModel>(message.clone()) {
             Ok(v) => v,
             Err(_) => return Err(NodeXError{}),
         };
 
-        let did_document = match service.find_identifier(&model.issuer.id).await {
-            Ok(v) => v,
-            Err(_) => return Err(NodeXError{}),
-        };
-        let public_keys = match did_document.did_document.public_key {
+        let proof = match &model.proof {
             Some(v) => v,
             None => return Err(NodeXError{}),
         };
 
-        // FIXME: workaround
-        if public_keys.len() != 1 {
+        // NOTE: the proof must be made by a key of the issuer
+        let key_id = match proof.verification_method.split_once('#') {
+            Some((did, key_id)) if did == model.issuer.id => key_id.to_string(),
+            _ => return Err(NodeXError{}),
+        };
+
+        // NOTE: the proof purpose is set by the signer, credentials are only asserted by assertion methods
+        if proof.proof_purpose != "assertionMethod" {
             return Err(NodeXError{})
         }
 
-        let public_key = public_keys[0].clone();
+        let mut contexts = vec![];
 
-        let context = match keyring::secp256k1::Secp256k1::from_jwk(&public_key.public_key_jwk) {
-            Ok(v) => v,
-            Err(_) => return Err(NodeXError{})
-        };
+        if let Ok(public_key) = resolver.resolve_verification_method(&proof.verification_method, VerificationRelationship::AssertionMethod).await {
+            if let Some(context) = public_key.signing_key() {
+                contexts.push(context);
+            }
+        }
 
-        let (verified_model, verified) = match CredentialSigner::verify(&model, &CredentialSignerSuite {
-            did: None,
-            key_id: None,
-            context,
-        }) {
-            Ok(v) => v,
-            Err(_) => return Err(NodeXError{})
-        };
+        // NOTE: credentials signed before a key rotation are verified with the document published at the time of signing
+        if contexts.is_empty() {
+            if let Some(context) = Self::historical_key(resolver.as_ref(), &proof.verification_method, &proof.created).await {
+                contexts.push(context);
+            }
+        }
 
-        if !verified {
-            return Err(NodeXError{})
+        for context in contexts {
+            if let Ok((verified_model, true)) = CredentialSigner::verify(&model, &CredentialSignerSuite {
+                did: Some(model.issuer.id.clone()),
+                key_id: Some(key_id.clone()),
+                context,
+            }) {
+                return Ok(verified_model)
+            }
         }
 
-        Ok(verified_model)
+        Err(NodeXError{})
     }
-}
\ No newline at end of file
+
+    async fn historical_key(resolver: &dyn DidResolver, verification_method: &str, created: &str) -> Option<keyring::signing_key::SigningKey> {
+        let version = DocumentVersion::Time(created.to_string());
+
+        resolver.resolve_verification_method_version(verification_method, VerificationRelationship::AssertionMethod, Some(&version)).await.ok()?.signing_key()
+    }
+}
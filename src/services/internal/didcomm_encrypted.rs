@@ -1,17 +1,13 @@
-use arrayref::array_ref;
-use serde_json::{Value};
-use didcomm_rs::{Message, crypto::{SignatureAlgorithm, CryptoAlgorithm}, AttachmentBuilder, AttachmentDataBuilder};
-use x25519_dalek::{PublicKey, StaticSecret};
+use serde_json::{Value, json};
+use didcomm_rs::{Message, crypto::{CryptoAlgorithm, Cypher}, AttachmentBuilder, AttachmentDataBuilder, MessageType};
 use cuid;
-use crate::{nodex::{errors::NodeXError, keyring::{self}, runtime::{self, base64_url::{self, PaddingType}}}};
-use super::{types::VerifiedContainer, did_vc::DIDVCService};
+use crate::{nodex::{errors::NodeXError, extension::secure_signer::SecureSigner, resolver::{DidResolver, cache::did_resolver}, schema::did_document::VerificationRelationship, keyring::{self, encryption_key::EncryptionKey, p256::P256, secp256k1::KeyPairSecp256K1, signing_key::SigningKey}, runtime::{self, base64_url::{self, PaddingType}}}};
+use super::{types::VerifiedContainer, did_vc::DIDVCService, didcomm_signed::DIDCommSignedService};
 
 pub struct DIDCommEncryptedService {}
 
 impl DIDCommEncryptedService {
     pub async fn generate(to_did: &str, message: &Value, metadata: Option<&Value>) -> Result<Value, NodeXError> {
-        let service = crate::services::nodex::NodeX::new();
-
         // NOTE: recipient from
         let my_keyring = match keyring::mnemonic::MnemonicKeyring::load_keyring() {
             Ok(v) => v,
@@ -23,38 +19,10 @@ impl DIDCommEncryptedService {
         };
 
         // NOTE: recipient to
-        let did_document = match service.find_identifier(to_did).await {
+        let other_key = match Self::agreement_key(to_did).await {
             Ok(v) => v,
             Err(_) => return Err(NodeXError{}),
         };
-        let public_keys = match did_document.did_document.public_key {
-            Some(v) => v,
-            None => return Err(NodeXError{}),
-        };
-
-        // FIXME: workaround
-        if public_keys.len() != 1 {
-            return Err(NodeXError{})
-        }
-
-        let public_key = public_keys[0].clone();
-
-        let other_key = match keyring::secp256k1::Secp256k1::from_jwk(&public_key.public_key_jwk) {
-            Ok(v) => v,
-            Err(_) => return Err(NodeXError{}),
-        };
-
-        // NOTE: ecdh
-        let shared_key = match runtime::secp256k1::Secp256k1::ecdh(
-            &my_keyring.get_sign_key_pair().get_secret_key(),
-            &other_key.get_public_key(),
-        ) {
-            Ok(v) => v,
-            Err(_) => return Err(NodeXError{})
-        };
-
-        let sk = StaticSecret::from(array_ref!(shared_key, 0, 32).to_owned());
-        let pk = PublicKey::from(&sk);
 
         // NOTE: message
         let body = match DIDVCService::generate(message) {
@@ -84,14 +52,89 @@ impl DIDCommEncryptedService {
             )
         }
 
-        match message.clone()
-            .as_jwe(&CryptoAlgorithm::XC20P, Some(pk.as_bytes().to_vec()))
-            .seal_signed(
-                sk.to_bytes().as_ref(),
-                Some(vec![ Some(pk.as_bytes().to_vec()) ]),
-                SignatureAlgorithm::Es256k,
-                &my_keyring.get_sign_key_pair().get_secret_key()
-            ) {
+        let (algorithm, signer, key) = DIDCommSignedService::signer(&my_keyring.get_sign_key_pair());
+
+        // NOTE: a P-256 recipient, or any recipient of a P-256 sender (ECDH-1PU is X25519 only), is sealed with ECDH-ES,
+        //       the content key is agreed with an ephemeral key on the curve of the recipient key
+        let other_key = match (other_key, my_keyring.get_encrypt_key_pair()) {
+            (Some(EncryptionKey::X25519(v)), EncryptionKey::X25519(_)) => Some(v),
+            (None, _) => None,
+            (Some(other_key), _) => {
+                // NOTE: the recipient checks that the key signing the JWS is a key of the sender ("skid")
+                let kid = format!("{}#{}", &my_did, my_keyring.get_sign_key_id());
+
+                let signed = match message
+                    .kid(&kid)
+                    .as_jws(&algorithm)
+                    .sign(signer, &key) {
+                        Ok(v) => v,
+                        Err(_) => return Err(NodeXError{}),
+                    };
+
+                return Self::seal_ecdh_es(&signed, &my_did, to_did, &other_key)
+            },
+        };
+
+        let my_key = match Self::agreement_key(&my_did).await {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+
+        // NOTE: the content key is agreed between the key agreement keys (ECDH-1PU), the signing key only signs
+        let (sk, pk) = match (other_key, my_key) {
+            (Some(other_key), Some(_)) => (my_keyring.get_encrypt_key_pair().get_secret_key(), other_key.get_public_key()),
+            _ => {
+                let other_key = match did_resolver().resolve_verification_method(to_did, VerificationRelationship::Authentication).await {
+                    Ok(v) => {
+                        match v.signing_key() {
+                            Some(v) => v,
+                            None => return Err(NodeXError{}),
+                        }
+                    },
+                    Err(_) => return Err(NodeXError{}),
+                };
+
+                match Self::legacy_key(&my_keyring.get_sign_key_pair(), &other_key) {
+                    Ok(v) => (v.get_secret_key(), v.get_public_key()),
+                    Err(_) => return Err(NodeXError{}),
+                }
+            },
+        };
+
+        // NOTE: seal_signed takes the secret key, so a key held by the secure element signs the JWS first and it is sealed
+        //       the way seal_signed does (the JWS as the body, typed as a JWS), Message::receive then verifies it
+        let sealed = match my_keyring.get_sign_key_pair().get_key_type() {
+            Some(_) => {
+                let message = message.as_jwe(&CryptoAlgorithm::XC20P, Some(pk.clone()));
+
+                let signed = match message.clone()
+                    .as_jws(&algorithm)
+                    .sign(signer, &key) {
+                        Ok(v) => v,
+                        Err(_) => return Err(NodeXError{}),
+                    };
+
+                message
+                    .body(&signed)
+                    .typ(MessageType::DidCommJws)
+                    .seal(
+                        &sk,
+                        Some(vec![ Some(pk) ]),
+                    )
+            },
+            None => {
+                message
+                    .as_jwe(&CryptoAlgorithm::XC20P, Some(pk.clone()))
+                    .seal_signed(
+                        &sk,
+                        Some(vec![ Some(pk) ]),
+                        algorithm,
+                        &key
+                    )
+            },
+        };
+
+        match sealed {
                 Ok(v) => {
                     match serde_json::from_str::<Value>(&v) {
                         Ok(v) => Ok(v),
@@ -102,8 +145,208 @@ impl DIDCommEncryptedService {
             }
     }
 
+    // NOTE: the key agreement key of a DID, None for a DID published by an older release which has none yet (the resolver
+    //       falls back to its secp256k1 signing key). Until its update adding the key is anchored, both devices agree on the
+    //       content key with their secp256k1 signing keys the way the older release does
+    async fn agreement_key(did: &str) -> Result<Option<EncryptionKey>, NodeXError> {
+        let public_key = match did_resolver().resolve_verification_method(did, VerificationRelationship::KeyAgreement).await {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+
+        let jwk = match public_key.jwk() {
+            Some(v) => v,
+            None => return Err(NodeXError{}),
+        };
+
+        if let Ok(v) = EncryptionKey::from_jwk(&jwk) {
+            return Ok(Some(v))
+        }
+
+        match keyring::secp256k1::Secp256k1::from_jwk(&jwk) {
+            Ok(_) => Ok(None),
+            Err(_) => Err(NodeXError{}),
+        }
+    }
+
+    // NOTE: both sides derive the same X25519 key pair from the secp256k1 ECDH of their signing keys, it is the sender and
+    //       the recipient key at once
+    fn legacy_key(my_key: &SigningKey, other_key: &SigningKey) -> Result<keyring::x25519::X25519, NodeXError> {
+        let (my_key, other_key) = match (my_key, other_key) {
+            (SigningKey::Secp256k1(u), SigningKey::Secp256k1(v)) => (u, v),
+            _ => return Err(NodeXError{}),
+        };
+
+        match SecureSigner::new().ecdh_secp256k1(my_key, &other_key.get_public_key()) {
+            Ok(v) => keyring::x25519::X25519::new(&v),
+            Err(_) => Err(NodeXError{}),
+        }
+    }
+
+    // NOTE: ECDH-ES (RFC 7518, 4.6) with XChaCha20-Poly1305, the content is the signed message (JWS). didcomm-rs serializes
+    //       the ephemeral key nested in "epk" and computes the AAD from the header it parsed again, so the JWE is built here
+    //       with the JWK of the key as "epk" of the protected header and the protected header as received as the AAD
+    const TAG_SIZE: usize = 16;
+    const NONCE_SIZE: usize = 24;
+
+    fn content_key(shared_secret: &[u8]) -> Result<Vec<u8>, NodeXError> {
+        runtime::p256::P256::concat_kdf(shared_secret, "XC20P", &[], &[], 32)
+    }
+
+    fn ephemeral_key(other_key: &EncryptionKey) -> Result<EncryptionKey, NodeXError> {
+        let secret = match runtime::random::Random::bytes(&32) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+
+        match other_key {
+            EncryptionKey::X25519(_) => keyring::x25519::X25519::new(&secret).map(EncryptionKey::X25519),
+            EncryptionKey::P256(_) => P256::new(&secret).map(EncryptionKey::P256),
+        }
+    }
+
+    // NOTE: a P-256 key agreement key may be held by the secure element
+    fn agree(my_key: &EncryptionKey, other_key: &EncryptionKey) -> Result<Vec<u8>, NodeXError> {
+        let shared_secret = match (my_key, other_key) {
+            (EncryptionKey::X25519(u), EncryptionKey::X25519(v)) => u.ecdh(&v.get_public_key()),
+            (EncryptionKey::P256(u), EncryptionKey::P256(v)) => SecureSigner::new().ecdh(u, &v.get_public_key()),
+            _ => return Err(NodeXError{}),
+        };
+
+        match shared_secret {
+            Ok(v) => Self::content_key(&v),
+            Err(_) => Err(NodeXError{}),
+        }
+    }
+
+    fn seal_ecdh_es(signed: &str, from: &str, to: &str, other_key: &EncryptionKey) -> Result<Value, NodeXError> {
+        let ephemeral = match Self::ephemeral_key(other_key) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+
+        let cek = match Self::agree(&ephemeral, other_key) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+
+        let epk = match ephemeral.to_jwk() {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+
+        let header = json!({
+            "typ": "application/didcomm-encrypted+json",
+            "cty": "application/didcomm-signed+json",
+            "alg": "ECDH-ES",
+            "enc": "XC20P",
+            "skid": from,
+            "kid": to,
+            "epk": epk,
+        });
+        let protected = base64_url::Base64Url::encode(header.to_string().as_bytes(), &PaddingType::NoPadding);
+
+        let iv = match runtime::random::Random::bytes(&Self::NONCE_SIZE) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+
+        let sealed = match CryptoAlgorithm::XC20P.encryptor()(&iv, &cek, signed.as_bytes(), protected.as_bytes()) {
+            Ok(v) if v.len() >= Self::TAG_SIZE => v,
+            _ => return Err(NodeXError{}),
+        };
+        let (ciphertext, tag) = sealed.split_at(sealed.len() - Self::TAG_SIZE);
+
+        Ok(json!({
+            "protected": protected,
+            "iv": base64_url::Base64Url::encode(&iv, &PaddingType::NoPadding),
+            "ciphertext": base64_url::Base64Url::encode(ciphertext, &PaddingType::NoPadding),
+            "tag": base64_url::Base64Url::encode(tag, &PaddingType::NoPadding),
+        }))
+    }
+
+    // NOTE: the signed message (JWS), ECDH-ES does not authenticate the sender, the JWS does
+    fn open_ecdh_es(message: &Value, protected: &str, header: &Value, my_key: &EncryptionKey) -> Result<Vec<u8>, NodeXError> {
+        let epk = match header.get("epk").map(|v| serde_json::from_value::<KeyPairSecp256K1>(v.clone())) {
+            Some(Ok(v)) => v,
+            _ => return Err(NodeXError{}),
+        };
+
+        let epk = match EncryptionKey::from_jwk(&epk) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+
+        let cek = match Self::agree(my_key, &epk) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+
+        let field = |name: &str| {
+            message
+                .get(name)
+                .and_then(|v| v.as_str())
+                .and_then(|v| base64_url::Base64Url::decode_as_bytes(v, &PaddingType::NoPadding).ok())
+        };
+
+        let (iv, ciphertext, tag) = match (field("iv"), field("ciphertext"), field("tag")) {
+            (Some(u), Some(v), Some(w)) => (u, v, w),
+            _ => return Err(NodeXError{}),
+        };
+
+        match CryptoAlgorithm::XC20P.decrypter()(&iv, &cek, &[ ciphertext, tag ].concat(), protected.as_bytes()) {
+            Ok(v) => Ok(v),
+            Err(_) => Err(NodeXError{}),
+        }
+    }
+
+    // NOTE: the "kid" of the (first) signature of a JWS in the general or the flattened serialization
+    fn signer_key_id(signed: &[u8]) -> Option<String> {
+        let jws = serde_json::from_slice::<Value>(signed).ok()?;
+
+        let signature = match jws.get("signatures") {
+            Some(v) => v.get(0)?,
+            None => jws.get("signature")?,
+        };
+
+        let protected = base64_url::Base64Url::decode_as_string(signature.get("protected")?.as_str()?, &PaddingType::NoPadding).ok()?;
+        let protected = serde_json::from_str::<Value>(&protected).ok()?;
+
+        protected.get("kid")?.as_str().map(|v| v.to_string())
+    }
+
+    fn did_of(did_url: &str) -> &str {
+        match did_url.split_once('#') {
+            Some((did, _)) => did,
+            None => did_url,
+        }
+    }
+
+    // NOTE: the JWS must be signed by an authentication key of the sender named by "skid"
+    async fn verify_signed(signed: &[u8], sender: &str) -> Result<Message, NodeXError> {
+        let kid = match Self::signer_key_id(signed) {
+            Some(v) if Self::did_of(&v) == Self::did_of(sender) => v,
+            _ => return Err(NodeXError{}),
+        };
+
+        let signing_key = match did_resolver().resolve_verification_method(&kid, VerificationRelationship::Authentication).await {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+
+        let signing_key = match signing_key.signing_key() {
+            Some(v) => v,
+            None => return Err(NodeXError{}),
+        };
+
+        match Message::verify(signed, &signing_key.get_public_key()) {
+            Ok(v) => Ok(v),
+            Err(_) => Err(NodeXError{})
+        }
+    }
+
     pub async fn verify(message: &Value) -> Result<VerifiedContainer, NodeXError> {
-        let service = crate::services::nodex::NodeX::new();
+        let resolver = did_resolver();
 
         // NOTE: recipient to
         let my_keyring = match keyring::mnemonic::MnemonicKeyring::load_keyring() {
@@ -142,50 +385,92 @@ impl DIDCommEncryptedService {
             None => return Err(NodeXError{}),
         };
 
-        let did_document = match service.find_identifier(&other_did).await {
+        if decoded.get("alg").and_then(|v| v.as_str()) == Some("ECDH-ES") {
+            let signed = match Self::open_ecdh_es(message, &protected, &decoded, &my_keyring.get_encrypt_key_pair()) {
+                Ok(v) => v,
+                Err(_) => return Err(NodeXError{}),
+            };
+
+            return match Self::verify_signed(&signed, &other_did).await {
+                Ok(v) => Self::container(v),
+                Err(_) => Err(NodeXError{}),
+            }
+        }
+
+        // NOTE: the inner JWS is signed by the authentication key of the sender
+        let signing_key = match resolver.resolve_verification_method(Self::did_of(&other_did), VerificationRelationship::Authentication).await {
             Ok(v) => v,
             Err(_) => return Err(NodeXError{}),
         };
 
-        let public_keys = match did_document.did_document.public_key {
+        let signing_key = match signing_key.signing_key() {
             Some(v) => v,
             None => return Err(NodeXError{}),
         };
 
-        // FIXME: workaround
-        if public_keys.len() != 1 {
-            return Err(NodeXError{})
-        }
-
-        let public_key = public_keys[0].clone();
-
-        let other_key = match keyring::secp256k1::Secp256k1::from_jwk(&public_key.public_key_jwk) {
+        let my_did = match my_keyring.get_identifier() {
             Ok(v) => v,
-            Err(_) => return Err(NodeXError{}),
+            Err(_) => return Err(NodeXError{})
         };
 
-        // NOTE: ecdh
-        let shared_key = match runtime::secp256k1::Secp256k1::ecdh(
-            &my_keyring.get_sign_key_pair().get_secret_key(),
-            &other_key.get_public_key(),
-        ) {
-            Ok(v) => v,
-            Err(_) => return Err(NodeXError{})
+        // NOTE: "skid" is either the sender DID or a DID URL of its key agreement key
+        let (other_key, my_key) = match (Self::agreement_key(&other_did).await, Self::agreement_key(&my_did).await) {
+            (Ok(u), Ok(v)) => (u, v),
+            _ => return Err(NodeXError{}),
         };
 
-        let sk = StaticSecret::from(array_ref!(shared_key, 0, 32).to_owned());
-        let pk = PublicKey::from(&sk);
+        let (sk, pk) = match (other_key, my_key) {
+            (Some(EncryptionKey::X25519(other_key)), Some(_)) => (my_keyring.get_encrypt_key_pair().get_secret_key(), other_key.get_public_key()),
+            (Some(EncryptionKey::P256(_)), Some(_)) => return Err(NodeXError{}),
+            _ => {
+                match Self::legacy_key(&my_keyring.get_sign_key_pair(), &signing_key) {
+                    Ok(v) => (v.get_secret_key(), v.get_public_key()),
+                    Err(_) => return Err(NodeXError{}),
+                }
+            },
+        };
 
         let message = match Message::receive(
             &message.to_string(),
-            Some(sk.to_bytes().as_ref()),
-            Some(pk.as_bytes().to_vec()),
-            Some(&other_key.get_public_key()),
+            Some(&sk),
+            Some(pk),
+            Some(&signing_key.get_public_key()),
         ) {
             Ok(v) => v,
             Err(_) => return Err(NodeXError{}),
         };
 
+        match Self::unwrap_signed(message, &signing_key) {
+            Ok(v) => Self::container(v),
+            Err(_) => Err(NodeXError{}),
+        }
+    }
+
+    // NOTE: a JWS sealed as the body of an untyped JWE (sent by an older release) is not verified by Message::receive,
+    //       it is verified against the authentication key of the sender here and its message is returned instead
+    fn unwrap_signed(message: Message, signing_key: &SigningKey) -> Result<Message, NodeXError> {
+        let body = match message.get_body() {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+
+        let is_signed = match serde_json::from_str::<Value>(&body) {
+            Ok(v) => v.get("signature").is_some() || v.get("signatures").is_some(),
+            Err(_) => false,
+        };
+
+        if !is_signed {
+            return Ok(message)
+        }
+
+        match Message::verify(body.as_bytes(), &signing_key.get_public_key()) {
+            Ok(v) => Ok(v),
+            Err(_) => Err(NodeXError{})
+        }
+    }
+
+    fn container(message: Message) -> Result<VerifiedContainer, NodeXError> {
+
         let metadata = message
             .get_attachments()
             .find(|item| {
@@ -230,4 +515,193 @@ impl DIDCommEncryptedService {
             }
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use serde_json::json;
+    use crate::nodex::keyring::{ed25519::Ed25519, secp256k1::{Secp256k1, Secp256k1Context}, x25519::X25519};
+
+    #[test]
+    fn test_sealed_jws() {
+        let (signing_key, other_signing_key) = match (Ed25519::new(&[ 0x01; 32 ]), Ed25519::new(&[ 0x02; 32 ])) {
+            (Ok(signing_key), Ok(other_signing_key)) => (SigningKey::Ed25519(signing_key), SigningKey::Ed25519(other_signing_key)),
+            _ => panic!()
+        };
+
+        let (sender, recipient) = match (X25519::new(&[ 0x03; 32 ]), X25519::new(&[ 0x04; 32 ])) {
+            (Ok(sender), Ok(recipient)) => (sender, recipient),
+            _ => panic!()
+        };
+
+        let (algorithm, signer, key) = DIDCommSignedService::signer(&signing_key);
+
+        let message = Message::new()
+            .from("did:nodex:test:a")
+            .to(&[ "did:nodex:test:b" ])
+            .body(&json!({ "value": 1 }).to_string())
+            .as_jwe(&CryptoAlgorithm::XC20P, Some(recipient.get_public_key()));
+
+        let signed = match message.clone().as_jws(&algorithm).sign(signer, &key) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        // NOTE: typed as a JWS, the inner signature is verified by Message::receive
+        let sealed = match message.clone()
+            .body(&signed)
+            .typ(MessageType::DidCommJws)
+            .seal(&sender.get_secret_key(), Some(vec![ Some(recipient.get_public_key()) ])) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        let received = Message::receive(&sealed, Some(&recipient.get_secret_key()), Some(sender.get_public_key()), Some(&signing_key.get_public_key()));
+        assert_eq!(received.ok().and_then(|v| v.get_body().ok()), Some(json!({ "value": 1 }).to_string()));
+
+        assert!(Message::receive(&sealed, Some(&recipient.get_secret_key()), Some(sender.get_public_key()), Some(&other_signing_key.get_public_key())).is_err());
+
+        // NOTE: untyped, the JWS comes back as the body and is unwrapped
+        let legacy = match message.clone()
+            .body(&signed)
+            .seal(&sender.get_secret_key(), Some(vec![ Some(recipient.get_public_key()) ])) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        let received = match Message::receive(&legacy, Some(&recipient.get_secret_key()), Some(sender.get_public_key()), None) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        let unwrapped = match DIDCommEncryptedService::unwrap_signed(received.clone(), &signing_key) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+        assert_eq!(unwrapped.get_body().ok(), Some(json!({ "value": 1 }).to_string()));
+
+        assert!(DIDCommEncryptedService::unwrap_signed(received, &other_signing_key).is_err());
+        assert!(DIDCommEncryptedService::unwrap_signed(message, &other_signing_key).is_ok());
+    }
+
+    #[actix_rt::test]
+    async fn test_verify_signed_by_other_did() {
+        let signing_key = match Ed25519::new(&[ 0x01; 32 ]) {
+            Ok(v) => SigningKey::Ed25519(v),
+            Err(_) => panic!()
+        };
+        let (algorithm, signer, key) = DIDCommSignedService::signer(&signing_key);
+
+        let signed = match Message::new()
+            .from("did:nodex:test:c")
+            .to(&[ "did:nodex:test:b" ])
+            .body(&json!({ "value": 1 }).to_string())
+            .kid("did:nodex:test:c#signingKey")
+            .as_jws(&algorithm)
+            .sign(signer, &key) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        // NOTE: rejected before the key of either DID is resolved
+        assert!(DIDCommEncryptedService::verify_signed(signed.as_bytes(), "did:nodex:test:a").await.is_err());
+        assert!(DIDCommEncryptedService::verify_signed(b"{}", "did:nodex:test:a").await.is_err());
+    }
+
+    #[test]
+    fn test_legacy_key() {
+        let key = |secret: &[u8]| {
+            let public = match runtime::secp256k1::Secp256k1::generate_public_key(secret) {
+                Ok(v) => v,
+                Err(_) => panic!()
+            };
+
+            match Secp256k1::new(&Secp256k1Context { public, secret: secret.to_vec() }) {
+                Ok(v) => SigningKey::Secp256k1(v),
+                Err(_) => panic!()
+            }
+        };
+
+        let (sender, recipient) = match (DIDCommEncryptedService::legacy_key(&key(&[ 0x01; 32 ]), &key(&[ 0x02; 32 ])), DIDCommEncryptedService::legacy_key(&key(&[ 0x02; 32 ]), &key(&[ 0x01; 32 ]))) {
+            (Ok(sender), Ok(recipient)) => (sender, recipient),
+            _ => panic!()
+        };
+
+        assert_eq!(sender.get_secret_key(), recipient.get_secret_key());
+        assert_eq!(sender.get_public_key(), recipient.get_public_key());
+
+        let other = match Ed25519::new(&[ 0x02; 32 ]) {
+            Ok(v) => SigningKey::Ed25519(v),
+            Err(_) => panic!()
+        };
+
+        assert!(DIDCommEncryptedService::legacy_key(&key(&[ 0x01; 32 ]), &other).is_err());
+    }
+
+    #[test]
+    fn test_ecdh_es() {
+        let signing_key = match Ed25519::new(&[ 0x01; 32 ]) {
+            Ok(v) => SigningKey::Ed25519(v),
+            Err(_) => panic!()
+        };
+        let (algorithm, signer, key) = DIDCommSignedService::signer(&signing_key);
+
+        let signed = match Message::new()
+            .from("did:nodex:test:a")
+            .to(&[ "did:nodex:test:b" ])
+            .body(&json!({ "value": 1 }).to_string())
+            .kid("did:nodex:test:a#signingKey")
+            .as_jws(&algorithm)
+            .sign(signer, &key) {
+            Ok(v) => v,
+            Err(_) => panic!()
+        };
+
+        assert_eq!(DIDCommEncryptedService::signer_key_id(signed.as_bytes()).as_deref(), Some("did:nodex:test:a#signingKey"));
+
+        let recipients = match (X25519::new(&[ 0x02; 32 ]), P256::new(&[ 0x03; 32 ])) {
+            (Ok(x25519), Ok(p256)) => [ EncryptionKey::X25519(x25519), EncryptionKey::P256(p256) ],
+            _ => panic!()
+        };
+
+        for recipient in recipients.iter() {
+            let jwk = match recipient.to_jwk() {
+                Ok(v) => v,
+                Err(_) => panic!()
+            };
+
+            let sealed = match EncryptionKey::from_jwk(&jwk).and_then(|v| DIDCommEncryptedService::seal_ecdh_es(&signed, "did:nodex:test:a", "did:nodex:test:b", &v)) {
+                Ok(v) => v,
+                Err(_) => panic!()
+            };
+
+            let protected = match sealed["protected"].as_str() {
+                Some(v) => v,
+                None => panic!()
+            };
+
+            let header = match base64_url::Base64Url::decode_as_string(protected, &PaddingType::NoPadding).ok().and_then(|v| serde_json::from_str::<Value>(&v).ok()) {
+                Some(v) => v,
+                None => panic!()
+            };
+
+            // NOTE: the ephemeral key is the JWK itself (RFC 7518, 4.6.1.1)
+            assert_eq!(header["epk"]["crv"], jwk.crv);
+            assert!(header["epk"]["x"].is_string());
+            assert!(header["epk"].get("epk").is_none());
+
+            let opened = match DIDCommEncryptedService::open_ecdh_es(&sealed, protected, &header, recipient) {
+                Ok(v) => v,
+                Err(_) => panic!()
+            };
+
+            assert_eq!(opened, signed.as_bytes().to_vec());
+            assert_eq!(Message::verify(&opened, &signing_key.get_public_key()).ok().and_then(|v| v.get_body().ok()), Some(json!({ "value": 1 }).to_string()));
+
+            let tampered_protected = base64_url::Base64Url::encode(header.to_string().replace("did:nodex:test:a", "did:nodex:test:c").as_bytes(), &PaddingType::NoPadding);
+            let mut tampered = sealed.clone();
+            tampered["protected"] = Value::String(tampered_protected.clone());
+            assert!(DIDCommEncryptedService::open_ecdh_es(&tampered, &tampered_protected, &header, recipient).is_err());
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use serde_json::Value;
-use didcomm_rs::{Message, crypto::{SignatureAlgorithm, Signer}, AttachmentBuilder, AttachmentDataBuilder};
+use didcomm_rs::{Message, crypto::{SignatureAlgorithm, Signer, SigningMethod}, AttachmentBuilder, AttachmentDataBuilder};
 use cuid;
-use crate::{nodex::{errors::NodeXError, keyring::{self}, runtime::base64_url::{self, PaddingType}}};
+use crate::{nodex::{errors::NodeXError, extension::secure_signer::SecureSigner, resolver::{DidResolver, cache::did_resolver}, schema::did_document::VerificationRelationship, keyring::{self, signing_key::SigningKey}, runtime::base64_url::{self, PaddingType}}};
 
 use super::{did_vc::DIDVCService, types::VerifiedContainer};
 
@@ -26,6 +26,7 @@ impl DIDCommSignedService {
         let mut message = Message::new()
             .from(&did)
             .to(&[ to_did ])
+            .kid(&format!("{}#{}", &did, keyring.get_sign_key_id()))
             .body(&body.to_string());
 
         // NOTE: Has attachment
@@ -44,9 +45,11 @@ impl DIDCommSignedService {
             )
         }
 
+        let (algorithm, signer, key) = Self::signer(&keyring.get_sign_key_pair());
+
         match message.clone()
-            .as_jws(&SignatureAlgorithm::Es256k)
-            .sign(SignatureAlgorithm::Es256k.signer(), &keyring.get_sign_key_pair().get_secret_key()) {
+            .as_jws(&algorithm)
+            .sign(signer, &key) {
                 Ok(v) => {
                     match serde_json::from_str::<Value>(&v) {
                         Ok(v) => Ok(v),
@@ -57,8 +60,67 @@ impl DIDCommSignedService {
             }
     }
 
+    // NOTE: the algorithm, the signer and the key it takes, the Ed25519 signer of didcomm-rs takes the secret key followed by the public key
+    pub(super) fn signer(context: &SigningKey) -> (SignatureAlgorithm, SigningMethod, Vec<u8>) {
+        let algorithm = match context {
+            SigningKey::Secp256k1(_) => SignatureAlgorithm::Es256k,
+            SigningKey::Ed25519(_) => SignatureAlgorithm::EdDsa,
+            SigningKey::P256(_) => SignatureAlgorithm::Es256,
+        };
+
+        if context.get_key_type().is_some() {
+            return (algorithm, Box::new(Self::secure_signer), vec![])
+        }
+
+        let key = match context {
+            SigningKey::Secp256k1(v) => v.get_secret_key(),
+            SigningKey::Ed25519(v) => [ v.get_secret_key(), v.get_public_key() ].concat(),
+            SigningKey::P256(v) => v.get_secret_key(),
+        };
+
+        let signer = algorithm.signer();
+
+        (algorithm, signer, key)
+    }
+
+    // NOTE: the signer is called without the key ring, so the sign key held by the secure element is loaded again
+    pub(super) fn secure_signer(_: &[u8], message: &[u8]) -> Result<Vec<u8>, didcomm_rs::Error> {
+        let keyring = match keyring::mnemonic::MnemonicKeyring::load_keyring() {
+            Ok(v) => v,
+            Err(_) => return Err(didcomm_rs::Error::Generic("unable to load keyring".to_string()))
+        };
+
+        match SecureSigner::new().sign(&keyring.get_sign_key_pair(), message) {
+            Ok(v) => Ok(v),
+            Err(_) => Err(didcomm_rs::Error::Generic("unable to sign with secure signer".to_string()))
+        }
+    }
+
+    fn header_key_id(header: &Value) -> Option<String> {
+        header.get("kid").or_else(|| header.get("skid")).and_then(|v| v.as_str()).map(|v| v.to_string())
+    }
+
+    // NOTE: "kid" may be in the protected or the unprotected header, of a flattened or general JWS
+    fn signer_key_id(message: &Value) -> Option<String> {
+        let mut signatures = vec![ message ];
+
+        if let Some(Value::Array(items)) = message.get("signatures") {
+            signatures.extend(items.iter());
+        }
+
+        signatures.iter().find_map(|signature| {
+            let protected = signature.get("protected")
+                .and_then(|v| v.as_str())
+                .and_then(|v| base64_url::Base64Url::decode_as_string(v, &PaddingType::NoPadding).ok())
+                .and_then(|v| serde_json::from_str::<Value>(&v).ok())
+                .and_then(|v| Self::header_key_id(&v));
+
+            protected.or_else(|| signature.get("header").and_then(Self::header_key_id))
+        })
+    }
+
     pub async fn verify(message: &Value) -> Result<VerifiedContainer, NodeXError> {
-        let service = crate::services::nodex::NodeX::new();
+        let resolver = did_resolver();
 
         let payload = match message.get("payload") {
             Some(v) => {
@@ -90,27 +152,27 @@ impl DIDCommSignedService {
             None => return Err(NodeXError{}),
         };
 
-        let did_document = match service.find_identifier(&from_did).await {
+        // NOTE: messages without "kid" come from agents publishing a single key
+        let key_id = match Self::signer_key_id(message) {
+            Some(v) => {
+                match v.split_once('#') {
+                    Some((did, _)) if did == from_did => v,
+                    _ => return Err(NodeXError{}),
+                }
+            },
+            None => from_did,
+        };
+
+        let public_key = match resolver.resolve_verification_method(&key_id, VerificationRelationship::Authentication).await {
             Ok(v) => v,
             Err(_) => return Err(NodeXError{}),
         };
-        let public_keys = match did_document.did_document.public_key {
+
+        let context = match public_key.signing_key() {
             Some(v) => v,
             None => return Err(NodeXError{}),
         };
 
-        // FIXME: workaround
-        if public_keys.len() != 1 {
-            return Err(NodeXError{})
-        }
-
-        let public_key = public_keys[0].clone();
-
-        let context = match keyring::secp256k1::Secp256k1::from_jwk(&public_key.public_key_jwk) {
-            Ok(v) => v,
-            Err(_) => return Err(NodeXError{}),
-        };
-
         let message = match Message::verify(message.to_string().as_bytes(), &context.get_public_key()) {
             Ok(v) => v,
             Err(_) => return Err(NodeXError{}),
@@ -160,4 +222,25 @@ impl DIDCommSignedService {
             }
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_signer_key_id() {
+        let protected = base64_url::Base64Url::encode(br#"{"alg":"ES256K","kid":"did:nodex:test:abc#signingKey"}"#, &PaddingType::NoPadding);
+
+        let flattened = json!({ "payload": "", "protected": protected, "signature": "" });
+        let general = json!({ "payload": "", "signatures": [{ "protected": protected, "signature": "" }] });
+        let unprotected = json!({ "payload": "", "signatures": [{ "header": { "kid": "did:nodex:test:abc#other" }, "signature": "" }] });
+        let missing = json!({ "payload": "", "signatures": [{ "signature": "" }] });
+
+        assert_eq!(DIDCommSignedService::signer_key_id(&flattened), Some("did:nodex:test:abc#signingKey".to_string()));
+        assert_eq!(DIDCommSignedService::signer_key_id(&general), Some("did:nodex:test:abc#signingKey".to_string()));
+        assert_eq!(DIDCommSignedService::signer_key_id(&unprotected), Some("did:nodex:test:abc#other".to_string()));
+        assert_eq!(DIDCommSignedService::signer_key_id(&missing), None);
+    }
+}
@@ -1,16 +1,19 @@
-use crate::{nodex::{errors::NodeXError, keyring, sidetree::payload::{OperationPayload, DIDCreateRequest, CommitmentKeys, DIDResolutionResponse}, utils::http_client::{HttpClient, HttpClientConfig}}};
+use crate::{app_config, config::with_profile, nodex::{errors::NodeXError, keyring::{self, secp256k1::Secp256k1}, schema::did_document::{DIDDocument, Service, VerificationRelationship}, resolver::{DidResolver, cache::did_resolver, nodex::{NodexDidResolver, DEFAULT_BASE_URL}}, sidetree::{resolver::LongFormResolver, queue::{operation_queue, OperationStatus, OperationType, QueuedOperation}, payload::{OperationPayload, ServiceEndpoint, DIDAddPublicKeysAction, DIDRemovePublicKeysAction, DIDAddServicesAction, DIDRemoveServicesAction, DIDCreateRequest, DIDUpdateRequest, DIDRecoverRequest, DIDDeactivateRequest, DIDPatchAction, CommitmentKeys, DIDResolutionResponse}}, utils::http_client::{HttpClient, HttpClientConfig}}};
+use chrono::Utc;
+use reqwest::StatusCode;
 use serde_json::{Value, json};
 
 use super::internal::didcomm_encrypted::DIDCommEncryptedService;
 
 pub struct NodeX {
-    http_client: HttpClient
+    http_client: HttpClient,
+    resolver: NodexDidResolver,
 }
 
 impl NodeX {
     pub fn new() -> Self {
         let client_config: HttpClientConfig = HttpClientConfig {
-            base_url: "https://did.nodecross.io".to_string(),
+            base_url: DEFAULT_BASE_URL.to_string(),
         };
 
         let client = match HttpClient::new(&client_config) {
@@ -18,16 +21,32 @@ impl NodeX {
             Err(_) => panic!()
         };
 
-        NodeX { http_client: client }
+        NodeX { http_client: client, resolver: NodexDidResolver::new() }
     }
 
     // NOTE: DONE
     pub async fn create_identifier(&self) -> Result<DIDResolutionResponse, NodeXError> {
-        // NOTE: find did
-        if let Ok(v) = keyring::mnemonic::MnemonicKeyring::load_keyring() {
-            if let Ok(did) = v.get_identifier() {
+        // NOTE: find did (a deactivated key ring is never reused)
+        if let Ok(mut v) = keyring::mnemonic::MnemonicKeyring::load_keyring() {
+            if v.is_deactivated() {
+                log::info!("local key ring is deactivated, creating a new identifier");
+            } else if let Ok(did) = v.get_identifier() {
                 if let Ok(json) = self.find_identifier(&did).await {
-                    return Ok(json)
+                    if !json.method_metadata.is_deactivated() {
+                        // NOTE: anchor a long-form DID which is not published yet
+                        if !json.method_metadata.published && !operation_queue().has_unfinished(&did) {
+                            if let Ok(payload) = OperationPayload::did_long_form_payload(&did) {
+                                operation_queue().enqueue(&did, OperationType::Create, &payload, None);
+                            }
+                        }
+
+                        // NOTE: add the keys a DID published by an older release lacks
+                        if json.method_metadata.published && !operation_queue().has_unfinished(&did) && self.migrate_keys(&mut v, &json).await.is_err() {
+                            log::error!("unable to add the missing keys to {}", &did);
+                        }
+
+                        return Ok(json)
+                    }
                 }
             }
         }
@@ -39,10 +58,16 @@ impl NodeX {
         };
 
         // NOTE: create payload
-        let public = match keyring.get_sign_key_pair().to_public_key("signingKey", &["auth", "general"]) {
+        let public = match keyring.get_sign_key_pair().to_public_key("signingKey", &["auth", "general", "assertionMethod"]) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+        let encrypt = match keyring.get_encrypt_key_pair().to_public_key("encryptionKey", &["keyAgreement"]) {
             Ok(v) => v,
             Err(_) => return Err(NodeXError{}),
         };
+        // NOTE: every published DID commits to the recovery key for its first update and to the first update key
+        //       for its recovery, see update_key() and recovery_key()
         let update = match keyring.get_recovery_key_pair().to_jwk(false) {
             Ok(v) => v,
             Err(_) => return Err(NodeXError{}),
@@ -52,47 +77,730 @@ impl NodeX {
             Err(_) => return Err(NodeXError{}),
         };
 
-        let payload = match OperationPayload::did_create_payload(&DIDCreateRequest {
-            public_keys: vec![ public ],
+        let request = DIDCreateRequest {
+            public_keys: vec![ public, encrypt ],
+            commitment_keys: CommitmentKeys {
+                recovery,
+                update,
+            },
+            service_endpoints: app_config().inner.lock().unwrap().load_services(),
+        };
+
+        let payload = match OperationPayload::did_create_payload(&request) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+        let did_prefix = app_config().inner.lock().unwrap().load_sidetree_config().did_prefix;
+        let long_form = match OperationPayload::did_long_form(&did_prefix, &request) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+
+        let (json, operation) = {
+            let queue = operation_queue();
+            let _guard = queue.lock().await;
+
+            let operation = queue.enqueue(&long_form, OperationType::Create, &payload, None);
+
+            match self.submit(&operation).await {
+                Some(res) => (res.json::<DIDResolutionResponse>().await.ok(), operation),
+                None => (None, operation),
+            }
+        };
+
+        // NOTE: a create rejected by the node is never used, it is not retried either
+        let is_failed = match operation_queue().find(&operation.id) {
+            Some(v) => v.status == OperationStatus::Failed,
+            None => true,
+        };
+
+        match json {
+            Some(json) => {
+                // NOTE: save context
+                if keyring.save(&json.did_document.id).is_err() {
+                    return Err(NodeXError{})
+                }
+
+                Ok(json)
+            },
+            None if is_failed => Err(NodeXError{}),
+            None => {
+                // NOTE: the node is unreachable or busy, so the DID is usable in its long form until anchored
+                let json = match LongFormResolver::resolve(&long_form) {
+                    Ok(v) => v,
+                    Err(_) => return Err(NodeXError{}),
+                };
+
+                // NOTE: save context
+                if keyring.save(&long_form).is_err() {
+                    return Err(NodeXError{})
+                }
+
+                Ok(json)
+            }
+        }
+    }
+
+    // NOTE: a DID created before the key agreement key (and the assertionMethod purpose) has neither, so other devices cannot
+    //       encrypt to it and its credentials do not verify. The keys are added by an update, until it is anchored the devices
+    //       agree on keys with the signing keys as before (see DIDCommEncryptedService)
+    fn migration_patches(keyring: &keyring::mnemonic::MnemonicKeyring, document: &DIDDocument) -> Result<Vec<DIDPatchAction>, NodeXError> {
+        let mut public_keys = vec![];
+
+        if document.verification_methods_for(VerificationRelationship::KeyAgreement).is_empty() {
+            match keyring.get_encrypt_key_pair().to_public_key("encryptionKey", &["keyAgreement"]) {
+                Ok(v) => public_keys.push(v),
+                Err(_) => return Err(NodeXError{}),
+            };
+        }
+
+        // NOTE: the signing key is added again with the same id, which replaces the published one
+        if document.verification_methods_for(VerificationRelationship::AssertionMethod).is_empty() {
+            match keyring.get_sign_key_pair().to_public_key(&keyring.get_sign_key_id(), &["auth", "general", "assertionMethod"]) {
+                Ok(v) => public_keys.push(v),
+                Err(_) => return Err(NodeXError{}),
+            };
+        }
+
+        if public_keys.is_empty() {
+            return Ok(vec![])
+        }
+
+        Ok(vec![ DIDPatchAction::AddPublicKeys(DIDAddPublicKeysAction { public_keys }) ])
+    }
+
+    async fn migrate_keys(&self, keyring: &mut keyring::mnemonic::MnemonicKeyring, json: &DIDResolutionResponse) -> Result<(), NodeXError> {
+        let patches = match Self::migration_patches(keyring, &json.did_document) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+
+        if patches.is_empty() {
+            return Ok(())
+        }
+
+        match self.submit_update(keyring, &patches).await {
+            Ok(did) => {
+                log::info!("added the missing keys to {}", &did);
+                Ok(())
+            },
+            Err(_) => Err(NodeXError{}),
+        }
+    }
+
+    // NOTE: DONE
+    pub async fn find_identifier(&self, did: &str) -> Result<DIDResolutionResponse, NodeXError> {
+        self.resolver.resolve(did).await
+    }
+
+    pub async fn find_services(&self) -> Result<Vec<Service>, NodeXError> {
+        let keyring = match keyring::mnemonic::MnemonicKeyring::load_keyring() {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+        let did = match keyring.get_identifier() {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+
+        match self.find_identifier(&did).await {
+            Ok(v) => Ok(v.did_document.service.unwrap_or_default()),
+            Err(_) => Err(NodeXError{}),
+        }
+    }
+
+    // NOTE: services with an existing id are replaced
+    pub async fn add_services(&self, services: &[ServiceEndpoint]) -> Result<DIDResolutionResponse, NodeXError> {
+        if services.is_empty() || services.iter().any(|v| !v.is_valid()) {
+            return Err(NodeXError{})
+        }
+
+        self.update_identifier(&[ DIDPatchAction::AddServices(DIDAddServicesAction {
+            services: services.to_vec(),
+        }) ]).await
+    }
+
+    pub async fn remove_services(&self, ids: &[String]) -> Result<DIDResolutionResponse, NodeXError> {
+        if ids.is_empty() {
+            return Err(NodeXError{})
+        }
+
+        self.update_identifier(&[ DIDPatchAction::RemoveServices(DIDRemoveServicesAction {
+            ids: ids.to_vec(),
+        }) ]).await
+    }
+
+    // NOTE: keep the configured services in line with the published ones, they are used when the DID is re-created
+    fn save_services(patches: &[DIDPatchAction]) -> Result<(), NodeXError> {
+        let config = app_config();
+        let mut config = config.inner.lock().unwrap();
+        let mut services = config.load_services();
+
+        for patch in patches {
+            match patch {
+                DIDPatchAction::AddServices(action) => {
+                    for service in action.services.iter() {
+                        services.retain(|v| v.id != service.id);
+                        services.push(service.clone());
+                    }
+                },
+                DIDPatchAction::RemoveServices(action) => {
+                    services.retain(|v| !action.ids.contains(&v.id));
+                },
+                _ => {},
+            }
+        }
+
+        config.save_services(&services)
+    }
+
+    pub async fn update_identifier(&self, patches: &[DIDPatchAction]) -> Result<DIDResolutionResponse, NodeXError> {
+        let mut keyring = match keyring::mnemonic::MnemonicKeyring::load_keyring() {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+
+        let did = match self.submit_update(&mut keyring, patches).await {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+
+        self.find_identifier(&did).await
+    }
+
+    // NOTE: replace the signing key with the one derived at the next index, credentials signed before are verified with the document version of their time
+    pub async fn rotate_keys(&self) -> Result<DIDResolutionResponse, NodeXError> {
+        let mut keyring = match keyring::mnemonic::MnemonicKeyring::load_keyring() {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+
+        let index = keyring.get_sign_key_index() + 1;
+
+        let next_sign = match keyring.next_sign_key_pair(index) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+        let public = match next_sign.to_public_key(&keyring::mnemonic::MnemonicKeyring::sign_key_id(index), &["auth", "general", "assertionMethod"]) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+
+        let patches = vec![
+            DIDPatchAction::RemovePublicKeys(DIDRemovePublicKeysAction {
+                ids: vec![ keyring.get_sign_key_id() ],
+            }),
+            DIDPatchAction::AddPublicKeys(DIDAddPublicKeysAction {
+                public_keys: vec![ public ],
+            }),
+        ];
+
+        let did = match self.submit_update(&mut keyring, &patches).await {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+
+        // NOTE: save context
+        if keyring.save_sign_key_pair(&next_sign, index).is_err() {
+            return Err(NodeXError{})
+        }
+
+        log::info!("rotated the signing key of {} to index {}", &did, index);
+
+        self.find_identifier(&did).await
+    }
+
+    async fn submit_update(&self, keyring: &mut keyring::mnemonic::MnemonicKeyring, patches: &[DIDPatchAction]) -> Result<String, NodeXError> {
+        if keyring.is_deactivated() {
+            return Err(NodeXError{})
+        }
+
+        let did = match keyring.get_identifier() {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+        let did_suffix = match OperationPayload::did_suffix(&did) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+
+        // NOTE: commit to the update key derived at the next index
+        let index = keyring.get_update_key_index() + 1;
+
+        let next_update = match keyring.derive_update_key_pair(index) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+        let next_update_key = match next_update.to_jwk(false) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+        let update_commitment = match OperationPayload::commitment(&next_update_key) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+
+        let update_key = Self::update_key(keyring);
+
+        let payload = match OperationPayload::did_update_payload(&DIDUpdateRequest {
+            did_suffix,
+            patches: patches.to_vec(),
+            update_key,
+            next_update_key,
+        }) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+
+        if self.enqueue(&did, OperationType::Update, &payload, Some(update_commitment)).await.is_err() {
+            return Err(NodeXError{})
+        }
+
+        // NOTE: save context
+        if keyring.save_update_key_pair(&next_update, index).is_err() {
+            return Err(NodeXError{})
+        }
+
+        if Self::save_services(patches).is_err() {
+            return Err(NodeXError{})
+        }
+
+        // NOTE: drop the document cached before the operation
+        did_resolver().invalidate(&did);
+
+        Ok(did)
+    }
+
+    pub async fn recover_identifier(&self) -> Result<DIDResolutionResponse, NodeXError> {
+        let mut keyring = match keyring::mnemonic::MnemonicKeyring::load_keyring() {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+
+        if keyring.is_deactivated() {
+            return Err(NodeXError{})
+        }
+
+        let did = match keyring.get_identifier() {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+        let did_suffix = match OperationPayload::did_suffix(&did) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+
+        // NOTE: the signing, update and recovery keys are derived from the mnemonic at their next index,
+        //       so the backup phrase restores the recovered DID too
+        let sign_index = keyring.get_sign_key_index() + 1;
+        let update_index = keyring.get_update_key_index() + 1;
+        let recovery_index = keyring.get_recovery_key_index() + 1;
+
+        let next_sign = match keyring.next_sign_key_pair(sign_index) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+        let next_update = match keyring.derive_update_key_pair(update_index) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+        let next_recovery = match keyring.derive_recovery_key_pair(recovery_index) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+
+        let public = match next_sign.to_public_key(&keyring::mnemonic::MnemonicKeyring::sign_key_id(sign_index), &["auth", "general", "assertionMethod"]) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+        let encrypt = match keyring.get_encrypt_key_pair().to_public_key("encryptionKey", &["keyAgreement"]) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+        let update = match next_update.to_jwk(false) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+        let recovery = match next_recovery.to_jwk(false) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+        let update_commitment = match OperationPayload::commitment(&update) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+
+        let recovery_key = match Self::recovery_key(&keyring) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+
+        let payload = match OperationPayload::did_recover_payload(&DIDRecoverRequest {
+            did_suffix,
+            recovery_key,
+            public_keys: vec![ public, encrypt ],
             commitment_keys: CommitmentKeys {
                 recovery,
                 update,
             },
-            service_endpoints: vec![],
+            service_endpoints: app_config().inner.lock().unwrap().load_services(),
         }) {
             Ok(v) => v,
             Err(_) => return Err(NodeXError{}),
         };
 
-        let res = match self.http_client.post("/api/v1/operations", &payload).await {
+        if self.enqueue(&did, OperationType::Recover, &payload, Some(update_commitment)).await.is_err() {
+            return Err(NodeXError{})
+        }
+
+        // NOTE: save context
+        if keyring.save_sign_key_pair(&next_sign, sign_index).is_err() {
+            return Err(NodeXError{})
+        }
+        if keyring.save_update_key_pair(&next_update, update_index).is_err() {
+            return Err(NodeXError{})
+        }
+        if keyring.save_recovery_key_pair(&next_recovery, recovery_index).is_err() {
+            return Err(NodeXError{})
+        }
+
+        // NOTE: drop the document cached before the operation
+        did_resolver().invalidate(&did);
+
+        self.find_identifier(&did).await
+    }
+
+    // NOTE: re-derive the key ring of an existing DID, the keys must match the published document and commitments
+    pub async fn restore_identifier(&self, did: &str, mnemonic: &str, passphrase: Option<&str>) -> Result<DIDResolutionResponse, NodeXError> {
+        let mut keyring = match keyring::mnemonic::MnemonicKeyring::restore_keyring(mnemonic, passphrase) {
             Ok(v) => v,
             Err(_) => return Err(NodeXError{}),
         };
 
-        let json = match res.json::<DIDResolutionResponse>().await {
+        let json = match self.find_identifier(did).await {
             Ok(v) => v,
             Err(_) => return Err(NodeXError{}),
         };
 
+        if json.method_metadata.is_deactivated() {
+            return Err(NodeXError{})
+        }
+
+        let metadata = &json.method_metadata;
+
+        // NOTE: a DID which has never been recovered commits to the first update key (see recovery_key())
+        let first_update = match keyring.derive_update_key_pair(0) {
+            Ok(v) => Self::commits_to(&v, &metadata.recovery_commitment),
+            Err(_) => false,
+        };
+
+        let recovery_index = match keyring.find_recovery_key_index(|index, key| index != 0 && Self::commits_to(key, &metadata.recovery_commitment)) {
+            Some(v) => v,
+            None if first_update => 0,
+            None => {
+                log::error!("the mnemonic phrase does not match the recovery commitment of {}", did);
+                return Err(NodeXError{})
+            },
+        };
+
+        // NOTE: a DID which has never been updated commits to the recovery key (see update_key())
+        let update_index = match keyring.find_update_key_index(|index, key| index != 0 && Self::commits_to(key, &metadata.update_commitment)) {
+            Some(v) => v,
+            None if Self::commits_to(&keyring.get_recovery_key_pair(), &metadata.update_commitment) => 0,
+            None => {
+                log::error!("no derived update key matches the update commitment of {}", did);
+                return Err(NodeXError{})
+            },
+        };
+
+        let document = &json.did_document;
+
+        let sign_index = match keyring.find_sign_key_index(|index, key| {
+            let id = format!("#{}", keyring::mnemonic::MnemonicKeyring::sign_key_id(index));
+
+            match (document.find_verification_method(&id, VerificationRelationship::Authentication).and_then(|v| v.jwk()), key.to_jwk(false)) {
+                (Some(published), Ok(derived)) => published.x == derived.x && published.y == derived.y,
+                _ => false,
+            }
+        }) {
+            Some(v) => v,
+            // NOTE: a signing key generated by a secure element is not derived, the published one is replaced below
+            None if keyring.holds_sign_key() => {
+                match keyring::mnemonic::MnemonicKeyring::find_sign_key_id_index(|id| document.find_verification_method(&format!("#{}", id), VerificationRelationship::Authentication).is_some()) {
+                    Some(v) => v,
+                    None => {
+                        log::error!("no signing key is published by {}", did);
+                        return Err(NodeXError{})
+                    },
+                }
+            },
+            None => {
+                log::error!("no derived signing key is published by {}", did);
+                return Err(NodeXError{})
+            },
+        };
+
+        let holds_sign_key = keyring.holds_sign_key();
+
         // NOTE: save context
-        keyring.save(&json.did_document.id);
+        if keyring.restore(did, sign_index, update_index, recovery_index).is_err() {
+            return Err(NodeXError{})
+        }
+
+        // NOTE: read the key ring back through the key store
+        let words = mnemonic.split_whitespace().map(|v| v.to_string()).collect::<Vec<String>>();
+
+        match keyring::mnemonic::MnemonicKeyring::load_keyring().and_then(|v| v.verify_mnemonic_phrase(&words)) {
+            Ok(true) => {},
+            _ => return Err(NodeXError{}),
+        };
+
+        log::info!("restored {} (signing key index {}, update key index {}, recovery key index {})", did, sign_index, update_index, recovery_index);
+
+        // NOTE: drop the document cached before the restore
+        did_resolver().invalidate(did);
+
+        // NOTE: the signing key of the secure element is published in place of the one of the lost device
+        if holds_sign_key {
+            return self.rotate_keys().await
+        }
 
         Ok(json)
     }
 
-    // NOTE: DONE
-    pub async fn find_identifier(&self, did: &str) -> Result<DIDResolutionResponse, NodeXError> {
-        let res = match self.http_client.get(&(format!("/api/v1/identifiers/{}", &did))).await {
+    pub async fn deactivate_identifier(&self) -> Result<DIDResolutionResponse, NodeXError> {
+        let mut keyring = match keyring::mnemonic::MnemonicKeyring::load_keyring() {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+
+        if keyring.is_deactivated() {
+            return Err(NodeXError{})
+        }
+
+        let did = match keyring.get_identifier() {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+        let did_suffix = match OperationPayload::did_suffix(&did) {
             Ok(v) => v,
-            Err(_) => return Err(NodeXError{})
+            Err(_) => return Err(NodeXError{}),
         };
 
-        match res.json::<DIDResolutionResponse>().await {
-            Ok(v) => Ok(v),
-            Err(_) => Err(NodeXError{})
+        let recovery_key = match Self::recovery_key(&keyring) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+
+        let payload = match OperationPayload::did_deactivate_payload(&DIDDeactivateRequest {
+            did_suffix,
+            recovery_key,
+        }) {
+            Ok(v) => v,
+            Err(_) => return Err(NodeXError{}),
+        };
+
+        if self.enqueue(&did, OperationType::Deactivate, &payload, None).await.is_err() {
+            return Err(NodeXError{})
+        }
+
+        // NOTE: save context
+        if keyring.mark_deactivated().is_err() {
+            return Err(NodeXError{})
+        }
+
+        // NOTE: drop the document cached before the operation
+        did_resolver().invalidate(&did);
+
+        self.find_identifier(&did).await
+    }
+
+    fn commits_to(key: &Secp256k1, commitment: &Option<String>) -> bool {
+        match key.to_jwk(false).and_then(|v| OperationPayload::commitment(&v)) {
+            Ok(v) => commitment.as_ref() == Some(&v),
+            Err(_) => false,
         }
     }
 
+    // NOTE: a DID is created with the commitment of the recovery key as its update commitment and the one of the first
+    //       update key as its recovery commitment. The first update reveals the recovery key and the first recovery (or
+    //       deactivation) the first update key, every operation then commits to the key derived at the next index
+    fn update_key(keyring: &keyring::mnemonic::MnemonicKeyring) -> Secp256k1 {
+        match keyring.get_update_key_index() {
+            0 => keyring.get_recovery_key_pair(),
+            _ => keyring.get_update_key_pair(),
+        }
+    }
+
+    fn recovery_key(keyring: &keyring::mnemonic::MnemonicKeyring) -> Result<Secp256k1, NodeXError> {
+        match keyring.get_recovery_key_index() {
+            0 => keyring.derive_update_key_pair(0),
+            _ => Ok(keyring.get_recovery_key_pair()),
+        }
+    }
+
+    // NOTE: an operation which can not be sent right now is retried in the background, so the key ring follows it at once
+    //       the keys it replaces are kept until it is published and restored if the node rejects it
+    async fn enqueue(&self, did: &str, r#type: OperationType, payload: &str, update_commitment: Option<String>) -> Result<(), NodeXError> {
+        let queue = operation_queue();
+
+        let operation = {
+            let _guard = queue.lock().await;
+            let operation = queue.enqueue(did, r#type, payload, update_commitment);
+
+            // NOTE: an operation whose keys could not be restored is never sent
+            let saved = app_config().inner.lock().unwrap().save_checkpoint(&operation.id);
+
+            if saved.is_err() {
+                queue.failed(&operation.id, "unable to save the key checkpoint");
+                return Err(NodeXError{})
+            }
+
+            operation
+        };
+
+        self.process_operations().await;
+
+        match queue.find(&operation.id) {
+            Some(v) if v.status == OperationStatus::Failed => Err(NodeXError{}),
+            Some(_) => Ok(()),
+            None => Err(NodeXError{}),
+        }
+    }
+
+    async fn submit(&self, operation: &QueuedOperation) -> Option<reqwest::Response> {
+        let queue = operation_queue();
+
+        match self.http_client.post("/api/v1/operations", &operation.payload).await {
+            Ok(res) if res.status().is_success() => {
+                queue.submitted(&operation.id);
+
+                Some(res)
+            },
+            // NOTE: the node rejects the operation itself, sending it again does not help
+            Ok(res) if res.status().is_client_error() && res.status() != StatusCode::REQUEST_TIMEOUT && res.status() != StatusCode::TOO_MANY_REQUESTS => {
+                log::error!("the {:?} operation of {} is rejected: {}", operation.r#type, &operation.did, res.status());
+
+                for blocked in queue.failed(&operation.id, &res.status().to_string()) {
+                    log::error!("the {:?} operation of {} is dropped with it", blocked.r#type, &blocked.did);
+                }
+
+                Self::roll_back(operation).await;
+
+                None
+            },
+            Ok(res) => {
+                queue.retry(&operation.id, &res.status().to_string());
+
+                None
+            },
+            Err(_) => {
+                log::info!("unable to send the {:?} operation of {}, it is retried later", operation.r#type, &operation.did);
+                queue.retry(&operation.id, "unable to reach the node");
+
+                None
+            },
+        }
+    }
+
+    // NOTE: restore the keys the rejected operation replaced, the keys of the operations dropped with it are discarded too
+    async fn roll_back(operation: &QueuedOperation) {
+        let profile = match app_config().inner.lock().unwrap().find_profile(&operation.did) {
+            Some(v) => v,
+            None => return,
+        };
+
+        with_profile(&profile, async {
+            let checkpoint = match app_config().inner.lock().unwrap().take_checkpoint(&operation.id) {
+                Some(v) => v,
+                None => return,
+            };
+
+            let did = match checkpoint.did {
+                Some(v) => v,
+                None => return,
+            };
+
+            // NOTE: operations never replace the mnemonic, the keys of the checkpoint are derived from it again
+            let mut keyring = match keyring::mnemonic::MnemonicKeyring::load_keyring() {
+                Ok(v) => v,
+                Err(_) => {
+                    log::error!("unable to restore the keys of {}", &did);
+                    return
+                },
+            };
+
+            if keyring.restore(&did, checkpoint.sign_index, checkpoint.update_index, checkpoint.recovery_index).is_err() {
+                log::error!("unable to restore the keys of {}", &did);
+                return
+            }
+
+            let config = app_config();
+            let mut config = config.inner.lock().unwrap();
+
+            if config.save_services(&checkpoint.services).is_err() {
+                log::error!("unable to restore the services of {}", &did);
+            }
+
+            if let Some(v) = checkpoint.rotated_at {
+                if config.save_rotated_at(v).is_err() {
+                    log::error!("unable to restore the rotation time of {}", &did);
+                }
+            }
+
+            log::warn!("restored the keys of {} replaced by the rejected {:?} operation", &did, operation.r#type);
+        }).await;
+
+        // NOTE: drop the document cached after the operation
+        did_resolver().invalidate(&operation.did);
+    }
+
+    pub async fn process_operations(&self) {
+        let queue = operation_queue();
+        let _guard = queue.lock().await;
+
+        for operation in queue.due(Utc::now().timestamp()) {
+            match operation.status {
+                OperationStatus::Pending => {
+                    self.submit(&operation).await;
+                },
+                OperationStatus::Submitted => {
+                    match self.resolver.resolve(&operation.did).await {
+                        Ok(v) if operation.is_published(&v.method_metadata) => {
+                            queue.published(&operation.id);
+
+                            let profile = app_config().inner.lock().unwrap().find_profile(&operation.did);
+
+                            if let Some(profile) = profile {
+                                let released = with_profile(&profile, async {
+                                    app_config().inner.lock().unwrap().release_checkpoint(&operation.id)
+                                }).await;
+
+                                if released.is_err() {
+                                    log::error!("unable to release the key checkpoint of {}", &operation.did);
+                                }
+                            }
+
+                            // NOTE: drop the document cached before the operation
+                            did_resolver().invalidate(&operation.did);
+                        },
+                        // NOTE: check again later
+                        _ => queue.submitted(&operation.id),
+                    }
+                },
+                _ => {},
+            }
+        }
+    }
+
+    pub fn find_operations(&self) -> Vec<QueuedOperation> {
+        operation_queue().list()
+    }
+
+    pub fn find_operation(&self, id: &str) -> Option<QueuedOperation> {
+        operation_queue().find(id)
+    }
+
     pub async fn transfer(&self, to_did: &str, messages: &Vec<Value>, metadata: &Value) -> Result<Value, NodeXError> {
         // NOTE: didcomm (enc)
         let container = match DIDCommEncryptedService::generate(to_did, &json!(messages), Some(metadata)).await {